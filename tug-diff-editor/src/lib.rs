@@ -26,6 +26,7 @@ use walkdir::WalkDir;
 use tug_record::helpers::CrosstermInput;
 use tug_record::{
     File, FileMode, RecordError, RecordState, Recorder, SelectedChanges, SelectedContents,
+    SelectionReport, SelectionSpec, SelectionSpecError,
 };
 
 /// Render a partial commit selector for use as a difftool or mergetool.
@@ -69,6 +70,12 @@ pub struct Opts {
     /// Write the resolved merge conflicts to this file.
     #[clap(short = 'o', long = "output", conflicts_with("dir_diff"))]
     pub output: Option<PathBuf>,
+
+    /// Select changes according to this spec instead of interactively. May be
+    /// passed multiple times; each value may also contain several entries
+    /// separated by newlines. See `tug_record::SelectionSpec` for the format.
+    #[clap(long = "select", value_name = "SPEC", conflicts_with("read_only"))]
+    pub select: Vec<String>,
 }
 
 #[derive(Debug, Error)]
@@ -117,6 +124,9 @@ pub enum Error {
 
     #[error("recording changes: {source}")]
     Record { source: RecordError },
+
+    #[error("parsing selection spec: {source}")]
+    SelectionSpec { source: SelectionSpecError },
 }
 
 /// Result type alias.
@@ -343,6 +353,7 @@ pub fn process_opts(filesystem: &dyn Filesystem, opts: &Opts) -> Result<DiffCont
             output: _,
             read_only: _,
             dry_run: _,
+            select: _,
         } => {
             let files = vec![render::create_file(
                 filesystem,
@@ -365,6 +376,7 @@ pub fn process_opts(filesystem: &dyn Filesystem, opts: &Opts) -> Result<DiffCont
             output: _,
             read_only: _,
             dry_run: _,
+            select: _,
         } => {
             let display_paths = filesystem.read_dir_diff_paths(left, right)?;
             let mut files = Vec::new();
@@ -391,6 +403,7 @@ pub fn process_opts(filesystem: &dyn Filesystem, opts: &Opts) -> Result<DiffCont
             output: Some(output),
             read_only: _,
             dry_run: _,
+            select: _,
         } => {
             let files = vec![render::create_merge_file(
                 filesystem,
@@ -413,6 +426,7 @@ pub fn process_opts(filesystem: &dyn Filesystem, opts: &Opts) -> Result<DiffCont
             output: None,
             read_only: _,
             dry_run: _,
+            select: _,
         } => {
             unreachable!("--output is required when --base is provided");
         }
@@ -425,6 +439,7 @@ pub fn process_opts(filesystem: &dyn Filesystem, opts: &Opts) -> Result<DiffCont
             output: _,
             read_only: _,
             dry_run: _,
+            select: _,
         } => {
             unimplemented!("--base cannot be used with --dir-diff");
         }
@@ -436,6 +451,7 @@ fn print_dry_run(write_root: &Path, state: RecordState) {
     let RecordState {
         is_read_only: _,
         commits: _,
+        comments: _,
         files,
     } = state;
     for file in files {
@@ -503,6 +519,7 @@ pub fn apply_changes(
     let RecordState {
         is_read_only,
         commits: _,
+        comments: _,
         files,
     } = state;
     if is_read_only {
@@ -556,11 +573,23 @@ pub fn run(opts: Opts) -> Result<()> {
     let state = RecordState {
         is_read_only: opts.read_only,
         commits: Default::default(),
+        comments: Default::default(),
         files,
     };
-    let mut input = CrosstermInput;
-    let recorder = Recorder::new(state, &mut input);
-    match recorder.run() {
+    let result = if opts.select.is_empty() {
+        let mut input = CrosstermInput;
+        Recorder::new(state, &mut input).run()
+    } else {
+        let spec = SelectionSpec::parse(&opts.select.join("\n"))
+            .map_err(|source| Error::SelectionSpec { source })?;
+        let (state, report) = spec.apply(state);
+        let SelectionReport { unmatched_entries } = report;
+        for entry in unmatched_entries {
+            eprintln!("warning: selection spec entry matched no changes: {entry}");
+        }
+        Ok(state)
+    };
+    match result {
         Ok(state) => {
             if opts.dry_run {
                 print_dry_run(&write_root, state);
@@ -716,6 +745,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
         assert_debug_snapshot!(files, @r###"
@@ -733,11 +763,13 @@ qux2
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Removed,
                                 line: "foo\n",
                             },
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "qux1\n",
                             },
@@ -753,11 +785,13 @@ qux2
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Removed,
                                 line: "bar\n",
                             },
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "qux2\n",
                             },
@@ -775,6 +809,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -837,6 +872,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
 
@@ -846,6 +882,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -900,6 +937,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
         assert_debug_snapshot!(files, @r###"
@@ -913,6 +951,7 @@ qux2
                 sections: [
                     FileMode {
                         is_checked: false,
+                        commit_idx: 0,
                         mode: Unix(
                             33188,
                         ),
@@ -921,6 +960,7 @@ qux2
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "right\n",
                             },
@@ -938,6 +978,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -982,6 +1023,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
         assert_debug_snapshot!(files, @r###"
@@ -997,12 +1039,14 @@ qux2
                 sections: [
                     FileMode {
                         is_checked: false,
+                        commit_idx: 0,
                         mode: Absent,
                     },
                     Changed {
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Removed,
                                 line: "left\n",
                             },
@@ -1020,6 +1064,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -1062,6 +1107,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         );
         insta::assert_debug_snapshot!(result, @r###"
@@ -1096,6 +1142,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
 
@@ -1105,6 +1152,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -1160,6 +1208,7 @@ qux2
                 output: None,
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
             },
         )?;
 
@@ -1169,6 +1218,7 @@ qux2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -1244,6 +1294,7 @@ Hello world 4
                 right: "right".into(),
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
                 base: Some("base".into()),
                 output: Some("output".into()),
             },
@@ -1269,16 +1320,19 @@ Hello world 4
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "Hello world L\n",
                             },
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Removed,
                                 line: "Hello world 3\n",
                             },
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "Hello world R\n",
                             },
@@ -1301,6 +1355,7 @@ Hello world 4
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files,
             },
         )?;
@@ -1379,6 +1434,7 @@ Hello world 2
                 right: "right".into(),
                 read_only: false,
                 dry_run: false,
+                select: Vec::new(),
                 base: None,
                 output: None,
             },
@@ -1394,6 +1450,7 @@ Hello world 2
                 sections: [
                     FileMode {
                         is_checked: false,
+                        commit_idx: 0,
                         mode: Unix(
                             33188,
                         ),
@@ -1402,11 +1459,13 @@ Hello world 2
                         lines: [
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "Hello world 1\n",
                             },
                             SectionChangedLine {
                                 is_checked: false,
+                                commit_idx: 0,
                                 change_type: Added,
                                 line: "Hello world 2\n",
                             },
@@ -1424,6 +1483,7 @@ Hello world 2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files: files.clone(),
             },
         )?;
@@ -1444,6 +1504,7 @@ Hello world 2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files: files.clone(),
             },
         )?;
@@ -1478,6 +1539,7 @@ Hello world 2
             RecordState {
                 is_read_only: false,
                 commits: Default::default(),
+                comments: Default::default(),
                 files: files.clone(),
             },
         )?;
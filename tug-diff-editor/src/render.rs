@@ -15,6 +15,7 @@ fn make_section_changed_lines(
         .split_inclusive('\n')
         .map(|line| SectionChangedLine {
             is_checked: false,
+            commit_idx: 0,
             change_type,
             line: Cow::Owned(line.to_owned()),
         })
@@ -41,6 +42,7 @@ pub fn create_file(
     if left_file_mode != right_file_mode {
         sections.push(Section::FileMode {
             is_checked: false,
+            commit_idx: 0,
             mode: right_file_mode,
         });
     }
@@ -61,6 +63,7 @@ pub fn create_file(
         (FileContents::Absent, FileContents::Binary { hash, num_bytes }) => {
             sections.push(Section::Binary {
                 is_checked: false,
+                commit_idx: 0,
                 old_description: None,
                 new_description: Some(Cow::Owned(make_binary_description(&hash, num_bytes))),
             })
@@ -113,6 +116,7 @@ pub fn create_file(
             },
         ) => sections.push(Section::Binary {
             is_checked: false,
+            commit_idx: 0,
             old_description: Some(Cow::Owned(make_binary_description(
                 &old_hash,
                 old_num_bytes,
@@ -126,6 +130,7 @@ pub fn create_file(
         (FileContents::Binary { hash, num_bytes }, FileContents::Absent) => {
             sections.push(Section::Binary {
                 is_checked: false,
+                commit_idx: 0,
                 old_description: Some(Cow::Owned(make_binary_description(&hash, num_bytes))),
                 new_description: None,
             })
@@ -242,6 +247,7 @@ fn create_diff(old_contents: &str, new_contents: &str) -> Vec<Section<'static>>
                 diffy::Line::Delete(line) => {
                     let line = SectionChangedLine {
                         is_checked: false,
+                        commit_idx: 0,
                         change_type: ChangeType::Removed,
                         line: Cow::Owned((*line).to_owned()),
                     };
@@ -257,6 +263,7 @@ fn create_diff(old_contents: &str, new_contents: &str) -> Vec<Section<'static>>
                 diffy::Line::Insert(line) => {
                     let line = SectionChangedLine {
                         is_checked: false,
+                        commit_idx: 0,
                         change_type: ChangeType::Added,
                         line: Cow::Owned((*line).to_owned()),
                     };
@@ -455,6 +462,7 @@ fn create_merge(
                         )
                         .map(|(line, change_type)| SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type,
                             line,
                         })
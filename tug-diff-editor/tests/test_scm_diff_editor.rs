@@ -5,7 +5,7 @@ use maplit::btreemap;
 
 use tug_diff_editor::testing::{file_info, select_all, TestFilesystem};
 use tug_diff_editor::{apply_changes, process_opts, DiffContext, Opts, Result};
-use tug_record::{RecordState, Section};
+use tug_record::{RecordState, Section, SelectionSpec};
 
 #[test]
 fn test_diff() -> Result<()> {
@@ -36,6 +36,7 @@ qux2
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
     assert_debug_snapshot!(files, @r###"
@@ -53,11 +54,13 @@ qux2
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Removed,
                             line: "foo\n",
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "qux1\n",
                         },
@@ -73,11 +76,13 @@ qux2
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Removed,
                             line: "bar\n",
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "qux2\n",
                         },
@@ -95,6 +100,7 @@ qux2
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -157,6 +163,7 @@ qux2
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
 
@@ -166,6 +173,7 @@ qux2
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -220,6 +228,7 @@ fn test_diff_absent_left() -> Result<()> {
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
     assert_debug_snapshot!(files, @r###"
@@ -233,6 +242,7 @@ fn test_diff_absent_left() -> Result<()> {
             sections: [
                 FileMode {
                     is_checked: false,
+                    commit_idx: 0,
                     mode: Unix(
                         33188,
                     ),
@@ -241,6 +251,7 @@ fn test_diff_absent_left() -> Result<()> {
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "right\n",
                         },
@@ -258,6 +269,7 @@ fn test_diff_absent_left() -> Result<()> {
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -302,6 +314,7 @@ fn test_diff_absent_right() -> Result<()> {
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
     assert_debug_snapshot!(files, @r###"
@@ -317,12 +330,14 @@ fn test_diff_absent_right() -> Result<()> {
             sections: [
                 FileMode {
                     is_checked: false,
+                    commit_idx: 0,
                     mode: Absent,
                 },
                 Changed {
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Removed,
                             line: "left\n",
                         },
@@ -340,6 +355,7 @@ fn test_diff_absent_right() -> Result<()> {
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -382,6 +398,7 @@ fn test_reject_diff_non_files() -> Result<()> {
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     );
     insta::assert_debug_snapshot!(result, @r###"
@@ -416,6 +433,7 @@ fn test_diff_files_in_subdirectories() -> Result<()> {
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
 
@@ -425,6 +443,7 @@ fn test_diff_files_in_subdirectories() -> Result<()> {
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -480,6 +499,7 @@ fn test_dir_diff_no_changes() -> Result<()> {
             output: None,
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
         },
     )?;
 
@@ -489,6 +509,7 @@ fn test_dir_diff_no_changes() -> Result<()> {
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -564,6 +585,7 @@ Hello world 4
             right: "right".into(),
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
             base: Some("base".into()),
             output: Some("output".into()),
         },
@@ -589,16 +611,19 @@ Hello world 4
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "Hello world L\n",
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Removed,
                             line: "Hello world 3\n",
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "Hello world R\n",
                         },
@@ -621,6 +646,7 @@ Hello world 4
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files,
         },
     )?;
@@ -699,6 +725,7 @@ Hello world 2
             right: "right".into(),
             read_only: false,
             dry_run: false,
+            select: Vec::new(),
             base: None,
             output: None,
         },
@@ -714,6 +741,7 @@ Hello world 2
             sections: [
                 FileMode {
                     is_checked: false,
+                    commit_idx: 0,
                     mode: Unix(
                         33188,
                     ),
@@ -722,11 +750,13 @@ Hello world 2
                     lines: [
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "Hello world 1\n",
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: Added,
                             line: "Hello world 2\n",
                         },
@@ -744,6 +774,7 @@ Hello world 2
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files: files.clone(),
         },
     )?;
@@ -764,6 +795,7 @@ Hello world 2
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files: files.clone(),
         },
     )?;
@@ -798,6 +830,7 @@ Hello world 2
         RecordState {
             is_read_only: false,
             commits: Default::default(),
+            comments: Default::default(),
             files: files.clone(),
         },
     )?;
@@ -823,3 +856,93 @@ Hello world 2
 
     Ok(())
 }
+
+#[test]
+fn test_select_spec() -> Result<()> {
+    let mut filesystem = TestFilesystem::new(btreemap! {
+        PathBuf::from("left") => file_info("\
+foo
+common1
+common2
+bar
+"),
+        PathBuf::from("right") => file_info("\
+qux1
+common1
+common2
+qux2
+"),
+    });
+    let DiffContext { files, write_root } = process_opts(
+        &filesystem,
+        &Opts {
+            dir_diff: false,
+            left: PathBuf::from("left"),
+            right: PathBuf::from("right"),
+            base: None,
+            output: None,
+            read_only: false,
+            dry_run: false,
+            select: Vec::new(),
+        },
+    )?;
+
+    let spec = SelectionSpec::parse(
+        "\
+# Comments and blank lines are ignored.
+
+right additions re:^qux\\d$
+old:4
+*.lock
+right new:1-2 deletions
+",
+    )
+    .unwrap();
+    let (state, report) = spec.apply(RecordState {
+        is_read_only: false,
+        commits: Default::default(),
+        comments: Default::default(),
+        files,
+    });
+    assert_debug_snapshot!(report, @r###"
+    SelectionReport {
+        unmatched_entries: [
+            "*.lock",
+            "right new:1-2 deletions",
+        ],
+    }
+    "###);
+
+    apply_changes(&mut filesystem, &write_root, state)?;
+    insta::assert_debug_snapshot!(filesystem, @r###"
+    TestFilesystem {
+        files: {
+            "left": FileInfo {
+                file_mode: Unix(
+                    33188,
+                ),
+                contents: Text {
+                    contents: "foo\ncommon1\ncommon2\nbar\n",
+                    hash: "abc123",
+                    num_bytes: 24,
+                },
+            },
+            "right": FileInfo {
+                file_mode: Unix(
+                    33188,
+                ),
+                contents: Text {
+                    contents: "foo\nqux1\ncommon1\ncommon2\nqux2\n",
+                    hash: "abc123",
+                    num_bytes: 30,
+                },
+            },
+        },
+        dirs: {
+            "",
+        },
+    }
+    "###);
+
+    Ok(())
+}
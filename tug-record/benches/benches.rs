@@ -1,55 +1,108 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, path::PathBuf};
 
-use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Bencher, Criterion};
 
 use tug_record::{
     helpers::TestingInput, ChangeType, Event, File, FileMode, RecordState, Recorder, Section,
     SectionChangedLine,
 };
 
+/// A file with a single changed section, in which `num_lines / 2` lines are
+/// removed and as many are added.
+fn make_file(path: String, num_lines: usize) -> File<'static> {
+    let make_line = |line_idx: usize, change_type: ChangeType| SectionChangedLine {
+        line: Cow::Owned(format!("let x{line_idx} = foo({line_idx}, bar, baz);")),
+        is_checked: false,
+        commit_idx: 0,
+        change_type,
+    };
+    let num_removed = num_lines / 2;
+    let removed_lines = (0..num_removed).map(|line_idx| make_line(line_idx, ChangeType::Removed));
+    let added_lines =
+        (num_removed..num_lines).map(|line_idx| make_line(line_idx, ChangeType::Added));
+    File {
+        old_path: None,
+        path: Cow::Owned(PathBuf::from(path)),
+        file_mode: FileMode::FILE_DEFAULT,
+        sections: vec![Section::Changed {
+            lines: removed_lines.chain(added_lines).collect(),
+        }],
+    }
+}
+
+/// A diff of `num_files` files with `num_lines_per_file` changed lines each.
+fn make_record_state(num_files: usize, num_lines_per_file: usize) -> RecordState<'static> {
+    RecordState {
+        is_read_only: false,
+        commits: Default::default(),
+        comments: Default::default(),
+        // The files have no extension, so that the benchmarks measure the
+        // layout rather than the syntax highlighting.
+        files: (0..num_files)
+            .map(|file_idx| make_file(format!("generated/file{file_idx}"), num_lines_per_file))
+            .collect(),
+    }
+}
+
+/// Run the recorder on `record_state` with the given events, in an 80x24
+/// terminal. Each event is handled in a separate frame.
+fn bench_events(b: &mut Bencher, record_state: &RecordState<'static>, events: &[Event]) {
+    b.iter_batched(
+        || {
+            let input = TestingInput::new(80, 24, events.to_vec());
+            (record_state.clone(), input)
+        },
+        |(record_state, mut input)| {
+            let recorder = Recorder::new(record_state, &mut input);
+            recorder.run()
+        },
+        BatchSize::PerIteration,
+    )
+}
+
 fn bench_record(c: &mut Criterion) {
     c.bench_function("tug_record: toggle line", |b| {
-        let before_line = SectionChangedLine {
-            line: Cow::Borrowed("foo"),
-            is_checked: false,
-            change_type: ChangeType::Removed,
-        };
-        let after_line = SectionChangedLine {
-            line: Cow::Borrowed("foo"),
-            is_checked: false,
-            change_type: ChangeType::Added,
-        };
-        let record_state = RecordState {
-            is_read_only: false,
-            commits: Default::default(),
-            files: vec![File {
-                old_path: None,
-                path: Cow::Borrowed(Path::new("foo")),
-                file_mode: FileMode::FILE_DEFAULT,
-                sections: vec![Section::Changed {
-                    lines: [vec![before_line; 1000], vec![after_line; 1000]].concat(),
-                }],
-            }],
-        };
-        let mut input = TestingInput::new(
-            80,
-            24,
-            [Event::ToggleItem, Event::ToggleItem, Event::QuitAccept],
-        );
-        b.iter_batched(
-            || record_state.clone(),
-            |record_state| {
-                let recorder = Recorder::new(record_state, &mut input);
-                recorder.run()
-            },
-            BatchSize::PerIteration,
+        let record_state = make_record_state(1, 2000);
+        bench_events(
+            b,
+            &record_state,
+            &[Event::ToggleItem, Event::ToggleItem, Event::QuitAccept],
         )
     });
 }
 
+fn bench_large_diff(c: &mut Criterion) {
+    let mut events = vec![Event::ExpandItem];
+    events.extend(std::iter::repeat_n(Event::FocusNext, 20));
+    events.extend(std::iter::repeat_n(Event::FocusNextPage, 10));
+    events.push(Event::QuitAccept);
+
+    c.bench_function("tug_record: move through 100k-line file", |b| {
+        let record_state = make_record_state(1, 100_000);
+        bench_events(b, &record_state, &events)
+    });
+
+    c.bench_function("tug_record: move through 100k-line file, wrapped", |b| {
+        let record_state = make_record_state(1, 100_000);
+        let events: Vec<Event> = [Event::ToggleLineWrap]
+            .into_iter()
+            .chain(events.iter().cloned())
+            .collect();
+        bench_events(b, &record_state, &events)
+    });
+
+    c.bench_function("tug_record: page through 1000 files of 100 lines", |b| {
+        let record_state = make_record_state(1000, 100);
+        let mut events = vec![Event::ExpandAll];
+        events.extend(std::iter::repeat_n(Event::FocusNextPage, 30));
+        events.push(Event::QuitAccept);
+        bench_events(b, &record_state, &events)
+    });
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().sample_size(10);
-    targets = bench_record,
+    targets = bench_record, bench_large_diff,
 );
 criterion_main!(benches);
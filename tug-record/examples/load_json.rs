@@ -32,6 +32,7 @@ fn main() {
             let RecordState {
                 is_read_only: _,
                 commits: _,
+                comments: _,
                 files,
             } = result;
             for file in files {
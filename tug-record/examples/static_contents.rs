@@ -25,22 +25,26 @@ fn main() {
                     lines: vec![
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Removed,
                             line: Cow::Borrowed("before text 1\n"),
                         },
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Removed,
                             line: Cow::Borrowed("before text 2\n"),
                         },
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Added,
 
                             line: Cow::Borrowed("after text 1\n"),
                         },
                         SectionChangedLine {
                             is_checked: false,
+                            commit_idx: 0,
                             change_type: ChangeType::Added,
                             line: Cow::Borrowed("after text 2\n"),
                         },
@@ -66,21 +70,25 @@ fn main() {
                     lines: vec![
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Removed,
                             line: Cow::Borrowed("before text 1\n"),
                         },
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Removed,
                             line: Cow::Borrowed("before text 2\n"),
                         },
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Added,
                             line: Cow::Borrowed("after text 1\n"),
                         },
                         SectionChangedLine {
                             is_checked: true,
+                            commit_idx: 0,
                             change_type: ChangeType::Added,
                             line: Cow::Borrowed("after text 2\n"),
                         },
@@ -95,16 +103,18 @@ fn main() {
     let record_state = RecordState {
         is_read_only: false,
         commits: Default::default(),
+        comments: Default::default(),
         files,
     };
     let mut input = CrosstermInput;
-    let recorder = Recorder::new(record_state, &mut input);
+    let recorder = Recorder::new(record_state, &mut input).with_mouse_capture(true);
     let result = recorder.run();
     match result {
         Ok(result) => {
             let RecordState {
                 is_read_only: _,
                 commits: _,
+                comments: _,
                 files,
             } = result;
             for file in files {
@@ -11,3 +11,19 @@ pub const DUMP_UI_STATE_FILENAME: &str = "tug_record_ui_state.json";
 /// Render a debug pane over the file. Only works if compiled with the `debug`
 /// feature.
 pub const ENV_VAR_DEBUG_UI: &str = "TUG_RECORD_DEBUG_UI";
+
+/// Disable colors in the UI if set to a non-empty value. See
+/// <https://no-color.org>.
+pub const ENV_VAR_NO_COLOR: &str = "NO_COLOR";
+
+/// The editor used to edit commit messages outside of the UI. Takes
+/// precedence over [`ENV_VAR_EDITOR`].
+pub const ENV_VAR_VISUAL: &str = "VISUAL";
+
+/// The editor used to edit commit messages outside of the UI, if
+/// [`ENV_VAR_VISUAL`] isn't set.
+pub const ENV_VAR_EDITOR: &str = "EDITOR";
+
+/// The editor used if neither [`ENV_VAR_VISUAL`] nor [`ENV_VAR_EDITOR`] is
+/// set.
+pub const DEFAULT_EDITOR: &str = "vi";
@@ -1,12 +1,23 @@
 //! Helper functions for rendering UI components.
 
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::{collections::VecDeque, time::Duration};
 
 use crate::{
+    consts::{DEFAULT_EDITOR, ENV_VAR_EDITOR, ENV_VAR_VISUAL},
     ui::{event, input::RecordInput, terminal::TerminalKind},
     RecordError,
 };
 
+/// Appended to a commit message opened in an external editor.
+const COMMIT_MESSAGE_HELP: &str = "\
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message cancels the edit.
+";
+
 /// Generate a one-line description of a binary file change.
 pub fn make_binary_description(hash: &str, num_bytes: u64) -> String {
     format!("{hash} ({num_bytes} bytes)")
@@ -14,8 +25,9 @@ pub fn make_binary_description(hash: &str, num_bytes: u64) -> String {
 
 /// Reads input events from the terminal using `crossterm`.
 ///
-/// Its default implementation of `edit_commit_message` returns the provided
-/// message unchanged.
+/// Its implementations of `edit_commit_message` and `edit_added_lines` open
+/// the text in the editor named by the `VISUAL` or `EDITOR` environment
+/// variables, falling back to `vi`.
 pub struct CrosstermInput;
 
 impl RecordInput for CrosstermInput {
@@ -37,8 +49,109 @@ impl RecordInput for CrosstermInput {
         Ok(events)
     }
 
+    fn next_events_timeout(&mut self, timeout: Duration) -> Result<Vec<event::Event>, RecordError> {
+        if crossterm::event::poll(timeout).map_err(RecordError::ReadInput)? {
+            self.next_events()
+        } else {
+            Ok(vec![event::Event::Tick])
+        }
+    }
+
     fn edit_commit_message(&mut self, message: &str) -> Result<String, RecordError> {
-        Ok(message.to_owned())
+        let contents = format!("{}\n\n{COMMIT_MESSAGE_HELP}", message.trim_end());
+        let edited_contents = edit_in_external_editor(&contents, "COMMIT_EDITMSG")?;
+
+        let lines: Vec<&str> = edited_contents
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(str::trim_end)
+            .collect();
+        let mut edited_message = lines.join("\n").trim_matches('\n').to_owned();
+        if edited_message.is_empty() {
+            return Err(RecordError::Cancelled);
+        }
+        if message.ends_with('\n') {
+            edited_message.push('\n');
+        }
+        Ok(edited_message)
+    }
+
+    fn edit_added_lines(&mut self, lines: &str) -> Result<String, RecordError> {
+        // As when editing a hunk in `git add --patch`, emptying the file
+        // cancels the edit.
+        let edited_lines = edit_in_external_editor(lines, "ADDED_LINES")?;
+        if edited_lines.is_empty() {
+            return Err(RecordError::Cancelled);
+        }
+        Ok(edited_lines)
+    }
+}
+
+/// Write `contents` to a temporary file whose name ends with `file_name`, open
+/// it in the user's editor, and return the contents of the file once the
+/// editor exits. If the editor exits with an error, such as with `:cq` in Vim,
+/// the edit is cancelled.
+fn edit_in_external_editor(contents: &str, file_name: &str) -> Result<String, RecordError> {
+    let path = create_temp_file(contents, file_name).map_err(RecordError::WriteFile)?;
+    let result =
+        run_editor(&path).and_then(|()| fs::read_to_string(&path).map_err(RecordError::ReadFile));
+    // The file is only a scratch copy, so failing to remove it isn't worth
+    // failing the edit over.
+    let _ = fs::remove_file(&path);
+    result
+}
+
+fn create_temp_file(contents: &str, file_name: &str) -> io::Result<PathBuf> {
+    let temp_dir = std::env::temp_dir();
+    let mut attempt = 0;
+    loop {
+        let path = temp_dir.join(format!(
+            "tug-record-{}-{attempt}-{file_name}",
+            std::process::id()
+        ));
+        // Don't follow or overwrite a file someone else created at the same
+        // path.
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn run_editor(path: &Path) -> Result<(), RecordError> {
+    let editor = [ENV_VAR_VISUAL, ENV_VAR_EDITOR]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_owned());
+
+    // The editor may include arguments, such as `code --wait`, so let the
+    // shell split it, as Git does.
+    let mut command = if cfg!(unix) {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{editor} \"$@\""))
+            .arg(&editor)
+            .arg(path);
+        command
+    } else {
+        let mut words = editor.split_whitespace();
+        let mut command = Command::new(words.next().unwrap_or(DEFAULT_EDITOR));
+        command.args(words).arg(path);
+        command
+    };
+    let status = command.status().map_err(RecordError::RunEditor)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(RecordError::Cancelled)
     }
 }
 
@@ -55,6 +168,10 @@ pub struct TestingInput {
 
     /// Commit messages to use when the commit editor is opened.
     pub commit_messages: VecDeque<String>,
+
+    /// Replacement lines to use when the added lines of a section are edited,
+    /// or `None` to cancel the edit.
+    pub edited_added_lines: VecDeque<Option<String>>,
 }
 
 impl TestingInput {
@@ -69,6 +186,7 @@ impl TestingInput {
             height,
             events: Box::new(events.into_iter()),
             commit_messages: Default::default(),
+            edited_added_lines: Default::default(),
         }
     }
 }
@@ -80,6 +198,7 @@ impl RecordInput for TestingInput {
             height,
             events: _,
             commit_messages: _,
+            edited_added_lines: _,
         } = self;
         TerminalKind::Testing {
             width: *width,
@@ -96,4 +215,50 @@ impl RecordInput for TestingInput {
             .pop_front()
             .ok_or_else(|| RecordError::Other("No more commit messages available".to_string()))
     }
+
+    fn edit_added_lines(&mut self, _lines: &str) -> Result<String, RecordError> {
+        self.edited_added_lines
+            .pop_front()
+            .ok_or_else(|| RecordError::Other("No more edited added lines available".to_string()))?
+            .ok_or(RecordError::Cancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_added_lines_in_external_editor() {
+        // The editor is run by the shell, with the path of the file appended.
+        std::env::set_var(ENV_VAR_VISUAL, "printf 'foo\\nqux\\n' >");
+        assert_eq!(
+            CrosstermInput.edit_added_lines("foo\nbar\n").unwrap(),
+            "foo\nqux\n"
+        );
+
+        // Saving the lines as they are leaves them unchanged.
+        std::env::set_var(ENV_VAR_VISUAL, "true");
+        assert_eq!(
+            CrosstermInput.edit_added_lines("foo\nbar\n").unwrap(),
+            "foo\nbar\n"
+        );
+
+        // Exiting with an error or emptying the file cancels the edit.
+        std::env::set_var(ENV_VAR_VISUAL, "false");
+        assert_matches!(
+            CrosstermInput.edit_added_lines("foo\n"),
+            Err(RecordError::Cancelled)
+        );
+        std::env::set_var(ENV_VAR_VISUAL, ": >");
+        assert_matches!(
+            CrosstermInput.edit_added_lines("foo\n"),
+            Err(RecordError::Cancelled)
+        );
+
+        std::env::remove_var(ENV_VAR_VISUAL);
+    }
 }
@@ -10,6 +10,7 @@
 #![allow(clippy::too_many_arguments)]
 
 mod render;
+mod select;
 mod types;
 mod ui;
 mod util;
@@ -17,9 +18,14 @@ mod util;
 pub mod consts;
 pub mod helpers;
 pub use types::{
-    ChangeType, Commit, File, FileMode, RecordError, RecordState, Section, SectionChangedLine,
-    SelectedChanges, SelectedContents, Tristate,
+    ChangeType, Commit, File, FileMode, RecordError, RecordState, ReviewComment,
+    ReviewCommentTarget, Section, SectionChangedLine, SelectedChanges, SelectedContents, Tristate,
 };
-pub use ui::{ recorder::Recorder };
+pub use ui::recorder::Recorder;
 
-pub use crate::ui::input::RecordInput;
+pub use crate::select::{SelectionReport, SelectionSpec, SelectionSpecError};
+pub use crate::ui::event::Event;
+pub use crate::ui::file_tree::FileTreeMode;
+pub use crate::ui::input::{RecordInput, TestingScreenshot};
+pub use crate::ui::keymap::{KeyChord, KeySequence, Keymap, KeymapConflict, KeymapError};
+pub use crate::ui::theme::Theme;
@@ -90,6 +90,11 @@ impl Rect {
         (self.end_x(), self.end_y())
     }
 
+    /// Whether the point `(x, y)` lies inside this `Rect`.
+    pub fn contains_point(self, x: isize, y: isize) -> bool {
+        self.x <= x && x < self.end_x() && self.y <= y && y < self.end_y()
+    }
+
     /// Whether this `Rect` has zero area.
     pub fn is_empty(self) -> bool {
         self.width == 0 || self.height == 0
@@ -335,6 +340,24 @@ pub(crate) struct DrawnRect {
 
 pub(crate) type DrawnRects<C> = HashMap<C, DrawnRect>;
 
+/// Whether the rows from `y` to `y + height` are close enough to a viewport
+/// showing `viewport_height` rows from `viewport_y` to be worth drawing: that
+/// is, whether they're visible, or would be after scrolling by up to one
+/// screen in either direction. Components further away than that can be
+/// skipped, which keeps drawing fast for very large diffs, while still
+/// tracking where the items reachable by paging were drawn.
+pub(crate) fn is_nearly_visible(
+    viewport_y: isize,
+    viewport_height: usize,
+    y: isize,
+    height: usize,
+) -> bool {
+    let viewport_height = viewport_height.unwrap_isize();
+    let start_y = viewport_y - viewport_height;
+    let end_y = viewport_y + 2 * viewport_height;
+    start_y < y + height.unwrap_isize() && y < end_y
+}
+
 /// Accessor to draw on the virtual canvas. The caller can draw anywhere on the
 /// canvas, but the actual renering will be restricted to this viewport. All
 /// draw calls are also tracked so that we know where each component was drawn
@@ -385,18 +408,24 @@ impl<'a, ComponentId: Clone + Debug + Eq + Hash> Viewport<'a, ComponentId> {
         self.mask().apply(self.rect())
     }
 
-    /// Render the provided component using the given `Frame`. Returns a mapping
+    /// Render the provided component into the given area of the `Frame`, which
+    /// must start at the top-left corner of the frame. Returns a mapping
     /// indicating where each component was drawn on the screen.
     pub fn render_top_level<C: Component>(
         frame: &mut Frame,
+        area: ratatui::layout::Rect,
         x: isize,
         y: isize,
         component: &C,
     ) -> DrawnRects<C::Id> {
+        debug_assert_eq!(
+            (area.x, area.y),
+            (0, 0),
+            "top-level components must be drawn at the top-left corner"
+        );
         let widget = TopLevelWidget { component, x, y };
-        let term_area = frame.area();
         let mut drawn_rects = Default::default();
-        frame.render_stateful_widget(widget, term_area, &mut drawn_rects);
+        frame.render_stateful_widget(widget, area, &mut drawn_rects);
         drawn_rects
     }
 
@@ -463,6 +492,31 @@ impl<'a, ComponentId: Clone + Debug + Eq + Hash> Viewport<'a, ComponentId> {
         trace_rect
     }
 
+    /// Record that the child component with the given ID takes up `rect`,
+    /// without drawing it, such as because it's far outside of the viewport.
+    /// Its own child components aren't recorded.
+    pub fn skip_component(&mut self, id: ComponentId, rect: Rect) -> Rect {
+        let timestamp = self.timestamp;
+        self.timestamp += 1;
+        let trace = self.current_trace_mut();
+        trace.merge_rect(rect);
+        trace.components.insert(id, DrawnRect { rect, timestamp });
+        rect
+    }
+
+    /// Record that `rect` is taken up by the current component, without
+    /// drawing anything there.
+    pub fn reserve_rect(&mut self, rect: Rect) {
+        self.current_trace_mut().merge_rect(rect);
+    }
+
+    /// Whether anything drawn in the rows from `y` to `y + height` would be
+    /// visible, or close enough to the viewport to be worth drawing. See
+    /// [`is_nearly_visible`].
+    pub fn is_nearly_visible(&self, y: isize, height: usize) -> bool {
+        is_nearly_visible(self.rect.y, self.rect.height, y, height)
+    }
+
     /// Draw a `Span` directly to the screen at the given `(x, y)` location.
     pub fn draw_span(&mut self, x: isize, y: isize, span: &Span) -> Rect {
         let Span { content, style } = span;
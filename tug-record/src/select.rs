@@ -0,0 +1,371 @@
+//! Selecting changes without the UI, according to a declarative spec.
+
+use std::iter;
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+use thiserror::Error;
+
+use crate::types::{ChangeType, File, FileMode, RecordState, Section, SectionChangedLine};
+use crate::ui::filter::glob_matches_path;
+use crate::ui::{move_file_mode_for_changed_lines, normalize_commits};
+
+/// A description of which changes to select, for use by scripts which need
+/// the same selection as the UI would produce without running it.
+///
+/// The spec consists of one entry per line. Blank lines and lines starting
+/// with `#` are ignored. Each entry is a list of space-separated terms:
+///
+/// - `additions` and `deletions` match added and removed lines. `deletions`
+///   also matches the deletion of a file.
+/// - `old:N` and `old:A-B` match removed lines by their line number in the
+///   old version of the file.
+/// - `new:N` and `new:A-B` match added lines by their line number in the new
+///   version of the file.
+/// - `re:PATTERN` matches changed lines whose contents match the regular
+///   expression `PATTERN`, which extends to the end of the entry and so may
+///   contain spaces. It must be the last term of the entry. Requires the
+///   `regex` feature.
+/// - Any other term is a glob pattern matched against the path of the file,
+///   as in the file filter of the UI: `*` and `?` don't match `/`, `**`
+///   matches across directories, and patterns without a `/` are matched
+///   against the file name only.
+///
+/// An entry applies to the files matching any of its globs (or every file, if
+/// it has none), and selects the changed lines which match all of its other
+/// terms. An entry with only globs selects the files as a whole, including
+/// file mode changes and binary contents.
+///
+/// Selecting a change assigns it to the first commit, just like checking it in
+/// the UI; changes which aren't matched by any entry are left as they were.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::path::Path;
+/// use tug_record::{
+///     ChangeType, File, FileMode, RecordState, Section, SectionChangedLine, SelectionSpec,
+/// };
+///
+/// let line = |change_type, line: &'static str| SectionChangedLine {
+///     is_checked: false,
+///     commit_idx: 0,
+///     change_type,
+///     line: Cow::Borrowed(line),
+/// };
+/// let state = RecordState {
+///     is_read_only: false,
+///     commits: Vec::new(),
+///     comments: Vec::new(),
+///     files: vec![File {
+///         old_path: None,
+///         path: Cow::Borrowed(Path::new("src/main.rs")),
+///         file_mode: FileMode::FILE_DEFAULT,
+///         sections: vec![Section::Changed {
+///             lines: vec![
+///                 line(ChangeType::Removed, "println!(\"hello\");\n"),
+///                 line(ChangeType::Added, "dbg!(x);\n"),
+///                 line(ChangeType::Added, "println!(\"hello, world\");\n"),
+///             ],
+///         }],
+///     }],
+/// };
+///
+/// let spec = SelectionSpec::parse(
+///     "\
+/// ## Keep the debugging output out of the commit.
+/// src/**/*.rs new:2
+/// *.lock
+/// ",
+/// )
+/// .unwrap();
+/// let (state, report) = spec.apply(state);
+/// let Section::Changed { lines } = &state.files[0].sections[0] else {
+///     unreachable!()
+/// };
+/// let is_checked: Vec<bool> = lines.iter().map(|line| line.is_checked).collect();
+/// assert_eq!(is_checked, [false, false, true]);
+/// assert_eq!(report.unmatched_entries, ["*.lock"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SelectionSpec {
+    entries: Vec<SpecEntry>,
+}
+
+/// An error when parsing a [`SelectionSpec`].
+#[derive(Debug, Error)]
+pub enum SelectionSpecError {
+    /// A line range wasn't of the form `N` or `A-B`, with `1 <= A <= B`.
+    #[error("invalid line range {range:?} in selection entry {entry:?}")]
+    InvalidLineRange {
+        /// The entry containing the line range, as written.
+        entry: String,
+
+        /// The line range, as written.
+        range: String,
+    },
+
+    /// A regular expression couldn't be compiled.
+    #[cfg(feature = "regex")]
+    #[error("invalid regex in selection entry {entry:?}: {source}")]
+    InvalidRegex {
+        /// The entry containing the regular expression, as written.
+        entry: String,
+
+        /// The reason that the regular expression is invalid.
+        #[source]
+        source: regex::Error,
+    },
+
+    /// A regular expression was given, but the `regex` feature is disabled.
+    #[error("regex in selection entry {entry:?} requires the `regex` feature")]
+    RegexUnsupported {
+        /// The entry containing the regular expression, as written.
+        entry: String,
+    },
+}
+
+/// The outcome of applying a [`SelectionSpec`], other than the selection
+/// itself.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SelectionReport {
+    /// The entries of the spec which didn't match any changes, as written.
+    /// These are usually mistakes, such as a misspelled path.
+    pub unmatched_entries: Vec<String>,
+}
+
+impl SelectionSpec {
+    /// Parse a spec in the format described in [`SelectionSpec`].
+    pub fn parse(text: &str) -> Result<Self, SelectionSpecError> {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(SpecEntry::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Select the changes in `state` matched by this spec. The returned state
+    /// is normalized in the same way as the one returned by
+    /// [`crate::Recorder::run`], so it can be used in its place.
+    ///
+    /// If the state is read-only, then it's returned without any changes
+    /// selected, but the report still lists the entries which wouldn't have
+    /// matched anything.
+    pub fn apply<'state>(
+        &self,
+        mut state: RecordState<'state>,
+    ) -> (RecordState<'state>, SelectionReport) {
+        let Self { entries } = self;
+        normalize_commits(&mut state);
+        let num_commits = state.commits.len();
+        let original_files = state.is_read_only.then(|| state.files.clone());
+
+        let mut is_matched = vec![false; entries.len()];
+        for file in &mut state.files {
+            for (entry, is_matched) in entries.iter().zip(&mut is_matched) {
+                if entry.select_in_file(file, num_commits) {
+                    *is_matched = true;
+                }
+            }
+        }
+
+        if let Some(original_files) = original_files {
+            state.files = original_files;
+        }
+        let unmatched_entries = entries
+            .iter()
+            .zip(is_matched)
+            .filter(|(_entry, is_matched)| !is_matched)
+            .map(|(entry, _is_matched)| entry.text.clone())
+            .collect();
+        (state, SelectionReport { unmatched_entries })
+    }
+}
+
+/// A single line of a [`SelectionSpec`].
+#[derive(Clone, Debug)]
+struct SpecEntry {
+    text: String,
+    globs: Vec<Vec<char>>,
+    filters: Vec<LineFilter>,
+}
+
+#[derive(Clone, Debug)]
+enum LineFilter {
+    Additions,
+    Deletions,
+    OldLines(RangeInclusive<usize>),
+    NewLines(RangeInclusive<usize>),
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+
+impl SpecEntry {
+    fn parse(text: &str) -> Result<Self, SelectionSpecError> {
+        let mut globs = Vec::new();
+        let mut filters = Vec::new();
+        let mut rest = text;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(pattern) = rest.strip_prefix("re:") {
+                filters.push(parse_regex(text, pattern)?);
+                break;
+            }
+
+            let (term, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            rest = after;
+            let filter = match term {
+                "additions" => LineFilter::Additions,
+                "deletions" => LineFilter::Deletions,
+                term => match (term.strip_prefix("old:"), term.strip_prefix("new:")) {
+                    (Some(range), _) => LineFilter::OldLines(parse_line_range(text, range)?),
+                    (_, Some(range)) => LineFilter::NewLines(parse_line_range(text, range)?),
+                    (None, None) => {
+                        globs.push(term.chars().collect());
+                        continue;
+                    }
+                },
+            };
+            filters.push(filter);
+        }
+        Ok(Self {
+            text: text.to_owned(),
+            globs,
+            filters,
+        })
+    }
+
+    /// Select the changes in `file` matched by this entry. Returns whether
+    /// anything was matched, even if it was already selected.
+    fn select_in_file(&self, file: &mut File, num_commits: usize) -> bool {
+        let Self {
+            text: _,
+            globs,
+            filters,
+        } = self;
+        let matches_path = globs.is_empty()
+            || globs.iter().any(|glob| {
+                iter::once(&file.path)
+                    .chain(&file.old_path)
+                    .any(|path| glob_matches_path(glob, path))
+            });
+        if !matches_path {
+            return false;
+        }
+
+        if filters.is_empty() {
+            let has_changes = file.sections.iter().any(Section::is_editable);
+            file.assign_commit_idx(0, num_commits);
+            return has_changes;
+        }
+
+        // Deleting the file requires deleting all of its lines, so the whole
+        // file is selected.
+        let is_deletion_matched = file.sections.iter().any(|section| match section {
+            Section::FileMode { mode, .. } => {
+                *mode == FileMode::Absent
+                    && filters.iter().all(|filter| match filter {
+                        LineFilter::Deletions => true,
+                        LineFilter::Additions
+                        | LineFilter::OldLines(_)
+                        | LineFilter::NewLines(_) => false,
+                        #[cfg(feature = "regex")]
+                        LineFilter::Regex(_) => false,
+                    })
+            }
+            Section::Unchanged { .. } | Section::Changed { .. } | Section::Binary { .. } => false,
+        });
+        if is_deletion_matched {
+            file.assign_commit_idx(0, num_commits);
+            return true;
+        }
+
+        let mut is_matched = false;
+        let mut old_line_num = 1;
+        let mut new_line_num = 1;
+        for section in &mut file.sections {
+            match section {
+                Section::Unchanged { lines } => {
+                    old_line_num += lines.len();
+                    new_line_num += lines.len();
+                }
+                Section::Changed { lines } => {
+                    for line in lines {
+                        let line_num = match line.change_type {
+                            ChangeType::Added => &mut new_line_num,
+                            ChangeType::Removed => &mut old_line_num,
+                        };
+                        if filters.iter().all(|filter| filter.matches(line, *line_num)) {
+                            line.assign_commit_idx(0, num_commits);
+                            is_matched = true;
+                        }
+                        *line_num += 1;
+                    }
+                }
+                Section::FileMode { .. } | Section::Binary { .. } => {}
+            }
+        }
+
+        // As in the UI, selecting lines in a new file also selects its
+        // creation.
+        if is_matched {
+            move_file_mode_for_changed_lines(file, num_commits, 0);
+        }
+        is_matched
+    }
+}
+
+impl LineFilter {
+    /// Whether `line`, which is at `line_num` in the old version of the file
+    /// if it was removed or the new version if it was added, matches.
+    fn matches(&self, line: &SectionChangedLine, line_num: usize) -> bool {
+        match (self, line.change_type) {
+            (LineFilter::Additions, change_type) => change_type == ChangeType::Added,
+            (LineFilter::Deletions, change_type) => change_type == ChangeType::Removed,
+            (LineFilter::OldLines(range), ChangeType::Removed)
+            | (LineFilter::NewLines(range), ChangeType::Added) => range.contains(&line_num),
+            (LineFilter::OldLines(_), ChangeType::Added)
+            | (LineFilter::NewLines(_), ChangeType::Removed) => false,
+            #[cfg(feature = "regex")]
+            (LineFilter::Regex(regex), _) => {
+                regex.is_match(line.line.trim_end_matches(['\n', '\r']))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+fn parse_regex(entry: &str, pattern: &str) -> Result<LineFilter, SelectionSpecError> {
+    let regex = Regex::new(pattern).map_err(|source| SelectionSpecError::InvalidRegex {
+        entry: entry.to_owned(),
+        source,
+    })?;
+    Ok(LineFilter::Regex(regex))
+}
+
+#[cfg(not(feature = "regex"))]
+fn parse_regex(entry: &str, _pattern: &str) -> Result<LineFilter, SelectionSpecError> {
+    Err(SelectionSpecError::RegexUnsupported {
+        entry: entry.to_owned(),
+    })
+}
+
+fn parse_line_range(entry: &str, range: &str) -> Result<RangeInclusive<usize>, SelectionSpecError> {
+    let parse_line_num = |line_num: &str| line_num.parse::<usize>().ok().filter(|n| *n > 0);
+    let bounds = match range.split_once('-') {
+        Some((start, end)) => parse_line_num(start).zip(parse_line_num(end)),
+        None => parse_line_num(range).map(|line_num| (line_num, line_num)),
+    };
+    match bounds {
+        Some((start, end)) if start <= end => Ok(start..=end),
+        _ => Err(SelectionSpecError::InvalidLineRange {
+            entry: entry.to_owned(),
+            range: range.to_owned(),
+        }),
+    }
+}
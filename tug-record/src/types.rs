@@ -4,7 +4,7 @@ use std::borrow::Cow;
 use std::fmt::Display;
 use std::io;
 use std::num::TryFromIntError;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
@@ -18,8 +18,10 @@ pub struct RecordState<'a> {
     /// changed by the user.
     pub is_read_only: bool,
 
-    /// The commits containing the selected changes. Each changed section be
-    /// assigned to exactly one commit.
+    /// The commits containing the selected changes. Each changed line and
+    /// section is assigned to exactly one commit: checked changes are assigned
+    /// to the commit at their `commit_idx`, and unchecked changes are assigned
+    /// to the last commit.
     ///
     /// If there are fewer than two commits in this list, then it is padded to
     /// two commits using `Commit::default` before being returned.
@@ -38,6 +40,111 @@ pub struct RecordState<'a> {
     /// The state of each file. This is rendered in order, so you may want to
     /// sort this list by path before providing it.
     pub files: Vec<File<'a>>,
+
+    /// The comments left on the changes in review mode (see
+    /// [`crate::Recorder::with_review_mode`]). Comments provided here are
+    /// shown in the UI and can be edited, as long as the item which they're
+    /// attached to can still be found.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<ReviewComment>,
+}
+
+impl RecordState<'_> {
+    /// Format the review comments as a Markdown document, grouped by file.
+    pub fn review_notes_markdown(&self) -> String {
+        let mut result = String::from("# Review notes\n");
+        let mut last_path = None;
+        for comment in &self.comments {
+            let ReviewComment {
+                path,
+                target,
+                old_line_num,
+                new_line_num,
+                line,
+                comment,
+            } = comment;
+            if last_path != Some(path) {
+                result.push_str(&format!("\n## `{}`\n", path.to_string_lossy()));
+                last_path = Some(path);
+            }
+
+            let heading = match (target, old_line_num, new_line_num) {
+                (ReviewCommentTarget::File, _, _) => None,
+                (ReviewCommentTarget::Section, Some(old_line_num), Some(new_line_num)) => Some(
+                    format!("Section at line {old_line_num} (old), {new_line_num} (new)"),
+                ),
+                (ReviewCommentTarget::Line, Some(line_num), None) => {
+                    Some(format!("Line {line_num} (old)"))
+                }
+                (ReviewCommentTarget::Line, None, Some(line_num)) => {
+                    Some(format!("Line {line_num} (new)"))
+                }
+                (ReviewCommentTarget::Line, Some(old_line_num), Some(new_line_num)) => {
+                    Some(format!("Line {old_line_num} (old), {new_line_num} (new)"))
+                }
+                (ReviewCommentTarget::Section | ReviewCommentTarget::Line, _, _) => None,
+            };
+            if let Some(heading) = heading {
+                result.push_str(&format!("\n### {heading}\n"));
+            }
+            if let (ReviewCommentTarget::Line, Some(line)) = (target, line) {
+                let prefix = match (old_line_num, new_line_num) {
+                    (Some(_), None) => "-",
+                    (None, Some(_)) => "+",
+                    (Some(_), Some(_)) | (None, None) => " ",
+                };
+                result.push_str(&format!("\n```diff\n{prefix}{line}\n```\n"));
+            }
+            result.push_str(&format!("\n{}\n", comment.trim_end()));
+        }
+        result
+    }
+}
+
+/// What a [`ReviewComment`] is attached to.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ReviewCommentTarget {
+    /// The file as a whole.
+    File,
+
+    /// A section of the file, i.e. a hunk.
+    Section,
+
+    /// A single line, which may be added, removed or unchanged.
+    Line,
+}
+
+/// A comment left on a file, section or line while reviewing changes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ReviewComment {
+    /// The path of the file which was commented on.
+    pub path: PathBuf,
+
+    /// What the comment is attached to.
+    pub target: ReviewCommentTarget,
+
+    /// The number of the line in the old version of the file, counting
+    /// from 1. For a removed or unchanged line, this is the line itself; for a
+    /// section, it's the first line of the section. `None` for added lines and
+    /// files.
+    pub old_line_num: Option<usize>,
+
+    /// The number of the line in the new version of the file, counting
+    /// from 1. For an added or unchanged line, this is the line itself; for a
+    /// section, it's the first line of the section. `None` for removed lines
+    /// and files.
+    pub new_line_num: Option<usize>,
+
+    /// The text of the commented line, or of the first line of the commented
+    /// section, without its trailing newline. `None` for files and for
+    /// sections without lines, such as file mode changes.
+    pub line: Option<String>,
+
+    /// The comment itself.
+    pub comment: String,
 }
 
 /// An error which occurred when attempting to record changes.
@@ -67,6 +174,12 @@ pub enum RecordError {
     #[error("failed to wrote file: {0}")]
     WriteFile(#[source] io::Error),
 
+    #[error("failed to read file: {0}")]
+    ReadFile(#[source] io::Error),
+
+    #[error("failed to run editor: {0}")]
+    RunEditor(#[source] io::Error),
+
     #[error("{0}")]
     Other(String),
 
@@ -248,7 +361,11 @@ impl File<'_> {
         let file_mode_section = sections.iter().find_map(|section| match section {
             Section::Unchanged { .. } | Section::Changed { .. } | Section::Binary { .. } => None,
 
-            Section::FileMode { is_checked, mode } => Some((mode, is_checked)),
+            Section::FileMode {
+                is_checked,
+                commit_idx: _,
+                mode,
+            } => Some((mode, is_checked)),
         });
 
         // The file mode for the selected changes is the selected file mode, if one was selected,
@@ -278,6 +395,7 @@ impl File<'_> {
                     for line in lines {
                         let SectionChangedLine {
                             is_checked,
+                            commit_idx: _,
                             change_type,
                             line,
                         } = line;
@@ -306,6 +424,7 @@ impl File<'_> {
 
                 Section::Binary {
                     is_checked,
+                    commit_idx: _,
                     old_description,
                     new_description,
                 } => {
@@ -352,6 +471,119 @@ impl File<'_> {
         )
     }
 
+    /// Calculate the contents of the file for each of `num_commits` commits, in
+    /// order. The contents are cumulative: the contents for a given commit
+    /// include all changes assigned to that commit or any earlier commit. Thus,
+    /// the contents for the last commit include every change, and when there
+    /// are two commits, the contents for the first commit are the same as the
+    /// selected contents returned by [`File::get_selected_contents`].
+    pub fn get_commit_contents(&self, num_commits: usize) -> Vec<SelectedChanges<'_>> {
+        (0..num_commits)
+            .map(|commit_idx| {
+                self.get_contents_up_to_commit(|is_checked, item_commit_idx| {
+                    assigned_commit_idx(is_checked, item_commit_idx, num_commits) <= commit_idx
+                })
+            })
+            .collect()
+    }
+
+    /// Calculate the contents of the file where only the changes for which
+    /// `is_included(is_checked, commit_idx)` returns `true` have been applied.
+    fn get_contents_up_to_commit(
+        &self,
+        is_included: impl Fn(bool, usize) -> bool,
+    ) -> SelectedChanges<'_> {
+        let mut acc = SelectedContents::Unchanged;
+
+        let Self {
+            old_path: _,
+            path: _,
+            file_mode,
+            sections,
+        } = self;
+
+        let acc_file_mode = sections
+            .iter()
+            .find_map(|section| match section {
+                Section::Unchanged { .. } | Section::Changed { .. } | Section::Binary { .. } => {
+                    None
+                }
+                Section::FileMode {
+                    is_checked,
+                    commit_idx,
+                    mode,
+                } => Some((mode, is_included(*is_checked, *commit_idx))),
+            })
+            .filter(|(_, is_included)| *is_included)
+            .map(|(mode, _)| *mode)
+            .unwrap_or(*file_mode);
+
+        for section in sections {
+            match section {
+                Section::Unchanged { lines } => {
+                    for line in lines {
+                        acc.push_str(line);
+                    }
+                }
+
+                Section::Changed { lines } => {
+                    for line in lines {
+                        let SectionChangedLine {
+                            is_checked,
+                            commit_idx,
+                            change_type,
+                            line,
+                        } = line;
+                        match (change_type, is_included(*is_checked, *commit_idx)) {
+                            (ChangeType::Added, true) | (ChangeType::Removed, false) => {
+                                acc.push_str(line);
+                            }
+                            (ChangeType::Added, false) | (ChangeType::Removed, true) => {
+                                // See `get_selected_contents`: an emptied file
+                                // which still exists is not unchanged.
+                                if acc_file_mode != FileMode::Absent {
+                                    acc.push_str("");
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Section::FileMode { .. } => {
+                    // Do nothing - this is handled outside of the loop
+                }
+
+                Section::Binary {
+                    is_checked,
+                    commit_idx,
+                    old_description,
+                    new_description,
+                } => {
+                    acc = if is_included(*is_checked, *commit_idx) {
+                        SelectedContents::Binary {
+                            old_description: old_description.clone(),
+                            new_description: new_description.clone(),
+                        }
+                    } else {
+                        SelectedContents::Unchanged
+                    };
+                }
+            }
+        }
+
+        if *file_mode == FileMode::Absent
+            && acc_file_mode != FileMode::Absent
+            && acc == SelectedContents::Unchanged
+        {
+            acc.push_str("");
+        }
+
+        SelectedChanges {
+            contents: acc,
+            file_mode: acc_file_mode,
+        }
+    }
+
     /// Get the tristate value of the file. If there are no sections in this
     /// file, returns `Tristate::False`.
     pub fn tristate(&self) -> Tristate {
@@ -377,10 +609,12 @@ impl File<'_> {
                 }
                 Section::FileMode {
                     is_checked,
+                    commit_idx: _,
                     mode: _,
                 }
                 | Section::Binary {
                     is_checked,
+                    commit_idx: _,
                     old_description: _,
                     new_description: _,
                 } => {
@@ -399,6 +633,38 @@ impl File<'_> {
         }
     }
 
+    /// Get the tristate value of the file with respect to the commit at
+    /// `commit_idx`, out of `num_commits` commits. That is, whether all, some,
+    /// or none of the changes in this file are assigned to that commit.
+    pub fn tristate_for_commit(&self, commit_idx: usize, num_commits: usize) -> Tristate {
+        let Self {
+            old_path: _,
+            path: _,
+            file_mode: _,
+            sections,
+        } = self;
+        fold_tristate(
+            sections
+                .iter()
+                .flat_map(|section| section.assigned_commit_idxs(num_commits))
+                .map(|assigned_commit_idx| assigned_commit_idx == commit_idx),
+        )
+    }
+
+    /// Assign all sections and lines in this file to the commit at
+    /// `commit_idx`, out of `num_commits` commits.
+    pub fn assign_commit_idx(&mut self, commit_idx: usize, num_commits: usize) {
+        let Self {
+            old_path: _,
+            path: _,
+            file_mode: _,
+            sections,
+        } = self;
+        for section in sections {
+            section.assign_commit_idx(commit_idx, num_commits);
+        }
+    }
+
     /// Set the selection of all sections and lines in this file.
     pub fn set_checked(&mut self, checked: bool) {
         let Self {
@@ -457,6 +723,11 @@ pub enum Section<'a> {
         /// the UI.
         is_checked: bool,
 
+        /// The index of the commit that this change is assigned to, if it is
+        /// checked. See [`SectionChangedLine::commit_idx`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        commit_idx: usize,
+
         /// The mode of the file after these changes.
         mode: FileMode,
     },
@@ -467,6 +738,11 @@ pub enum Section<'a> {
         /// in the UI.
         is_checked: bool,
 
+        /// The index of the commit that this change is assigned to, if it is
+        /// checked. See [`SectionChangedLine::commit_idx`].
+        #[cfg_attr(feature = "serde", serde(default))]
+        commit_idx: usize,
+
         /// The description of the old binary contents, for use in the UI only.
         old_description: Option<Cow<'a, str>>,
 
@@ -503,10 +779,12 @@ impl Section<'_> {
             }
             Section::FileMode {
                 is_checked,
+                commit_idx: _,
                 mode: _,
             }
             | Section::Binary {
                 is_checked,
+                commit_idx: _,
                 old_description: _,
                 new_description: _,
             } => {
@@ -524,6 +802,65 @@ impl Section<'_> {
         }
     }
 
+    /// The indexes of the commits that each item in this section is assigned
+    /// to, out of `num_commits` commits.
+    pub fn assigned_commit_idxs(&self, num_commits: usize) -> Vec<usize> {
+        match self {
+            Section::Unchanged { .. } => Vec::new(),
+            Section::Changed { lines } => lines
+                .iter()
+                .map(|line| line.assigned_commit_idx(num_commits))
+                .collect(),
+            Section::FileMode {
+                is_checked,
+                commit_idx,
+                mode: _,
+            }
+            | Section::Binary {
+                is_checked,
+                commit_idx,
+                old_description: _,
+                new_description: _,
+            } => vec![assigned_commit_idx(*is_checked, *commit_idx, num_commits)],
+        }
+    }
+
+    /// Get the tristate value of this section with respect to the commit at
+    /// `commit_idx`, out of `num_commits` commits.
+    pub fn tristate_for_commit(&self, commit_idx: usize, num_commits: usize) -> Tristate {
+        fold_tristate(
+            self.assigned_commit_idxs(num_commits)
+                .into_iter()
+                .map(|assigned_commit_idx| assigned_commit_idx == commit_idx),
+        )
+    }
+
+    /// Assign all items in this section to the commit at `commit_idx`, out of
+    /// `num_commits` commits.
+    pub fn assign_commit_idx(&mut self, commit_idx: usize, num_commits: usize) {
+        match self {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    line.assign_commit_idx(commit_idx, num_commits);
+                }
+            }
+            Section::FileMode {
+                is_checked,
+                commit_idx: item_commit_idx,
+                mode: _,
+            }
+            | Section::Binary {
+                is_checked,
+                commit_idx: item_commit_idx,
+                old_description: _,
+                new_description: _,
+            } => {
+                assign_commit_idx(is_checked, item_commit_idx, commit_idx, num_commits);
+            }
+        }
+    }
+
     /// Select or unselect all items in this section.
     pub fn set_checked(&mut self, checked: bool) {
         match self {
@@ -535,6 +872,7 @@ impl Section<'_> {
             }
             Section::FileMode {
                 is_checked,
+                commit_idx: _,
                 mode: _,
             } => {
                 *is_checked = checked;
@@ -582,6 +920,13 @@ pub struct SectionChangedLine<'a> {
     /// Whether or not this line was selected to be recorded.
     pub is_checked: bool,
 
+    /// The index of the commit in [`RecordState::commits`] that this line is
+    /// assigned to, if it is checked. Unchecked lines are always assigned to
+    /// the last commit, so when there are only two commits, this is always
+    /// `0`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub commit_idx: usize,
+
     /// The type of change this line was.
     pub change_type: ChangeType,
 
@@ -589,3 +934,324 @@ pub struct SectionChangedLine<'a> {
     /// if any.
     pub line: Cow<'a, str>,
 }
+
+impl SectionChangedLine<'_> {
+    /// The index of the commit that this line is assigned to, out of
+    /// `num_commits` commits.
+    pub fn assigned_commit_idx(&self, num_commits: usize) -> usize {
+        assigned_commit_idx(self.is_checked, self.commit_idx, num_commits)
+    }
+
+    /// Assign this line to the commit at `commit_idx`, out of `num_commits`
+    /// commits.
+    pub fn assign_commit_idx(&mut self, commit_idx: usize, num_commits: usize) {
+        assign_commit_idx(
+            &mut self.is_checked,
+            &mut self.commit_idx,
+            commit_idx,
+            num_commits,
+        );
+    }
+}
+
+/// Calculate the index of the commit that a change with the given
+/// `is_checked` and `commit_idx` fields is assigned to.
+fn assigned_commit_idx(is_checked: bool, commit_idx: usize, num_commits: usize) -> usize {
+    let last_commit_idx = num_commits.saturating_sub(1);
+    if is_checked {
+        commit_idx.min(last_commit_idx)
+    } else {
+        last_commit_idx
+    }
+}
+
+/// Update the `is_checked` and `commit_idx` fields of a change such that it is
+/// assigned to the commit at `target_commit_idx`.
+fn assign_commit_idx(
+    is_checked: &mut bool,
+    commit_idx: &mut usize,
+    target_commit_idx: usize,
+    num_commits: usize,
+) {
+    if target_commit_idx + 1 >= num_commits {
+        *is_checked = false;
+        *commit_idx = 0;
+    } else {
+        *is_checked = true;
+        *commit_idx = target_commit_idx;
+    }
+}
+
+/// Fold an iterator of per-item selection states into a single `Tristate`.
+fn fold_tristate(items: impl IntoIterator<Item = bool>) -> Tristate {
+    let mut seen_value = None;
+    for is_checked in items {
+        seen_value = match (seen_value, is_checked) {
+            (None, is_checked) => Some(is_checked),
+            (Some(true), true) => Some(true),
+            (Some(false), false) => Some(false),
+            (Some(true), false) | (Some(false), true) => return Tristate::Partial,
+        };
+    }
+    match seen_value {
+        Some(true) => Tristate::True,
+        None | Some(false) => Tristate::False,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn changed_line(
+        is_checked: bool,
+        commit_idx: usize,
+        change_type: ChangeType,
+        line: &'static str,
+    ) -> SectionChangedLine<'static> {
+        SectionChangedLine {
+            is_checked,
+            commit_idx,
+            change_type,
+            line: Cow::Borrowed(line),
+        }
+    }
+
+    fn make_file(file_mode: FileMode, sections: Vec<Section<'static>>) -> File<'static> {
+        File {
+            old_path: None,
+            path: Cow::Borrowed(Path::new("foo")),
+            file_mode,
+            sections,
+        }
+    }
+
+    fn commit_contents(
+        file: &File,
+        num_commits: usize,
+    ) -> Vec<(FileMode, SelectedContents<'static>)> {
+        file.get_commit_contents(num_commits)
+            .into_iter()
+            .map(
+                |SelectedChanges {
+                     file_mode,
+                     contents,
+                 }| {
+                    let contents = match contents {
+                        SelectedContents::Unchanged => SelectedContents::Unchanged,
+                        SelectedContents::Binary {
+                            old_description,
+                            new_description,
+                        } => SelectedContents::Binary {
+                            old_description: old_description.map(|s| Cow::Owned(s.into_owned())),
+                            new_description: new_description.map(|s| Cow::Owned(s.into_owned())),
+                        },
+                        SelectedContents::Text { contents } => SelectedContents::Text { contents },
+                    };
+                    (file_mode, contents)
+                },
+            )
+            .collect()
+    }
+
+    fn text(contents: &str) -> SelectedContents<'static> {
+        SelectedContents::Text {
+            contents: contents.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_commit_contents_are_cumulative() {
+        let file = make_file(
+            FileMode::FILE_DEFAULT,
+            vec![
+                Section::Unchanged {
+                    lines: vec![Cow::Borrowed("context\n")],
+                },
+                Section::Changed {
+                    lines: vec![
+                        changed_line(true, 0, ChangeType::Removed, "old\n"),
+                        changed_line(true, 1, ChangeType::Added, "first\n"),
+                        changed_line(true, 0, ChangeType::Added, "zeroth\n"),
+                        changed_line(false, 0, ChangeType::Added, "last\n"),
+                    ],
+                },
+            ],
+        );
+        assert_eq!(
+            commit_contents(&file, 3),
+            vec![
+                (FileMode::FILE_DEFAULT, text("context\nzeroth\n")),
+                (FileMode::FILE_DEFAULT, text("context\nfirst\nzeroth\n")),
+                (
+                    FileMode::FILE_DEFAULT,
+                    text("context\nfirst\nzeroth\nlast\n")
+                ),
+            ]
+        );
+
+        // A checked change assigned past the last commit belongs to the last
+        // commit.
+        assert_eq!(
+            commit_contents(&file, 2),
+            vec![
+                (FileMode::FILE_DEFAULT, text("context\nzeroth\n")),
+                (
+                    FileMode::FILE_DEFAULT,
+                    text("context\nfirst\nzeroth\nlast\n")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_contents_file_creation() {
+        let file = make_file(
+            FileMode::Absent,
+            vec![
+                Section::FileMode {
+                    is_checked: true,
+                    commit_idx: 1,
+                    mode: FileMode::FILE_DEFAULT,
+                },
+                Section::Changed {
+                    lines: vec![
+                        changed_line(true, 1, ChangeType::Added, "foo\n"),
+                        changed_line(false, 0, ChangeType::Added, "bar\n"),
+                    ],
+                },
+            ],
+        );
+        assert_eq!(
+            commit_contents(&file, 3),
+            vec![
+                (FileMode::Absent, SelectedContents::Unchanged),
+                (FileMode::FILE_DEFAULT, text("foo\n")),
+                (FileMode::FILE_DEFAULT, text("foo\nbar\n")),
+            ]
+        );
+
+        // A created empty file has empty contents rather than being
+        // unchanged.
+        let file = make_file(
+            FileMode::Absent,
+            vec![Section::FileMode {
+                is_checked: true,
+                commit_idx: 1,
+                mode: FileMode::FILE_DEFAULT,
+            }],
+        );
+        assert_eq!(
+            commit_contents(&file, 3),
+            vec![
+                (FileMode::Absent, SelectedContents::Unchanged),
+                (FileMode::FILE_DEFAULT, text("")),
+                (FileMode::FILE_DEFAULT, text("")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_contents_file_deletion() {
+        let file = make_file(
+            FileMode::FILE_DEFAULT,
+            vec![
+                Section::FileMode {
+                    is_checked: false,
+                    commit_idx: 0,
+                    mode: FileMode::Absent,
+                },
+                Section::Changed {
+                    lines: vec![
+                        changed_line(true, 0, ChangeType::Removed, "foo\n"),
+                        changed_line(true, 1, ChangeType::Removed, "bar\n"),
+                    ],
+                },
+            ],
+        );
+        assert_eq!(
+            commit_contents(&file, 3),
+            vec![
+                // The file is emptied gradually, and only deleted in the last
+                // commit.
+                (FileMode::FILE_DEFAULT, text("bar\n")),
+                (FileMode::FILE_DEFAULT, text("")),
+                (FileMode::Absent, SelectedContents::Unchanged),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_contents_binary() {
+        let file = make_file(
+            FileMode::FILE_DEFAULT,
+            vec![Section::Binary {
+                is_checked: true,
+                commit_idx: 1,
+                old_description: Some(Cow::Borrowed("old")),
+                new_description: Some(Cow::Borrowed("new")),
+            }],
+        );
+        let binary = SelectedContents::Binary {
+            old_description: Some(Cow::Borrowed("old")),
+            new_description: Some(Cow::Borrowed("new")),
+        };
+        assert_eq!(
+            commit_contents(&file, 3),
+            vec![
+                (FileMode::FILE_DEFAULT, SelectedContents::Unchanged),
+                (FileMode::FILE_DEFAULT, binary.clone()),
+                (FileMode::FILE_DEFAULT, binary),
+            ]
+        );
+    }
+
+    fn arb_file() -> impl Strategy<Value = File<'static>> {
+        let arb_file_mode = prop_oneof![Just(FileMode::FILE_DEFAULT), Just(FileMode::Absent)];
+        let arb_change_type = prop_oneof![Just(ChangeType::Added), Just(ChangeType::Removed)];
+        let arb_line = prop_oneof![Just("foo\n"), Just("bar\n"), Just("baz")];
+        let arb_lines =
+            prop::collection::vec((any::<bool>(), arb_change_type, arb_line.clone()), 0..5);
+        let arb_section = prop_oneof![
+            prop::collection::vec(arb_line, 0..3).prop_map(|lines| Section::Unchanged {
+                lines: lines.into_iter().map(Cow::Borrowed).collect(),
+            }),
+            arb_lines.prop_map(|lines| Section::Changed {
+                lines: lines
+                    .into_iter()
+                    .map(|(is_checked, change_type, line)| {
+                        changed_line(is_checked, 0, change_type, line)
+                    })
+                    .collect(),
+            }),
+            (any::<bool>(), arb_file_mode.clone()).prop_map(|(is_checked, mode)| {
+                Section::FileMode {
+                    is_checked,
+                    commit_idx: 0,
+                    mode,
+                }
+            }),
+            any::<bool>().prop_map(|is_checked| Section::Binary {
+                is_checked,
+                commit_idx: 0,
+                old_description: Some(Cow::Borrowed("old")),
+                new_description: Some(Cow::Borrowed("new")),
+            }),
+        ];
+        (arb_file_mode, prop::collection::vec(arb_section, 0..4))
+            .prop_map(|(file_mode, sections)| make_file(file_mode, sections))
+    }
+
+    proptest! {
+        #[test]
+        fn test_first_commit_contents_are_selected_contents(file in arb_file()) {
+            let (selected, _unselected) = file.get_selected_contents();
+            let commit_contents = file.get_commit_contents(2);
+            prop_assert_eq!(commit_contents.len(), 2);
+            prop_assert_eq!(&commit_contents[0].file_mode, &selected.file_mode);
+            prop_assert_eq!(&commit_contents[0].contents, &selected.contents);
+        }
+    }
+}
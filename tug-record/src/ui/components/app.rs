@@ -1,18 +1,26 @@
-use crate::render::{Component, DrawnRect, Mask, Viewport};
+use crate::render::{Component, DrawnRect, Mask, Rect, Viewport};
+use crate::ui::components::comment_list_dialog::CommentListDialog;
 use crate::ui::components::commit_message_view::CommitViewMode;
 use crate::ui::components::commit_view::CommitView;
+use crate::ui::components::confirm_dialog::ConfirmDialog;
+use crate::ui::components::dir::{DirKey, FileTreeSidebar};
 use crate::ui::components::file::FileKey;
 use crate::ui::components::help_dialog::HelpDialog;
 use crate::ui::components::line::LineKey;
+use crate::ui::components::message_editor_dialog::MessageEditorDialog;
+use crate::ui::components::search_bar::SearchBar;
 use crate::ui::components::section::SectionKey;
+use crate::ui::components::session_dialog::SessionDialog;
 use crate::ui::components::ComponentId;
 use crate::util::UsizeExt;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum SelectionKey {
     None,
+    Dir(DirKey),
     File(FileKey),
     Section(SectionKey),
     Line(LineKey),
@@ -24,6 +32,65 @@ impl Default for SelectionKey {
     }
 }
 
+impl SelectionKey {
+    /// The index of the commit view that this key belongs to, if any.
+    pub fn commit_idx(self) -> Option<usize> {
+        match self {
+            SelectionKey::None => None,
+            SelectionKey::Dir(DirKey { commit_idx, .. })
+            | SelectionKey::File(FileKey { commit_idx, .. })
+            | SelectionKey::Section(SectionKey { commit_idx, .. })
+            | SelectionKey::Line(LineKey { commit_idx, .. }) => Some(commit_idx),
+        }
+    }
+
+    /// The key of the file containing this item, if any.
+    pub fn file_key(self) -> Option<FileKey> {
+        match self {
+            SelectionKey::None | SelectionKey::Dir(_) => None,
+            SelectionKey::File(file_key) => Some(file_key),
+            SelectionKey::Section(SectionKey {
+                commit_idx,
+                file_idx,
+                section_idx: _,
+            })
+            | SelectionKey::Line(LineKey {
+                commit_idx,
+                file_idx,
+                section_idx: _,
+                line_idx: _,
+            }) => Some(FileKey {
+                commit_idx,
+                file_idx,
+            }),
+        }
+    }
+
+    /// The equivalent key for the same item in the commit view at
+    /// `commit_idx`.
+    pub fn with_commit_idx(self, commit_idx: usize) -> Self {
+        match self {
+            SelectionKey::None => SelectionKey::None,
+            SelectionKey::Dir(dir_key) => SelectionKey::Dir(DirKey {
+                commit_idx,
+                ..dir_key
+            }),
+            SelectionKey::File(file_key) => SelectionKey::File(FileKey {
+                commit_idx,
+                ..file_key
+            }),
+            SelectionKey::Section(section_key) => SelectionKey::Section(SectionKey {
+                commit_idx,
+                ..section_key
+            }),
+            SelectionKey::Line(line_key) => SelectionKey::Line(LineKey {
+                commit_idx,
+                ..line_key
+            }),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub struct AppDebugInfo {
@@ -39,7 +106,13 @@ pub struct AppView<'a> {
     pub debug_info: Option<AppDebugInfo>,
     pub commit_view_mode: CommitViewMode,
     pub commit_views: Vec<CommitView<'a>>,
+    pub file_tree_sidebar: Option<FileTreeSidebar<'a>>,
     pub help_dialog: Option<HelpDialog>,
+    pub message_editor_dialog: Option<MessageEditorDialog<'a>>,
+    pub session_dialog: Option<SessionDialog<'a>>,
+    pub confirm_dialog: Option<ConfirmDialog<'a>>,
+    pub comment_list_dialog: Option<CommentListDialog<'a>>,
+    pub search_bar: Option<SearchBar<'a>>,
 }
 
 impl Component for AppView<'_> {
@@ -54,7 +127,13 @@ impl Component for AppView<'_> {
             debug_info,
             commit_view_mode,
             commit_views,
+            file_tree_sidebar,
             help_dialog,
+            message_editor_dialog,
+            session_dialog,
+            confirm_dialog,
+            comment_list_dialog,
+            search_bar,
         } = self;
 
         if let Some(debug_info) = debug_info {
@@ -63,25 +142,47 @@ impl Component for AppView<'_> {
 
         let viewport_rect = viewport.mask_rect();
 
+        let sidebar_width = match file_tree_sidebar {
+            Some(file_tree_sidebar) => {
+                let sidebar_width = FileTreeSidebar::width(viewport.rect().width);
+                let sidebar_mask = Mask {
+                    x: viewport_rect.x,
+                    y: viewport_rect.y,
+                    width: Some(sidebar_width),
+                    height: Some(viewport_rect.height),
+                };
+                viewport.with_mask(sidebar_mask, |viewport| {
+                    viewport.draw_component(viewport_rect.x, viewport_rect.y, file_tree_sidebar)
+                });
+                sidebar_width
+            }
+            None => 0,
+        };
+        let files_width = viewport.rect().width.saturating_sub(sidebar_width);
+
         let commit_view_width = match commit_view_mode {
-            CommitViewMode::Inline => viewport.rect().width,
+            CommitViewMode::Inline => files_width,
             CommitViewMode::Adjacent => {
                 const MAX_COMMIT_VIEW_WIDTH: usize = 120;
-                MAX_COMMIT_VIEW_WIDTH
-                    .min(viewport.rect().width.saturating_sub(CommitView::MARGIN) / 2)
+                let num_commit_views = commit_views.len().max(1);
+                MAX_COMMIT_VIEW_WIDTH.min(
+                    files_width.saturating_sub(CommitView::MARGIN * (num_commit_views - 1))
+                        / num_commit_views,
+                )
             }
         };
         let commit_views_mask = Mask {
-            x: viewport_rect.x,
+            x: viewport_rect.x + sidebar_width.unwrap_isize(),
             y: viewport_rect.y,
-            width: Some(viewport_rect.width),
+            width: Some(viewport_rect.width.saturating_sub(sidebar_width)),
             height: None,
         };
-        viewport.with_mask(commit_views_mask, |viewport| {
-            let mut commit_view_x = 0;
+        let commit_views_rect = viewport.with_mask(commit_views_mask, |viewport| {
+            let mut commit_views_rect = Rect::default();
+            let mut commit_view_x = sidebar_width.unwrap_isize();
             for commit_view in commit_views {
                 let commit_view_mask = Mask {
-                    x: commit_views_mask.x + commit_view_x,
+                    x: viewport_rect.x + commit_view_x,
                     y: commit_views_mask.y,
                     width: Some(commit_view_width),
                     height: None,
@@ -89,14 +190,40 @@ impl Component for AppView<'_> {
                 let commit_view_rect = viewport.with_mask(commit_view_mask, |viewport| {
                     viewport.draw_component(commit_view_x, 0, commit_view)
                 });
-                commit_view_x += (CommitView::MARGIN
-                    + commit_view_mask.apply(commit_view_rect).width)
-                    .unwrap_isize();
+                commit_views_rect = commit_views_rect.union_bounding(commit_view_rect);
+                // Advance by the full column width so that the columns stay
+                // aligned even if a commit view draws less than its width.
+                commit_view_x += (CommitView::MARGIN + commit_view_width).unwrap_isize();
             }
+            commit_views_rect
         });
+        // The sidebar and dialogs are drawn over whatever part of the commit
+        // views is scrolled into view, so only the commit views determine how
+        // far the UI can be scrolled.
+        viewport.skip_component(ComponentId::CommitViews, commit_views_rect);
+
+        if let Some(search_bar) = search_bar {
+            viewport.draw_component(0, 0, search_bar);
+        }
+
+        if let Some(message_editor_dialog) = message_editor_dialog {
+            viewport.draw_component(0, 0, message_editor_dialog);
+        }
+
+        if let Some(comment_list_dialog) = comment_list_dialog {
+            viewport.draw_component(0, 0, comment_list_dialog);
+        }
 
         if let Some(help_dialog) = help_dialog {
             viewport.draw_component(0, 0, help_dialog);
         }
+
+        if let Some(session_dialog) = session_dialog {
+            viewport.draw_component(0, 0, session_dialog);
+        }
+
+        if let Some(confirm_dialog) = confirm_dialog {
+            viewport.draw_component(0, 0, confirm_dialog);
+        }
     }
 }
@@ -0,0 +1,139 @@
+use crate::render::{Component, Viewport};
+use crate::types::ReviewComment;
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::widgets::{truncate_start, Button};
+use crate::ui::components::ComponentId;
+use crate::ui::review::comment_location;
+use crate::ui::theme::Theme;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A dialog listing every review comment, one per row.
+#[derive(Clone, Debug)]
+pub struct CommentListDialog<'a> {
+    pub comments: &'a [ReviewComment],
+    /// The index of the focused comment in `comments`.
+    pub focused_idx: usize,
+    pub theme: &'a Theme,
+}
+
+impl CommentListDialog<'_> {
+    /// The space taken by the border of the dialog and the margin around it.
+    const CHROME_WIDTH: usize = 6;
+
+    /// The space taken by the border, the help line, and the margin around
+    /// the dialog.
+    const CHROME_HEIGHT: usize = 8;
+
+    const MAX_WIDTH: usize = 100;
+
+    fn comment_line(&self, comment: &ReviewComment, is_focused: bool, width: usize) -> Line<'_> {
+        // Leave at least half of the width for the comment.
+        let location = comment_location(comment);
+        let location = truncate_start(&location, width / 2).into_owned();
+        let text_width = width.saturating_sub(location.width() + 2);
+        // Only the first line of the comment is shown.
+        let mut text = comment.comment.trim().lines();
+        let first_line = text.next().unwrap_or_default();
+        let text = match text.next() {
+            Some(_) => truncate_end(&format!("{first_line} …"), text_width),
+            None => truncate_end(first_line, text_width),
+        };
+        let padding = width.saturating_sub(location.width() + 2 + text.width());
+        let line = Line::from(vec![
+            Span::styled(location, self.theme.file_path),
+            Span::raw("  "),
+            Span::raw(text),
+            Span::raw(" ".repeat(padding)),
+        ]);
+        if is_focused {
+            line.style(self.theme.selection_highlight)
+        } else {
+            line
+        }
+    }
+}
+
+impl Component for CommentListDialog<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::CommentListDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self {
+            comments,
+            focused_idx,
+            theme: _,
+        } = self;
+        let viewport_rect = viewport.rect();
+        let width = viewport_rect
+            .width
+            .saturating_sub(Self::CHROME_WIDTH)
+            .clamp(1, Self::MAX_WIDTH);
+        let max_num_rows = viewport_rect
+            .height
+            .saturating_sub(Self::CHROME_HEIGHT)
+            .max(1);
+
+        let mut lines = if comments.is_empty() {
+            vec![Line::from("No comments yet.")]
+        } else {
+            // Scroll the list so that the focused comment is visible.
+            let first_idx = (focused_idx + 1).saturating_sub(max_num_rows);
+            comments
+                .iter()
+                .enumerate()
+                .skip(first_idx)
+                .take(max_num_rows)
+                .map(|(idx, comment)| self.comment_line(comment, idx == *focused_idx, width))
+                .collect()
+        };
+        lines.extend([
+            Line::from(""),
+            Line::from(Span::styled(
+                "↑/↓ move • Enter go to comment • Esc close",
+                Style::default().add_modifier(Modifier::DIM),
+            )),
+        ]);
+        let body = Text::from(lines);
+
+        let close_button = Button {
+            id: ComponentId::CommentListCloseButton,
+            label: Cow::Borrowed("Close"),
+            style: Style::default(),
+            is_focused: true,
+        };
+        let buttons = [close_button];
+        let dialog = Dialog {
+            id: self.id(),
+            title: Cow::Owned(format!("Comments ({})", comments.len())),
+            body: Cow::Borrowed(&body),
+            buttons: &buttons,
+        };
+        viewport.draw_component(0, 0, &dialog);
+    }
+}
+
+/// Shorten `s` to at most `width` columns by replacing its end with `…`.
+fn truncate_end(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_owned();
+    }
+    let mut result = String::new();
+    let mut result_width = 0;
+    for c in s.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if result_width + char_width + 1 > width {
+            break;
+        }
+        result.push(c);
+        result_width += char_width;
+    }
+    result.push('…');
+    result
+}
@@ -2,6 +2,7 @@ use crate::render::{Component, Rect, Viewport};
 use crate::types::Commit;
 use crate::ui::components::widgets::Button;
 use crate::ui::components::ComponentId;
+use crate::ui::theme::Theme;
 use ratatui::style::{Modifier, Style};
 use ratatui::text::Span;
 use std::borrow::Cow;
@@ -17,6 +18,22 @@ pub enum CommitViewMode {
 pub struct CommitMessageView<'a> {
     pub commit_idx: usize,
     pub commit: &'a Commit,
+    pub theme: &'a Theme,
+}
+
+impl CommitMessageView<'_> {
+    /// The number of rows taken up by the view: none if the commit has no
+    /// message, or else the subject line and the preview of the body, if any,
+    /// with a blank row above and below.
+    pub fn height(&self) -> usize {
+        match &self.commit.message {
+            None => 0,
+            Some(message) => match body_preview(message) {
+                Some(_) => 4,
+                None => 3,
+            },
+        }
+    }
 }
 
 impl Component for CommitMessageView<'_> {
@@ -27,7 +44,11 @@ impl Component for CommitMessageView<'_> {
     }
 
     fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
-        let Self { commit_idx, commit } = self;
+        let Self {
+            commit_idx,
+            commit,
+            theme,
+        } = self;
         match commit {
             Commit { message: None } => {}
             Commit {
@@ -54,15 +75,13 @@ impl Component for CommitMessageView<'_> {
                 );
                 let divider_rect =
                     viewport.draw_span(button_rect.end_x() + 1, y, &Span::raw(" • "));
+                let first_line = message.split('\n').next().unwrap_or_default();
+                let text_x = divider_rect.end_x() + 1;
                 viewport.draw_text(
-                    divider_rect.end_x() + 1,
+                    text_x,
                     y,
                     Span::styled(
                         Cow::Borrowed({
-                            let first_line = match message.split_once('\n') {
-                                Some((before, _after)) => before,
-                                None => message,
-                            };
                             let first_line = first_line.trim();
                             if first_line.is_empty() {
                                 "(no message)"
@@ -75,6 +94,25 @@ impl Component for CommitMessageView<'_> {
                 );
                 let y = y + 1;
 
+                // Preview the start of the body under the subject line.
+                let y = match body_preview(message) {
+                    Some(preview) => {
+                        viewport.draw_blank(Rect {
+                            x,
+                            y,
+                            width: viewport.mask_rect().width,
+                            height: 1,
+                        });
+                        viewport.draw_text(
+                            text_x,
+                            y,
+                            Span::styled(preview, theme.commit_message_body),
+                        );
+                        y + 1
+                    }
+                    None => y,
+                };
+
                 viewport.draw_blank(Rect {
                     x,
                     y,
@@ -85,3 +123,20 @@ impl Component for CommitMessageView<'_> {
         }
     }
 }
+
+/// The first non-blank line of the body of `message`, to preview under the
+/// subject line, followed by an ellipsis if there's more to the body.
+fn body_preview(message: &str) -> Option<Cow<'_, str>> {
+    let body = message.split_once('\n').map_or("", |(_subject, body)| body);
+    let mut body_lines = body
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty());
+    let body_line = body_lines.next()?;
+    let preview = if body_lines.next().is_some() {
+        Cow::Owned(format!("{body_line} …"))
+    } else {
+        Cow::Borrowed(body_line)
+    };
+    Some(preview)
+}
@@ -1,17 +1,42 @@
 use crate::render::{centered_rect, Component, Mask, Rect, RectSize, Viewport};
-use crate::ui::components::app::AppDebugInfo;
+use crate::ui::components::app::{AppDebugInfo, SelectionKey};
 use crate::ui::components::commit_message_view::CommitMessageView;
-use crate::ui::components::file::{FileView, FileViewHeader};
+use crate::ui::components::dir::DirView;
+use crate::ui::components::file::{FileKey, FileView, FileViewHeader};
 use crate::ui::components::ComponentId;
-use crate::util::UsizeExt;
+use crate::util::{IsizeExt, UsizeExt};
 use ratatui::text::Span;
 use std::fmt::Debug;
 
+/// An item in the list of files of a commit view.
+#[derive(Clone, Debug)]
+pub enum FileListItem<'a> {
+    /// A directory, when the directory tree is shown inline.
+    Dir { depth: usize, dir_view: DirView<'a> },
+
+    /// A file, nested `depth` directories deep if the directory tree is shown
+    /// inline.
+    File {
+        depth: usize,
+        file_view: FileView<'a>,
+    },
+
+    /// A file which is far outside of the viewport, and so isn't drawn. Only
+    /// the space that it took up when it was last drawn is kept for it.
+    Offscreen {
+        depth: usize,
+        file_key: FileKey,
+        size: RectSize,
+    },
+}
+
 #[derive(Clone, Debug)]
 pub struct CommitView<'a> {
     pub debug_info: Option<&'a AppDebugInfo>,
     pub commit_message_view: CommitMessageView<'a>,
-    pub file_views: Vec<FileView<'a>>,
+    pub file_list_items: Vec<FileListItem<'a>>,
+    /// Whether some files are hidden by the file filter.
+    pub is_filtered: bool,
 }
 
 impl CommitView<'_> {
@@ -29,12 +54,17 @@ impl Component for CommitView<'_> {
         let Self {
             debug_info,
             commit_message_view,
-            file_views,
+            file_list_items,
+            is_filtered,
         } = self;
 
         let commit_message_view_rect = viewport.draw_component(x, y, commit_message_view);
-        if file_views.is_empty() {
-            let message = "There are no changes to view.";
+        if file_list_items.is_empty() {
+            let message = if *is_filtered {
+                "No files match the filter."
+            } else {
+                "There are no changes to view."
+            };
             let message_rect = centered_rect(
                 Rect {
                     x,
@@ -55,12 +85,44 @@ impl Component for CommitView<'_> {
 
         let mut y = y;
         y += commit_message_view_rect.height.unwrap_isize();
-        for file_view in file_views {
+        for file_list_item in file_list_items {
+            let (depth, file_view) = match file_list_item {
+                FileListItem::Dir { depth, dir_view } => {
+                    let dir_view_rect =
+                        viewport.draw_component(x + DirView::indent(*depth), y, dir_view);
+                    y += dir_view_rect.height.unwrap_isize();
+                    continue;
+                }
+                FileListItem::File { depth, file_view } => (*depth, file_view),
+                FileListItem::Offscreen {
+                    depth,
+                    file_key,
+                    size,
+                } => {
+                    let RectSize { width, height } = *size;
+                    viewport.skip_component(
+                        ComponentId::SelectableItem(SelectionKey::File(*file_key)),
+                        Rect {
+                            x: x + DirView::indent(*depth),
+                            y,
+                            width,
+                            height,
+                        },
+                    );
+                    y += height.unwrap_isize();
+                    continue;
+                }
+            };
+            let indent = DirView::indent(depth);
+            let x = x + indent;
             let file_view_rect = {
                 let file_view_mask = Mask {
                     x,
                     y,
-                    width: viewport.mask().width,
+                    width: viewport
+                        .mask()
+                        .width
+                        .map(|width| width.saturating_sub(indent.unwrap_usize())),
                     height: None,
                 };
                 viewport.with_mask(file_view_mask, |viewport| {
@@ -77,10 +139,18 @@ impl Component for CommitView<'_> {
                     Mask {
                         x,
                         y: mask.y,
-                        width: Some(viewport.mask_rect().width),
+                        width: Some(
+                            viewport
+                                .mask_rect()
+                                .width
+                                .saturating_sub(indent.unwrap_usize()),
+                        ),
                         height: Some(1),
                     },
                     |viewport| {
+                        // Don't let the lines scrolled under the header show
+                        // through past its end.
+                        viewport.draw_blank(viewport.mask_rect());
                         viewport.draw_component(
                             x,
                             mask.y,
@@ -89,8 +159,10 @@ impl Component for CommitView<'_> {
                                 path: file_view.path,
                                 old_path: file_view.old_path,
                                 is_selected: file_view.is_header_selected,
+                                is_commented: file_view.is_commented,
                                 toggle_box: file_view.toggle_box.clone(),
                                 expand_box: file_view.expand_box.clone(),
+                                theme: file_view.theme,
                             },
                         );
                     },
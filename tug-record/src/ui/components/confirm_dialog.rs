@@ -0,0 +1,141 @@
+use crate::render::{Component, Viewport};
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::widgets::{truncate_start, Button};
+use crate::ui::components::ComponentId;
+use crate::ui::confirm::{ConfirmPrompt, FileChange, FileChangeKind};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use unicode_width::UnicodeWidthStr;
+
+/// A dialog asking for confirmation before accepting or discarding the
+/// selection.
+#[derive(Clone, Debug)]
+pub struct ConfirmDialog<'a> {
+    pub prompt: &'a ConfirmPrompt,
+}
+
+impl ConfirmDialog<'_> {
+    /// The space taken by the border of the dialog and the margin around it.
+    const CHROME_WIDTH: usize = 6;
+
+    /// The space taken by the border, the text around the list of files, and
+    /// the margin around the dialog.
+    const CHROME_HEIGHT: usize = 10;
+
+    /// The width of the longest description of a change, such as `create`.
+    const CHANGE_KIND_WIDTH: usize = 6;
+
+    fn file_change_line(file_change: &FileChange, width: usize) -> Line<'static> {
+        let FileChange {
+            path,
+            kind,
+            mode_change,
+        } = file_change;
+        let kind = match kind {
+            FileChangeKind::Created => "create",
+            FileChangeKind::Written => "write",
+            FileChangeKind::Deleted => "delete",
+            FileChangeKind::ModeChanged => "chmod",
+        };
+        let mode_change = match mode_change {
+            Some((old_mode, new_mode)) => format!(" ({old_mode} → {new_mode})"),
+            None => String::new(),
+        };
+        let prefix = format!("  {kind:<width$}  ", width = Self::CHANGE_KIND_WIDTH);
+        let path = path.to_string_lossy();
+        let path_width = width
+            .saturating_sub(prefix.width() + mode_change.width())
+            .max(1);
+        Line::from(vec![
+            Span::styled(prefix, Style::default().add_modifier(Modifier::DIM)),
+            Span::raw(truncate_start(&path, path_width).into_owned()),
+            Span::raw(mode_change),
+        ])
+    }
+}
+
+impl Component for ConfirmDialog<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::ConfirmDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self { prompt } = self;
+        let viewport_rect = viewport.rect();
+        let max_width = viewport_rect.width.saturating_sub(Self::CHROME_WIDTH);
+
+        let (title, mut lines, confirm_label) = match prompt {
+            ConfirmPrompt::Accept { file_changes } => {
+                let mut lines = match file_changes.len() {
+                    0 => vec![Line::from("No files will be changed.")],
+                    1 => vec![Line::from("1 file will be changed:")],
+                    num_files => vec![Line::from(format!("{num_files} files will be changed:"))],
+                };
+                let max_num_lines = viewport_rect
+                    .height
+                    .saturating_sub(Self::CHROME_HEIGHT)
+                    .max(1);
+                let num_shown = if file_changes.len() > max_num_lines {
+                    // Leave room for the line saying how many were omitted.
+                    max_num_lines.saturating_sub(1)
+                } else {
+                    file_changes.len()
+                };
+                lines.extend(
+                    file_changes
+                        .iter()
+                        .take(num_shown)
+                        .map(|file_change| Self::file_change_line(file_change, max_width)),
+                );
+                if num_shown < file_changes.len() {
+                    lines.push(Line::from(Span::styled(
+                        format!("  …and {} more", file_changes.len() - num_shown),
+                        Style::default().add_modifier(Modifier::DIM),
+                    )));
+                }
+                ("Accept changes?", lines, "Accept")
+            }
+            ConfirmPrompt::Cancel => (
+                "Discard changes?",
+                vec![
+                    Line::from("The selection has changed since starting."),
+                    Line::from("Quitting now will discard it."),
+                ],
+                "Discard",
+            ),
+        };
+        lines.extend([
+            Line::from(""),
+            Line::from(Span::styled(
+                "Enter confirm • Esc go back",
+                Style::default().add_modifier(Modifier::DIM),
+            )),
+        ]);
+        let body = Text::from(lines);
+
+        let confirm_button = Button {
+            id: ComponentId::ConfirmButton,
+            label: Cow::Borrowed(confirm_label),
+            style: Style::default(),
+            is_focused: true,
+        };
+        let back_button = Button {
+            id: ComponentId::ConfirmBackButton,
+            label: Cow::Borrowed("Go back"),
+            style: Style::default(),
+            is_focused: false,
+        };
+        let buttons = [confirm_button, back_button];
+        let dialog = Dialog {
+            id: self.id(),
+            title: Cow::Borrowed(title),
+            body: Cow::Borrowed(&body),
+            buttons: &buttons,
+        };
+        viewport.draw_component(0, 0, &dialog);
+    }
+}
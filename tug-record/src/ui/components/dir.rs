@@ -0,0 +1,215 @@
+use crate::render::{Component, Mask, Rect, Viewport};
+use crate::ui::components::app::SelectionKey;
+use crate::ui::components::widgets::{highlight_rect, TristateBox};
+use crate::ui::components::ComponentId;
+use crate::ui::theme::Theme;
+use crate::util::UsizeExt;
+use ratatui::text::Span;
+use std::fmt::Debug;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DirKey {
+    pub commit_idx: usize,
+    pub dir_idx: usize,
+}
+
+/// The number of columns to indent each level of the directory tree by.
+const INDENT_WIDTH: usize = 2;
+
+/// A directory in the list of files, when the directory tree is shown inline.
+#[derive(Clone, Debug)]
+pub struct DirView<'a> {
+    pub dir_key: DirKey,
+    pub name: &'a Path,
+    pub toggle_box: TristateBox<'a, ComponentId>,
+    pub expand_box: TristateBox<'a, ComponentId>,
+    pub is_selected: bool,
+    pub theme: &'a Theme,
+}
+
+impl DirView<'_> {
+    /// The number of rows taken up by a directory.
+    pub const HEIGHT: usize = 1;
+
+    /// The indentation of items nested `depth` directories deep.
+    pub fn indent(depth: usize) -> isize {
+        (depth * INDENT_WIDTH).unwrap_isize()
+    }
+}
+
+impl Component for DirView<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::SelectableItem(SelectionKey::Dir(self.dir_key))
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
+        let Self {
+            dir_key: _,
+            name,
+            toggle_box,
+            expand_box,
+            is_selected,
+            theme,
+        } = self;
+
+        let mut cursor_x = x;
+        let expand_box_rect = viewport.draw_component(cursor_x, y, expand_box);
+        cursor_x += expand_box_rect.width.unwrap_isize() + 1;
+        let toggle_box_rect = viewport.draw_component(cursor_x, y, toggle_box);
+        cursor_x += toggle_box_rect.width.unwrap_isize() + 1;
+        viewport.draw_text(
+            cursor_x,
+            y,
+            Span::styled(format!("{}/", name.to_string_lossy()), theme.dir_path),
+        );
+
+        if *is_selected {
+            highlight_rect(
+                viewport,
+                Rect {
+                    x: viewport.mask_rect().x,
+                    y,
+                    width: viewport.mask_rect().width,
+                    height: Self::HEIGHT,
+                },
+                theme,
+            );
+        }
+    }
+}
+
+/// A directory or file in the directory tree sidebar.
+#[derive(Clone, Debug)]
+pub struct FileTreeSidebarRow<'a> {
+    /// Either a directory or a file key.
+    pub selection_key: SelectionKey,
+    pub name: &'a Path,
+    pub depth: usize,
+    pub toggle_box: TristateBox<'a, ComponentId>,
+    /// Only directories can be expanded or collapsed.
+    pub expand_box: Option<TristateBox<'a, ComponentId>>,
+    pub is_selected: bool,
+    pub theme: &'a Theme,
+}
+
+impl Component for FileTreeSidebarRow<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::SidebarRow(self.selection_key)
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
+        let Self {
+            selection_key: _,
+            name,
+            depth,
+            toggle_box,
+            expand_box,
+            is_selected,
+            theme,
+        } = self;
+
+        let mut cursor_x = x + DirView::indent(*depth);
+        let style = match expand_box {
+            Some(expand_box) => {
+                let expand_box_rect = viewport.draw_component(cursor_x, y, expand_box);
+                cursor_x += expand_box_rect.width.unwrap_isize() + 1;
+                theme.dir_path
+            }
+            None => {
+                // Align with the toggle boxes of directories.
+                cursor_x += 2;
+                theme.file_path
+            }
+        };
+        let toggle_box_rect = viewport.draw_component(cursor_x, y, toggle_box);
+        cursor_x += toggle_box_rect.width.unwrap_isize() + 1;
+        let name = match expand_box {
+            Some(_) => format!("{}/", name.to_string_lossy()),
+            None => name.to_string_lossy().into_owned(),
+        };
+        viewport.draw_text(cursor_x, y, Span::styled(name, style));
+
+        if *is_selected {
+            highlight_rect(
+                viewport,
+                Rect {
+                    x: viewport.mask_rect().x,
+                    y,
+                    width: viewport.mask_rect().width,
+                    height: 1,
+                },
+                theme,
+            );
+        }
+    }
+}
+
+/// A pane showing the directory tree of the files being recorded.
+#[derive(Clone, Debug)]
+pub struct FileTreeSidebar<'a> {
+    /// The visible rows of the tree, in order.
+    pub rows: Vec<FileTreeSidebarRow<'a>>,
+    pub theme: &'a Theme,
+}
+
+impl FileTreeSidebar<'_> {
+    /// The width of the sidebar, including its border, for a terminal of the
+    /// given width.
+    pub fn width(terminal_width: usize) -> usize {
+        const MIN_WIDTH: usize = 20;
+        const MAX_WIDTH: usize = 40;
+        (terminal_width / 4).clamp(MIN_WIDTH, MAX_WIDTH)
+    }
+}
+
+impl Component for FileTreeSidebar<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::FileTreeSidebar
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
+        let Self { rows, theme } = self;
+
+        let mask_rect = viewport.mask_rect();
+        let border_x = mask_rect.end_x() - 1;
+        let rows_mask = Mask {
+            x: mask_rect.x,
+            y: mask_rect.y,
+            width: Some(mask_rect.width.saturating_sub(1)),
+            height: Some(mask_rect.height),
+        };
+
+        // Keep the selected row on the screen, regardless of where the list
+        // of files is scrolled to.
+        let height = mask_rect.height.unwrap_isize();
+        let selected_row_idx = rows
+            .iter()
+            .position(|row| row.is_selected)
+            .unwrap_or_default()
+            .unwrap_isize();
+        let scroll_offset = (selected_row_idx + 1 - height).max(0);
+        viewport.with_mask(rows_mask, |viewport| {
+            viewport.draw_blank(viewport.mask_rect());
+            for (row_idx, row) in rows.iter().enumerate() {
+                let row_y = y + row_idx.unwrap_isize() - scroll_offset;
+                if row_y >= y + height {
+                    break;
+                }
+                if row_y >= y {
+                    viewport.draw_component(x, row_y, row);
+                }
+            }
+        });
+        for row_y in y..y + height {
+            viewport.draw_span(border_x, row_y, &Span::styled("│", theme.sidebar_border));
+        }
+    }
+}
@@ -1,16 +1,17 @@
 use crate::render::{Component, Rect, Viewport};
 use crate::types::Tristate;
 use crate::ui::components::app::SelectionKey;
-use crate::ui::components::widgets::{highlight_rect, TristateBox};
+use crate::ui::components::widgets::{draw_comment_marker, highlight_rect, TristateBox};
 use crate::ui::components::{section, ComponentId};
+use crate::ui::theme::Theme;
 use crate::util::UsizeExt;
-use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Span;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::path::Path;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct FileKey {
     pub commit_idx: usize,
     pub file_idx: usize,
@@ -20,12 +21,15 @@ pub struct FileKey {
 pub struct FileView<'a> {
     pub debug: bool,
     pub file_key: FileKey,
-    pub toggle_box: TristateBox<ComponentId>,
-    pub expand_box: TristateBox<ComponentId>,
+    pub toggle_box: TristateBox<'a, ComponentId>,
+    pub expand_box: TristateBox<'a, ComponentId>,
     pub is_header_selected: bool,
+    /// Whether the file has a review comment.
+    pub is_commented: bool,
     pub old_path: Option<&'a Path>,
     pub path: &'a Path,
     pub section_views: Vec<section::SectionView<'a>>,
+    pub theme: &'a Theme,
 }
 
 impl FileView<'_> {
@@ -54,6 +58,8 @@ impl Component for FileView<'_> {
             path,
             section_views,
             is_header_selected,
+            is_commented,
+            theme,
         } = self;
 
         let file_view_header_rect = viewport.draw_component(
@@ -64,8 +70,10 @@ impl Component for FileView<'_> {
                 path,
                 old_path: *old_path,
                 is_selected: *is_header_selected,
+                is_commented: *is_commented,
                 toggle_box: toggle_box.clone(),
                 expand_box: expand_box.clone(),
+                theme,
             },
         );
         if self.is_expanded() {
@@ -107,8 +115,10 @@ pub struct FileViewHeader<'a> {
     pub path: &'a Path,
     pub old_path: Option<&'a Path>,
     pub is_selected: bool,
-    pub toggle_box: TristateBox<ComponentId>,
-    pub expand_box: TristateBox<ComponentId>,
+    pub is_commented: bool,
+    pub toggle_box: TristateBox<'a, ComponentId>,
+    pub expand_box: TristateBox<'a, ComponentId>,
+    pub theme: &'a Theme,
 }
 
 impl Component for FileViewHeader<'_> {
@@ -120,8 +130,10 @@ impl Component for FileViewHeader<'_> {
             path: _,
             old_path: _,
             is_selected: _,
+            is_commented: _,
             toggle_box: _,
             expand_box: _,
+            theme: _,
         } = self;
         ComponentId::FileViewHeader(*file_key)
     }
@@ -133,8 +145,10 @@ impl Component for FileViewHeader<'_> {
             path,
             old_path,
             is_selected,
+            is_commented,
             toggle_box,
             expand_box,
+            theme,
         } = self;
 
         // Draw components left-to-right: expand icon -> select checkbox -> file path
@@ -158,12 +172,14 @@ impl Component for FileViewHeader<'_> {
                     },
                     path.to_string_lossy(),
                 ),
-                Style::default()
-                    .fg(Color::Magenta)
-                    .add_modifier(Modifier::BOLD),
+                theme.file_path,
             ),
         );
 
+        if *is_commented {
+            draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, theme);
+        }
+
         // 4. Highlight the entire line if it's selected.
         if *is_selected {
             highlight_rect(
@@ -174,6 +190,7 @@ impl Component for FileViewHeader<'_> {
                     width: viewport.mask_rect().width,
                     height: 1,
                 },
+                theme,
             );
         }
     }
@@ -47,7 +47,13 @@ impl Component for HelpDialog {
                 Span::styled("Scrolling", Style::new().bold().underlined()),
             ]),
             Line::from("    Edit commit message     e           Scroll up/down          ^y/^e"),
-            Line::from("                                                             or ^↑/^↓"),
+            Line::from("    Toggle view mode        Tab                              or ^↑/^↓"),
+            Line::from("    Split/unified diff      s           Scroll left/right       ^←/^→"),
+            Line::from("    File tree/sidebar       t                                or zh/zl"),
+            Line::from("    Filter files            ="),
+            Line::from("    More context up/down    {/}"),
+            Line::from("    Reveal hidden context   x"),
+            Line::from("    Wrap long lines         w"),
             Line::from(vec![
                 Span::raw("    "),
                 Span::styled("Selection", Style::new().bold().underlined()),
@@ -57,8 +63,21 @@ impl Component for HelpDialog {
                 "    Toggle current          Space                            or ^PgUp/^PgDn",
             ),
             Line::from("    Toggle and advance      Enter       Previous/Next page      ^u/^d"),
-            Line::from("    Invert all              a"),
-            Line::from("    Invert all uniformly    A"),
+            Line::from("    Invert all              a           Search                  /"),
+            Line::from("    Invert all uniformly    A           Next/Prev match         n/N"),
+            Line::from("    Undo/Redo               u/^r"),
+            Line::from("    Move to next commit     >"),
+            Line::from("    Move to previous commit <"),
+            Line::from("    Focus next/prev commit  ]/["),
+            Line::from("    Edit added lines        E"),
+            Line::from("    Select a range          v"),
+            Line::from("    Check/Uncheck range     +/-"),
+            Line::from(vec![
+                Span::raw("    "),
+                Span::styled("Review mode", Style::new().bold().underlined()),
+            ]),
+            Line::from("    Comment on current      m"),
+            Line::from("    List comments           M"),
         ]);
 
         let quit_button = Button {
@@ -3,12 +3,19 @@ use crate::types::ChangeType;
 use crate::ui::components::app::SelectionKey;
 use crate::ui::components::widgets::TristateBox;
 use crate::ui::components::ComponentId;
-use ratatui::style::{Color, Modifier, Style};
+use crate::ui::highlight::HighlightedToken;
+use crate::ui::search::SearchQuery;
+use crate::ui::theme::Theme;
+use crate::util::{IsizeExt, UsizeExt};
+use ratatui::style::Style;
 use ratatui::text::{Line, Span};
+use std::borrow::Cow;
 use std::fmt::Debug;
-use unicode_width::UnicodeWidthChar;
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct LineKey {
     pub commit_idx: usize,
     pub file_idx: usize,
@@ -16,6 +23,22 @@ pub struct LineKey {
     pub line_idx: usize,
 }
 
+/// The number of columns scrolled by each request to scroll long lines
+/// horizontally.
+pub const HORIZONTAL_SCROLL_STEP: usize = 8;
+
+/// How the text of lines too long to fit in the view is shown.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineLayout {
+    /// Cut off at the edge of the view, after skipping `offset_x` columns of
+    /// the text.
+    Scroll { offset_x: usize },
+
+    /// Wrapped onto as many rows as needed, with a marker in the gutter of
+    /// each extra row.
+    Wrap,
+}
+
 #[derive(Clone, Debug)]
 pub enum SectionLineViewInner<'a> {
     Unchanged {
@@ -23,7 +46,7 @@ pub enum SectionLineViewInner<'a> {
         line_num: usize,
     },
     Changed {
-        toggle_box: TristateBox<ComponentId>,
+        toggle_box: TristateBox<'a, ComponentId>,
         change_type: ChangeType,
         line: &'a str,
     },
@@ -81,16 +104,14 @@ fn replace_control_character(character: char) -> Option<&'static str> {
 
 /// Split the line into a sequence of [`Span`]s where control characters are
 /// replaced with styled [`Span`]'s and push them to the [`spans`] argument.
-pub fn push_spans_from_line<'line>(line: &'line str, spans: &mut Vec<Span<'line>>) {
-    const CONTROL_CHARACTER_STYLE: Style = Style::new().fg(Color::DarkGray);
-
+pub fn push_spans_from_line<'line>(line: &'line str, theme: &Theme, spans: &mut Vec<Span<'line>>) {
     let mut last_index = 0;
     // Find index of the start of each character to replace
     for (idx, char) in line.match_indices(|char| replace_control_character(char).is_some()) {
         // Push the string leading up to the character and the styled replacement string
         if let Some(replacement_string) = char.chars().next().and_then(replace_control_character) {
             spans.push(Span::raw(&line[last_index..idx]));
-            spans.push(Span::styled(replacement_string, CONTROL_CHARACTER_STYLE));
+            spans.push(Span::styled(replacement_string, theme.control_character));
             // Move the "cursor" to just after the character we're replacing
             last_index = idx + char.len();
         }
@@ -102,10 +123,241 @@ pub fn push_spans_from_line<'line>(line: &'line str, spans: &mut Vec<Span<'line>
     }
 }
 
+/// The number of columns taken by `line` once its control characters are
+/// replaced as by [`push_spans_from_line`].
+pub fn line_display_width(line: &str) -> usize {
+    line.chars()
+        .map(|c| match replace_control_character(c) {
+            Some(replacement) => replacement.width(),
+            None => c.width().unwrap_or_default(),
+        })
+        .sum()
+}
+
+/// The part of `span` in the byte range `range`.
+fn sub_span<'a>(span: &Span<'a>, range: Range<usize>) -> Span<'a> {
+    let content = match &span.content {
+        Cow::Borrowed(content) => Cow::Borrowed(&content[range]),
+        Cow::Owned(content) => Cow::Owned(content[range].to_owned()),
+    };
+    Span::styled(content, span.style)
+}
+
+/// Drop the first `num_columns` columns of `spans`. A wide character which
+/// would be cut in half is dropped entirely.
+fn skip_columns(spans: Vec<Span<'_>>, num_columns: usize) -> Vec<Span<'_>> {
+    let mut num_skipped = 0;
+    let mut result = Vec::new();
+    for span in spans {
+        if num_skipped >= num_columns {
+            result.push(span);
+            continue;
+        }
+        let start = span
+            .content
+            .char_indices()
+            .find(|(_idx, c)| {
+                let is_skipped = num_skipped >= num_columns;
+                num_skipped += c.width().unwrap_or_default();
+                is_skipped
+            })
+            .map(|(idx, _c)| idx);
+        if let Some(start) = start {
+            result.push(sub_span(&span, start..span.content.len()));
+        }
+    }
+    result
+}
+
+/// Split `spans` into rows at most `width` columns wide, without splitting
+/// characters. There's always at least one row.
+fn wrap_spans(spans: Vec<Span<'_>>, width: usize) -> Vec<Vec<Span<'_>>> {
+    let width = width.max(1);
+    let mut rows = vec![Vec::new()];
+    let mut row_width = 0;
+    for span in spans {
+        let mut piece_start = 0;
+        for (idx, c) in span.content.char_indices() {
+            let char_width = c.width().unwrap_or_default();
+            if row_width > 0 && row_width + char_width > width {
+                if idx > piece_start {
+                    rows.last_mut()
+                        .unwrap()
+                        .push(sub_span(&span, piece_start..idx));
+                }
+                rows.push(Vec::new());
+                piece_start = idx;
+                row_width = 0;
+            }
+            row_width += char_width;
+        }
+        if span.content.len() > piece_start {
+            rows.last_mut()
+                .unwrap()
+                .push(sub_span(&span, piece_start..span.content.len()));
+        }
+    }
+    rows
+}
+
+/// The number of rows that [`wrap_spans`] splits `line` into at the given
+/// `width`, once its control characters are replaced as by
+/// [`push_spans_from_line`].
+fn wrapped_num_rows(line: &str, width: usize) -> usize {
+    let width = width.max(1);
+    let mut num_rows = 1;
+    let mut row_width = 0;
+    let mut push_char = |c: char| {
+        let char_width = c.width().unwrap_or_default();
+        if row_width > 0 && row_width + char_width > width {
+            num_rows += 1;
+            row_width = 0;
+        }
+        row_width += char_width;
+    };
+    for c in line.chars() {
+        match replace_control_character(c) {
+            Some(replacement) => replacement.chars().for_each(&mut push_char),
+            None => push_char(c),
+        }
+    }
+    num_rows
+}
+
+/// Like [`push_spans_from_line`], but also styles the syntax highlighting
+/// tokens of the line, if any, emphasizes the changed parts of the line with
+/// `emphasized_style`, and highlights the parts of the line which match the
+/// search query, if any.
+fn push_spans_with_highlights<'line>(
+    line: &'line str,
+    highlighted_tokens: Option<&[HighlightedToken]>,
+    emphasized_ranges: &[Range<usize>],
+    emphasized_style: Style,
+    search_query: Option<&SearchQuery>,
+    theme: &Theme,
+    spans: &mut Vec<Span<'line>>,
+) {
+    let matches = match search_query {
+        Some(search_query) => search_query.find_matches(line),
+        None => Vec::new(),
+    };
+    let whole_line = [HighlightedToken {
+        style: Style::default(),
+        range: 0..line.len(),
+    }];
+    let tokens = highlighted_tokens.unwrap_or(&whole_line);
+
+    // Split the line at the boundaries of every token, emphasized range and
+    // search match, so that each piece is covered by at most one of each.
+    let mut boundaries: Vec<usize> = tokens
+        .iter()
+        .map(|token| &token.range)
+        .chain(emphasized_ranges)
+        .chain(&matches)
+        .flat_map(|range| [range.start, range.end])
+        .chain([0, line.len()])
+        .collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut token_idx = 0;
+    let mut emphasized_idx = 0;
+    let mut match_idx = 0;
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        while tokens
+            .get(token_idx)
+            .is_some_and(|token| token.range.end <= start)
+        {
+            token_idx += 1;
+        }
+        let token_style = match tokens.get(token_idx) {
+            Some(token) if token.range.start <= start => token.style,
+            Some(_) | None => Style::default(),
+        };
+        let is_emphasized = is_covered(emphasized_ranges, &mut emphasized_idx, start);
+        let is_match = is_covered(&matches, &mut match_idx, start);
+
+        let piece_spans_start = spans.len();
+        push_spans_from_line(&line[start..end], theme, spans);
+        for span in &mut spans[piece_spans_start..] {
+            span.style = token_style.patch(span.style);
+            if is_emphasized {
+                span.style = span.style.patch(emphasized_style);
+            }
+            if is_match {
+                span.style = span.style.patch(theme.search_match);
+            }
+        }
+    }
+}
+
+/// Whether `pos` is inside one of the sorted, non-overlapping `ranges`.
+/// `idx` is advanced past the ranges which end before `pos`, so that calling
+/// this with increasing positions takes linear time overall.
+fn is_covered(ranges: &[Range<usize>], idx: &mut usize, pos: usize) -> bool {
+    while ranges.get(*idx).is_some_and(|range| range.end <= pos) {
+        *idx += 1;
+    }
+    ranges.get(*idx).is_some_and(|range| range.start <= pos)
+}
+
 #[derive(Clone, Debug)]
 pub struct SectionLineView<'a> {
     pub line_key: LineKey,
     pub inner: SectionLineViewInner<'a>,
+    pub layout: LineLayout,
+    pub search_query: Option<&'a SearchQuery>,
+    pub highlighted_tokens: Option<&'a [HighlightedToken]>,
+    /// The parts of the line which differ from the corresponding added or
+    /// removed line.
+    pub emphasized_ranges: &'a [Range<usize>],
+    pub theme: &'a Theme,
+}
+
+impl SectionLineView<'_> {
+    /// The number of rows that the line takes up when drawn at `x`, without
+    /// drawing it.
+    pub fn num_rows(&self, viewport: &Viewport<ComponentId>, x: isize) -> usize {
+        let Self {
+            line_key: _,
+            inner,
+            layout,
+            search_query: _,
+            highlighted_tokens: _,
+            emphasized_ranges: _,
+            theme: _,
+        } = self;
+        match layout {
+            LineLayout::Scroll { offset_x: _ } => 1,
+            LineLayout::Wrap => {
+                let (text_x, line) = match inner {
+                    SectionLineViewInner::Unchanged { line, line_num } => (
+                        x + line_number_gutter(*line_num).width().unwrap_isize(),
+                        line,
+                    ),
+                    SectionLineViewInner::Changed {
+                        toggle_box,
+                        change_type: _,
+                        line,
+                    } => {
+                        // The toggle box, a space, and the `+`/`-` marker.
+                        let gutter_width = toggle_box.text().width() + 1 + 2;
+                        (x + gutter_width.unwrap_isize(), line)
+                    }
+                };
+                let text_width = (viewport.mask_rect().end_x() - text_x).clamp_into_usize();
+                wrapped_num_rows(line, text_width)
+            }
+        }
+    }
+}
+
+/// The line number shown before an unchanged line.
+fn line_number_gutter(line_num: usize) -> String {
+    // Pad the number in 5 columns because that will align the beginning of
+    // the actual text with the `+`/`-` of the changed lines.
+    format!("{line_num:5} ")
 }
 
 impl Component for SectionLineView<'_> {
@@ -116,24 +368,39 @@ impl Component for SectionLineView<'_> {
     }
 
     fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
+        let Self {
+            line_key: _,
+            inner,
+            layout,
+            search_query,
+            highlighted_tokens,
+            emphasized_ranges,
+            theme,
+        } = self;
+        let mask_rect = viewport.mask_rect();
         viewport.draw_blank(Rect {
-            x: viewport.mask_rect().x,
+            x: mask_rect.x,
             y,
-            width: viewport.mask_rect().width,
+            width: mask_rect.width,
             height: 1,
         });
 
-        match &self.inner {
+        // The line number or `+`/`-` marker, and the text of the line after
+        // it.
+        let (x, gutter, spans, line_style) = match inner {
             SectionLineViewInner::Unchanged { line, line_num } => {
-                // Pad the number in 5 columns because that will align the
-                // beginning of the actual text with the `+`/`-` of the changed
-                // lines.
-                let line_number = Span::raw(format!("{line_num:5} "));
-                let mut spans = vec![line_number];
-                push_spans_from_line(line, &mut spans);
-
-                const UI_UNCHANGED_STYLE: Style = Style::new().fg(Color::Gray).add_modifier(Modifier::DIM);
-                viewport.draw_text(x, y, Line::from(spans).style(UI_UNCHANGED_STYLE));
+                let line_number = Span::raw(line_number_gutter(*line_num));
+                let mut spans = Vec::new();
+                push_spans_with_highlights(
+                    line,
+                    *highlighted_tokens,
+                    &[],
+                    Style::default(),
+                    *search_query,
+                    theme,
+                    &mut spans,
+                );
+                (x, line_number, spans, theme.unchanged_line)
             }
 
             SectionLineViewInner::Changed {
@@ -144,15 +411,74 @@ impl Component for SectionLineView<'_> {
                 let toggle_box_rect = viewport.draw_component(x, y, toggle_box);
                 let x = toggle_box_rect.end_x() + 1;
 
-                let (change_type_text, changed_line_style) = match change_type {
-                    ChangeType::Added => ("+ ", Style::default().fg(Color::Green)),
-                    ChangeType::Removed => ("- ", Style::default().fg(Color::Red)),
+                let (change_type_text, changed_line_style, syntax_line_style, word_style) =
+                    match change_type {
+                        ChangeType::Added => (
+                            "+ ",
+                            theme.added_line,
+                            theme.syntax_added_line,
+                            theme.added_word,
+                        ),
+                        ChangeType::Removed => (
+                            "- ",
+                            theme.removed_line,
+                            theme.syntax_removed_line,
+                            theme.removed_word,
+                        ),
+                    };
+                // When syntax highlighting, the token colors replace the
+                // added/removed color, so only the `+`/`-` marker keeps it.
+                let line_style = match highlighted_tokens {
+                    Some(_) => syntax_line_style,
+                    None => changed_line_style,
                 };
 
-                let mut spans = vec![Span::raw(change_type_text)];
-                push_spans_from_line(line, &mut spans);
+                let mut spans = Vec::new();
+                push_spans_with_highlights(
+                    line,
+                    *highlighted_tokens,
+                    emphasized_ranges,
+                    word_style,
+                    *search_query,
+                    theme,
+                    &mut spans,
+                );
+                let change_type_marker = Span::styled(change_type_text, changed_line_style);
+                (x, change_type_marker, spans, line_style)
+            }
+        };
+
+        match layout {
+            LineLayout::Scroll { offset_x } => {
+                let mut line_spans = vec![gutter];
+                line_spans.extend(skip_columns(spans, *offset_x));
+                viewport.draw_text(x, y, Line::from(line_spans).style(line_style));
+            }
 
-                viewport.draw_text(x, y, Line::from(spans).style(changed_line_style));
+            LineLayout::Wrap => {
+                let gutter_width = gutter.width();
+                let text_x = x + gutter_width.unwrap_isize();
+                let text_width = (mask_rect.end_x() - text_x).clamp_into_usize();
+                // Right-align the marker with the `+`/`-` of the first row.
+                let wrap_marker =
+                    Span::styled(format!("{:>gutter_width$}", "↪ "), theme.wrap_marker);
+                for (row_idx, row) in wrap_spans(spans, text_width).into_iter().enumerate() {
+                    let y = y + row_idx.unwrap_isize();
+                    let row_gutter = if row_idx == 0 {
+                        gutter.clone()
+                    } else {
+                        viewport.draw_blank(Rect {
+                            x: mask_rect.x,
+                            y,
+                            width: mask_rect.width,
+                            height: 1,
+                        });
+                        wrap_marker.clone()
+                    };
+                    let mut line_spans = vec![row_gutter];
+                    line_spans.extend(row);
+                    viewport.draw_text(x, y, Line::from(line_spans).style(line_style));
+                }
             }
         }
     }
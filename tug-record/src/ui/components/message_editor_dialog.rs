@@ -0,0 +1,194 @@
+use crate::render::{Component, Viewport};
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::widgets::{truncate_start, Button};
+use crate::ui::components::ComponentId;
+use crate::ui::message_editor::{
+    wrap_line, MessageEditor, MessageTarget, SUBJECT_HARD_LIMIT, SUBJECT_SOFT_LIMIT,
+};
+use crate::ui::theme::Theme;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use unicode_width::UnicodeWidthStr;
+
+/// A dialog for editing a commit message or a review comment, with the text
+/// wrapped to the width of the dialog.
+#[derive(Clone, Debug)]
+pub struct MessageEditorDialog<'a> {
+    pub editor: &'a MessageEditor,
+    /// Where the comment being edited is attached, such as `src/main.rs:12`.
+    /// Only used when editing a comment.
+    pub comment_location: Option<String>,
+    pub theme: &'a Theme,
+}
+
+impl MessageEditorDialog<'_> {
+    /// The space taken by the border of the dialog and the margin around it.
+    const CHROME_WIDTH: usize = 6;
+
+    /// The space taken by the border, the subject line indicator or comment
+    /// location, the help line, and the margin around the dialog.
+    const CHROME_HEIGHT: usize = 10;
+
+    const MAX_TEXT_HEIGHT: usize = 20;
+
+    /// Lay out the message as rows of styled spans, including the cursor.
+    /// Also returns the index of the row containing the cursor.
+    fn text_rows(&self, width: usize) -> (Vec<Vec<Span<'_>>>, usize) {
+        let Self {
+            editor,
+            comment_location: _,
+            theme: _,
+        } = self;
+        let is_commit_message = match editor.target {
+            MessageTarget::Commit(_) => true,
+            MessageTarget::Comment(_) => false,
+        };
+        let text = editor.text();
+        let cursor = editor.cursor();
+        let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+
+        let mut rows = Vec::new();
+        let mut cursor_row_idx = 0;
+        let mut line_start = 0;
+        for (line_idx, line) in text.split('\n').enumerate() {
+            let style = if line_idx == 0 && is_commit_message {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let line_rows = wrap_line(line, width);
+            let num_line_rows = line_rows.len();
+            for (row_idx, row) in line_rows.into_iter().enumerate() {
+                let row = line_start + row.start..line_start + row.end;
+                // A cursor at the end of a row is drawn at the start of the
+                // next row, unless this is the last row of the line.
+                let is_last_row = row_idx + 1 == num_line_rows;
+                let has_cursor = row.contains(&cursor) || (is_last_row && cursor == row.end);
+                if !has_cursor {
+                    rows.push(vec![Span::styled(&text[row], style)]);
+                    continue;
+                }
+
+                cursor_row_idx = rows.len();
+                let after_cursor = &text[cursor..row.end];
+                let cursor_len = after_cursor.chars().next().map_or(0, char::len_utf8);
+                let cursor_text = match &after_cursor[..cursor_len] {
+                    "" => " ",
+                    cursor_text => cursor_text,
+                };
+                rows.push(vec![
+                    Span::styled(&text[row.start..cursor], style),
+                    Span::styled(cursor_text, style.patch(cursor_style)),
+                    Span::styled(&after_cursor[cursor_len..], style),
+                ]);
+            }
+            line_start += line.len() + 1;
+        }
+        (rows, cursor_row_idx)
+    }
+}
+
+impl Component for MessageEditorDialog<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::MessageEditorDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self {
+            editor,
+            comment_location,
+            theme,
+        } = self;
+        let viewport_rect = viewport.rect();
+        let width = viewport_rect
+            .width
+            .saturating_sub(Self::CHROME_WIDTH)
+            .clamp(1, SUBJECT_HARD_LIMIT);
+        let height = viewport_rect
+            .height
+            .saturating_sub(Self::CHROME_HEIGHT)
+            .clamp(1, Self::MAX_TEXT_HEIGHT);
+
+        let (title, header, help) = match editor.target {
+            MessageTarget::Commit(commit_idx) => {
+                let subject_len = editor.subject_len();
+                let subject_style = if subject_len > SUBJECT_HARD_LIMIT {
+                    theme.subject_length_error
+                } else if subject_len > SUBJECT_SOFT_LIMIT {
+                    theme.subject_length_warning
+                } else {
+                    Style::default()
+                };
+                (
+                    format!("Commit message for commit {}", commit_idx + 1),
+                    Span::styled(
+                        format!("Subject: {subject_len}/{SUBJECT_SOFT_LIMIT} characters"),
+                        subject_style,
+                    ),
+                    "^s save • Esc cancel • ^o external editor",
+                )
+            }
+            MessageTarget::Comment(_) => {
+                let location = comment_location.as_deref().unwrap_or_default();
+                (
+                    "Comment".to_owned(),
+                    Span::styled(truncate_start(location, width), theme.file_path),
+                    "^s save • Esc cancel • save empty to delete",
+                )
+            }
+        };
+
+        // Pad every line to the same width so that the dialog doesn't change
+        // size while typing.
+        let pad = |spans| padded_line(spans, width);
+        let mut lines = vec![pad(vec![header]), pad(Vec::new())];
+
+        // Scroll the text so that the cursor is visible.
+        let (rows, cursor_row_idx) = self.text_rows(width);
+        let first_row_idx = (cursor_row_idx + 1).saturating_sub(height);
+        let visible_rows = rows
+            .into_iter()
+            .skip(first_row_idx)
+            .chain(std::iter::repeat_with(Vec::new))
+            .take(height);
+        lines.extend(visible_rows.map(pad));
+
+        lines.push(pad(Vec::new()));
+        lines.push(pad(vec![Span::styled(
+            help,
+            Style::default().add_modifier(Modifier::DIM),
+        )]));
+        let body = Text::from(lines);
+
+        let save_button = Button {
+            id: ComponentId::MessageEditorSaveButton,
+            label: Cow::Borrowed("Save"),
+            style: Style::default(),
+            is_focused: true,
+        };
+        let cancel_button = Button {
+            id: ComponentId::MessageEditorCancelButton,
+            label: Cow::Borrowed("Cancel"),
+            style: Style::default(),
+            is_focused: false,
+        };
+        let buttons = [save_button, cancel_button];
+        let dialog = Dialog {
+            id: self.id(),
+            title: Cow::Owned(title),
+            body: Cow::Borrowed(&body),
+            buttons: &buttons,
+        };
+        viewport.draw_component(0, 0, &dialog);
+    }
+}
+
+fn padded_line(mut spans: Vec<Span<'_>>, width: usize) -> Line<'_> {
+    let line_width: usize = spans.iter().map(|span| span.content.width()).sum();
+    spans.push(Span::raw(" ".repeat(width.saturating_sub(line_width))));
+    Line::from(spans)
+}
@@ -1,25 +1,50 @@
-use crate::ui::components::{app::SelectionKey, file::FileKey};
+use crate::ui::components::{app::SelectionKey, file::FileKey, section::SectionKey};
 
 pub mod app;
+pub mod comment_list_dialog;
 pub mod commit_message_view;
 pub mod commit_view;
+pub mod confirm_dialog;
 pub mod dialog;
+pub mod dir;
 pub mod file;
 pub mod help_dialog;
 pub mod line;
+pub mod message_editor_dialog;
+pub mod search_bar;
 pub mod section;
+pub mod session_dialog;
+pub mod status_bar;
 pub mod widgets;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub enum ComponentId {
     App,
     AppFiles,
+    CommitViews,
     CommitMessageView,
     CommitEditMessageButton(usize),
     FileViewHeader(FileKey),
     SelectableItem(SelectionKey),
     ToggleBox(SelectionKey),
     ExpandBox(SelectionKey),
+    ContextEllipsis(SectionKey),
+    FileTreeSidebar,
+    SidebarRow(SelectionKey),
+    SidebarToggleBox(SelectionKey),
     HelpDialog,
     HelpDialogQuitButton,
+    MessageEditorDialog,
+    MessageEditorSaveButton,
+    MessageEditorCancelButton,
+    SessionDialog,
+    SessionRestoreButton,
+    SessionDiscardButton,
+    ConfirmDialog,
+    ConfirmButton,
+    ConfirmBackButton,
+    CommentListDialog,
+    CommentListCloseButton,
+    SearchBar,
+    StatusBar,
 }
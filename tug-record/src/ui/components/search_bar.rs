@@ -0,0 +1,44 @@
+use crate::render::{Component, Rect, Viewport};
+use crate::ui::components::ComponentId;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::fmt::Debug;
+
+/// The prompt shown at the bottom of the screen while typing a search query
+/// or a file filter.
+#[derive(Clone, Debug)]
+pub struct SearchBar<'a> {
+    /// Shown before the text, such as `/` for a search.
+    pub prefix: &'a str,
+    pub text: &'a str,
+}
+
+impl Component for SearchBar<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::SearchBar
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self { prefix, text } = self;
+        let viewport_rect = viewport.rect();
+        let rect = Rect {
+            x: viewport_rect.x,
+            y: viewport_rect.end_y() - 1,
+            width: viewport_rect.width,
+            height: 1,
+        };
+        viewport.draw_blank(rect);
+        viewport.draw_text(
+            rect.x,
+            rect.y,
+            Line::from(vec![
+                Span::raw(*prefix),
+                Span::raw(*text),
+                // Cursor.
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ]),
+        );
+    }
+}
@@ -1,49 +1,167 @@
-use std::cmp::min;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::rc::Rc;
 
-use ratatui::{
-    style::{Color, Modifier, Style},
-    text::Span,
-};
+use ratatui::text::Span;
 
 use crate::{
-    render::{Component, Rect, Viewport},
+    render::{Component, Mask, Rect, Viewport},
     ui::components::{
         app::SelectionKey,
-        line::{LineKey, SectionLineView, SectionLineViewInner},
-        widgets::{highlight_rect, TristateBox, TristateIconStyle},
+        line::{LineKey, LineLayout, SectionLineView, SectionLineViewInner},
+        widgets::{draw_comment_marker, highlight_rect, TristateBox, TristateIconStyle},
         ComponentId,
     },
-    util::UsizeExt,
+    ui::highlight::FileHighlights,
+    ui::search::SearchQuery,
+    ui::theme::Theme,
+    ui::word_diff::{pair_lines, SectionWordDiff},
+    util::{IsizeExt, UsizeExt},
     FileMode, Section, SectionChangedLine, Tristate,
 };
 
-pub const NUM_CONTEXT_LINES: usize = 4;
+/// The number of unchanged lines shown on each side of a changed section,
+/// unless configured otherwise.
+pub const DEFAULT_NUM_CONTEXT_LINES: usize = 4;
+
+/// The number of additional unchanged lines revealed by each request for more
+/// context.
+pub const NUM_CONTEXT_LINES_PER_EXPANSION: usize = 10;
+
+/// The number of columns between the two sides of the split view.
+const SPLIT_MARGIN: usize = 1;
+
+/// How the lines of a section are laid out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiffViewMode {
+    /// Removed and added lines are shown in a single column.
+    Unified,
+
+    /// Removed lines are shown in a left column and added lines in a right
+    /// column, with unchanged lines mirrored in both.
+    Split,
+}
 
 #[derive(Clone, Debug)]
 pub enum SectionSelection {
     SectionHeader,
-    ChangedLine(usize),
+    Line(usize),
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SectionKey {
     pub commit_idx: usize,
     pub file_idx: usize,
     pub section_idx: usize,
 }
 
+/// The number of lines of an unchanged section which the user has asked to
+/// see, beyond the default amount of context.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ContextExpansion {
+    /// Additional lines shown at the start of the section, i.e. below the
+    /// preceding changed section.
+    pub num_leading_lines: usize,
+
+    /// Additional lines shown at the end of the section, i.e. above the
+    /// following changed section.
+    pub num_trailing_lines: usize,
+}
+
+impl ContextExpansion {
+    /// An expansion which reveals the whole section, however long it is.
+    pub fn whole() -> Self {
+        Self {
+            num_leading_lines: usize::MAX,
+            num_trailing_lines: usize::MAX,
+        }
+    }
+
+    /// Combine two expansions of the same section.
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            num_leading_lines: self
+                .num_leading_lines
+                .saturating_add(other.num_leading_lines),
+            num_trailing_lines: self
+                .num_trailing_lines
+                .saturating_add(other.num_trailing_lines),
+        }
+    }
+}
+
+/// The indices of the lines of the unchanged section at `section_idx` which
+/// are shown as context: those at its start, and those at its end. If there's
+/// no gap between the two ranges, the whole section is shown.
+pub fn context_line_ranges(
+    section_idx: usize,
+    total_num_sections: usize,
+    num_lines: usize,
+    num_context_lines: usize,
+    context_expansion: ContextExpansion,
+) -> (Range<usize>, Range<usize>) {
+    // The lines before the first changed section and after the last one
+    // aren't next to a change on the other side, so there's no context to
+    // show there.
+    let is_first_section = section_idx == 0;
+    let is_last_section = section_idx + 1 == total_num_sections;
+    let ContextExpansion {
+        num_leading_lines,
+        num_trailing_lines,
+    } = context_expansion;
+    let num_leading_lines = if is_first_section {
+        0
+    } else {
+        num_context_lines.saturating_add(num_leading_lines)
+    };
+    let num_trailing_lines = if is_last_section {
+        0
+    } else {
+        num_context_lines.saturating_add(num_trailing_lines)
+    };
+
+    if num_leading_lines.saturating_add(num_trailing_lines) >= num_lines {
+        (0..num_lines, num_lines..num_lines)
+    } else {
+        (
+            0..num_leading_lines,
+            num_lines - num_trailing_lines..num_lines,
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SectionView<'a> {
     pub is_read_only: bool,
     pub section_key: SectionKey,
-    pub toggle_box: TristateBox<ComponentId>,
-    pub expand_box: TristateBox<ComponentId>,
+    pub toggle_box: TristateBox<'a, ComponentId>,
+    pub expand_box: TristateBox<'a, ComponentId>,
     pub selection: Option<SectionSelection>,
+    /// The indices of the lines in this section which are in the visual range.
+    pub visual_line_range: Range<usize>,
     pub total_num_sections: usize,
     pub editable_section_num: usize,
     pub total_num_editable_sections: usize,
     pub section: &'a Section<'a>,
     pub line_start_num: usize,
+    pub new_line_start_num: usize,
+    pub diff_view_mode: DiffViewMode,
+    pub line_layout: LineLayout,
+    /// The number of unchanged lines to show on each side of a changed
+    /// section.
+    pub num_context_lines: usize,
+    /// Additional unchanged lines to show, if this is an unchanged section.
+    pub context_expansion: ContextExpansion,
+    pub num_commits: usize,
+    pub search_query: Option<&'a SearchQuery>,
+    pub highlights: Option<Rc<FileHighlights>>,
+    pub word_diff: Option<Rc<SectionWordDiff>>,
+    /// Whether the section has a review comment.
+    pub is_commented: bool,
+    /// The indices of the lines in this section which have a review comment.
+    pub commented_line_idxs: HashSet<usize>,
+    pub theme: &'a Theme,
 }
 
 impl SectionView<'_> {
@@ -57,6 +175,241 @@ impl SectionView<'_> {
             Tristate::True => true,
         }
     }
+
+    /// The x-coordinates of the left and right columns of the split view, and
+    /// the width of each, when lines start at `x`.
+    fn split_columns(viewport: &Viewport<ComponentId>, x: isize) -> ([isize; 2], usize) {
+        let total_width = (viewport.mask_rect().end_x() - x).clamp_into_usize();
+        let column_width = total_width.saturating_sub(SPLIT_MARGIN) / 2;
+        let right_x = x + (column_width + SPLIT_MARGIN).unwrap_isize();
+        ([x, right_x], column_width)
+    }
+
+    /// Draw `line_view` at `(x, y)`. If it's far outside of the viewport and
+    /// isn't selected, then only the rows that it would take up are reserved,
+    /// so that very long sections can be drawn quickly.
+    fn draw_line_view(
+        viewport: &mut Viewport<ComponentId>,
+        x: isize,
+        y: isize,
+        line_view: &SectionLineView,
+        is_selected: bool,
+    ) -> Rect {
+        let num_rows = line_view.num_rows(viewport, x);
+        if is_selected || viewport.is_nearly_visible(y, num_rows) {
+            return viewport.draw_component(x, y, line_view);
+        }
+        let mask_rect = viewport.mask_rect();
+        let line_rect = Rect {
+            x: mask_rect.x,
+            y,
+            width: mask_rect.width,
+            height: num_rows,
+        };
+        viewport.reserve_rect(line_rect);
+        line_rect
+    }
+
+    /// Draw `line_view` clipped to the column of the split view starting at
+    /// `column_x`.
+    fn draw_in_column(
+        viewport: &mut Viewport<ComponentId>,
+        column_x: isize,
+        column_width: usize,
+        y: isize,
+        line_view: &SectionLineView,
+        is_selected: bool,
+    ) -> Rect {
+        let mask = viewport.mask();
+        let column_mask = Mask {
+            x: column_x,
+            y: mask.y,
+            width: Some(column_width),
+            height: mask.height,
+        };
+        viewport.with_mask(column_mask, |viewport| {
+            Self::draw_line_view(viewport, column_x, y, line_view, is_selected)
+        })
+    }
+
+    /// Draw the unchanged line at `line_idx`. In split mode, it's mirrored in
+    /// both columns. Returns the number of rows taken, which is more than one
+    /// if the line is wrapped.
+    fn draw_unchanged_line(
+        &self,
+        viewport: &mut Viewport<ComponentId>,
+        x: isize,
+        y: isize,
+        line_idx: usize,
+        line: &str,
+    ) -> usize {
+        let SectionKey {
+            commit_idx,
+            file_idx,
+            section_idx,
+        } = self.section_key;
+        let is_focused = match self.selection {
+            Some(SectionSelection::Line(selected_line_idx)) => line_idx == selected_line_idx,
+            Some(SectionSelection::SectionHeader) | None => false,
+        };
+        let make_line_view = |line_num| SectionLineView {
+            line_key: LineKey {
+                commit_idx,
+                file_idx,
+                section_idx,
+                line_idx,
+            },
+            inner: SectionLineViewInner::Unchanged { line, line_num },
+            layout: self.line_layout,
+            search_query: self.search_query,
+            highlighted_tokens: self
+                .highlights
+                .as_ref()
+                .and_then(|highlights| highlights.line(section_idx, line_idx)),
+            emphasized_ranges: &[],
+            theme: self.theme,
+        };
+        let num_rows = match self.diff_view_mode {
+            DiffViewMode::Unified => {
+                let line_view = make_line_view(self.line_start_num + line_idx);
+                Self::draw_line_view(viewport, x, y, &line_view, is_focused).height
+            }
+            DiffViewMode::Split => {
+                let ([left_x, right_x], column_width) = Self::split_columns(viewport, x);
+                let left_line_view = make_line_view(self.line_start_num + line_idx);
+                let left_rect = Self::draw_in_column(
+                    viewport,
+                    left_x,
+                    column_width,
+                    y,
+                    &left_line_view,
+                    is_focused,
+                );
+                let right_line_view = make_line_view(self.new_line_start_num + line_idx);
+                let right_rect = Self::draw_in_column(
+                    viewport,
+                    right_x,
+                    column_width,
+                    y,
+                    &right_line_view,
+                    is_focused,
+                );
+                left_rect.height.max(right_rect.height)
+            }
+        };
+        if self.commented_line_idxs.contains(&line_idx) {
+            draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, self.theme);
+        }
+        if is_focused {
+            let mask_rect = viewport.mask_rect();
+            highlight_rect(
+                viewport,
+                Rect {
+                    x: mask_rect.x,
+                    y,
+                    width: mask_rect.width,
+                    height: num_rows,
+                },
+                self.theme,
+            );
+        }
+        num_rows
+    }
+
+    /// Draw the changed line at `line_idx`. If `column_width` is set, the line
+    /// is clipped to a column of the split view starting at `x`. Returns the
+    /// number of rows taken, which is more than one if the line is wrapped.
+    fn draw_changed_line(
+        &self,
+        viewport: &mut Viewport<ComponentId>,
+        x: isize,
+        y: isize,
+        line_idx: usize,
+        changed_line: &SectionChangedLine,
+        column_width: Option<usize>,
+    ) -> usize {
+        let SectionKey {
+            commit_idx,
+            file_idx,
+            section_idx,
+        } = self.section_key;
+        let is_checked = changed_line.assigned_commit_idx(self.num_commits) == commit_idx;
+        let SectionChangedLine {
+            is_checked: _,
+            commit_idx: _,
+            change_type,
+            line,
+        } = changed_line;
+        let is_focused = match self.selection {
+            Some(SectionSelection::Line(selected_line_idx)) => line_idx == selected_line_idx,
+            Some(SectionSelection::SectionHeader) | None => false,
+        };
+        let is_in_visual_range = self.visual_line_range.contains(&line_idx);
+        let line_key = LineKey {
+            commit_idx,
+            file_idx,
+            section_idx,
+            line_idx,
+        };
+        let toggle_box = TristateBox {
+            id: ComponentId::ToggleBox(SelectionKey::Line(line_key)),
+            icon_style: TristateIconStyle::Check,
+            tristate: Tristate::from(is_checked),
+            is_read_only: self.is_read_only,
+            theme: self.theme,
+        };
+        let line_view = SectionLineView {
+            line_key,
+            inner: SectionLineViewInner::Changed {
+                toggle_box,
+                change_type: *change_type,
+                line: line.as_ref(),
+            },
+            layout: self.line_layout,
+            search_query: self.search_query,
+            highlighted_tokens: self
+                .highlights
+                .as_ref()
+                .and_then(|highlights| highlights.line(section_idx, line_idx)),
+            emphasized_ranges: self
+                .word_diff
+                .as_ref()
+                .map_or(&[], |word_diff| word_diff.line(line_idx)),
+            theme: self.theme,
+        };
+        let (line_rect, highlight_rect_x_width) = match column_width {
+            None => {
+                let line_rect = Self::draw_line_view(viewport, x, y, &line_view, is_focused);
+                (
+                    line_rect,
+                    (viewport.mask_rect().x, viewport.mask_rect().width),
+                )
+            }
+            Some(column_width) => {
+                let line_rect =
+                    Self::draw_in_column(viewport, x, column_width, y, &line_view, is_focused);
+                (line_rect, (x, column_width))
+            }
+        };
+        if self.commented_line_idxs.contains(&line_idx) {
+            let (x, width) = highlight_rect_x_width;
+            draw_comment_marker(viewport, x + width.unwrap_isize(), y, self.theme);
+        }
+        if is_focused || is_in_visual_range {
+            let (x, width) = highlight_rect_x_width;
+            highlight_rect(
+                viewport,
+                Rect {
+                    x,
+                    y,
+                    width,
+                    height: line_rect.height,
+                },
+                self.theme,
+            );
+        }
+        line_rect.height
+    }
 }
 
 // ... (imports and struct definitions remain the same) ...
@@ -71,16 +424,29 @@ impl Component for SectionView<'_> {
 
     fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
         let Self {
-            is_read_only,
+            is_read_only: _,
             section_key,
             toggle_box,
             expand_box,
             selection,
+            visual_line_range: _,
             total_num_sections,
             editable_section_num,
             total_num_editable_sections,
             section,
-            line_start_num,
+            line_start_num: _,
+            new_line_start_num: _,
+            diff_view_mode,
+            line_layout: _,
+            num_context_lines,
+            context_expansion,
+            num_commits: _,
+            search_query: _,
+            highlights: _,
+            word_diff: _,
+            is_commented,
+            commented_line_idxs: _,
+            theme,
         } = self;
         viewport.draw_blank(Rect {
             x,
@@ -90,108 +456,57 @@ impl Component for SectionView<'_> {
         });
 
         let SectionKey {
-            commit_idx,
-            file_idx,
+            commit_idx: _,
+            file_idx: _,
             section_idx,
         } = *section_key;
         match section {
             Section::Unchanged { lines } => {
-                // ... (this entire block remains unchanged)
                 if lines.is_empty() {
                     return;
                 }
 
-                let lines: Vec<_> = lines.iter().enumerate().collect();
-                let is_first_section = section_idx == 0;
-                let is_last_section = section_idx + 1 == *total_num_sections;
-                let before_ellipsis_lines = &lines[..min(NUM_CONTEXT_LINES, lines.len())];
-                let after_ellipsis_lines = &lines[lines.len().saturating_sub(NUM_CONTEXT_LINES)..];
-
-                match (before_ellipsis_lines, after_ellipsis_lines) {
-                    ([.., (last_before_idx, _)], [(first_after_idx, _), ..])
-                        if *last_before_idx + 1 >= *first_after_idx
-                            && !is_first_section
-                            && !is_last_section =>
-                    {
-                        let first_before_idx = before_ellipsis_lines.first().unwrap().0;
-                        let last_after_idx = after_ellipsis_lines.last().unwrap().0;
-                        let overlapped_lines = &lines[first_before_idx..=last_after_idx];
-                        let overlapped_lines = if is_first_section {
-                            &overlapped_lines
-                                [overlapped_lines.len().saturating_sub(NUM_CONTEXT_LINES)..]
-                        } else if is_last_section {
-                            &overlapped_lines[..lines.len().min(NUM_CONTEXT_LINES)]
-                        } else {
-                            overlapped_lines
-                        };
-                        for (dy, (line_idx, line)) in overlapped_lines.iter().enumerate() {
-                            let line_view = SectionLineView {
-                                line_key: LineKey {
-                                    commit_idx,
-                                    file_idx,
-                                    section_idx,
-                                    line_idx: *line_idx,
-                                },
-                                inner: SectionLineViewInner::Unchanged {
-                                    line: line.as_ref(),
-                                    line_num: line_start_num + line_idx,
-                                },
-                            };
-                            viewport.draw_component(x + 2, y + dy.unwrap_isize(), &line_view);
-                        }
-                        return;
-                    }
-                    _ => {}
-                };
-
+                let (leading_line_idxs, trailing_line_idxs) = context_line_ranges(
+                    section_idx,
+                    *total_num_sections,
+                    lines.len(),
+                    *num_context_lines,
+                    *context_expansion,
+                );
                 let mut dy = 0;
-                if !is_first_section {
-                    for (line_idx, line) in before_ellipsis_lines {
-                        let line_view = SectionLineView {
-                            line_key: LineKey {
-                                commit_idx,
-                                file_idx,
-                                section_idx,
-                                line_idx: *line_idx,
-                            },
-                            inner: SectionLineViewInner::Unchanged {
-                                line: line.as_ref(),
-                                line_num: line_start_num + line_idx,
-                            },
-                        };
-                        viewport.draw_component(x + 2, y + dy, &line_view);
-                        dy += 1;
-                    }
-                }
-
-                let should_render_ellipsis = lines.len() > NUM_CONTEXT_LINES;
-                if should_render_ellipsis {
-                    let ellipsis = "\u{22EE}";
-                    viewport.draw_span(
-                        x + 6, // align with line numbering
+                for line_idx in leading_line_idxs.clone() {
+                    let num_rows = self.draw_unchanged_line(
+                        viewport,
+                        x + 2,
                         y + dy,
-                        &Span::styled(ellipsis, Style::default().add_modifier(Modifier::DIM)),
+                        line_idx,
+                        &lines[line_idx],
                     );
+                    dy += num_rows.unwrap_isize();
+                }
+
+                if leading_line_idxs.end < trailing_line_idxs.start {
+                    let ellipsis = ContextEllipsis {
+                        section_key: *section_key,
+                        diff_view_mode: *diff_view_mode,
+                        theme,
+                    };
+                    viewport.draw_component(x + 2, y + dy, &ellipsis);
                     dy += 1;
                 }
 
-                if !is_last_section {
-                    for (line_idx, line) in after_ellipsis_lines {
-                        let line_view = SectionLineView {
-                            line_key: LineKey {
-                                commit_idx,
-                                file_idx,
-                                section_idx,
-                                line_idx: *line_idx,
-                            },
-                            inner: SectionLineViewInner::Unchanged {
-                                line: line.as_ref(),
-                                line_num: line_start_num + line_idx,
-                            },
-                        };
-                        viewport.draw_component(x + 2, y + dy, &line_view);
-                        dy += 1;
-                    }
+                for line_idx in trailing_line_idxs {
+                    let num_rows = self.draw_unchanged_line(
+                        viewport,
+                        x + 2,
+                        y + dy,
+                        line_idx,
+                        &lines[line_idx],
+                    );
+                    dy += num_rows.unwrap_isize();
+                }
+                if *is_commented {
+                    draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, theme);
                 }
             }
 
@@ -214,9 +529,12 @@ impl Component for SectionView<'_> {
                     Span::styled(
                         format!("Section {editable_section_num}/{total_num_editable_sections}"),
                         // Use a distinct color for hunk headers.
-                        Style::default().fg(Color::LightMagenta),
+                        theme.section_header,
                     ),
                 );
+                if *is_commented {
+                    draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, theme);
+                }
 
                 match selection {
                     Some(SectionSelection::SectionHeader) => {
@@ -228,82 +546,63 @@ impl Component for SectionView<'_> {
                                 width: viewport.mask_rect().width,
                                 height: 1,
                             },
+                            theme,
                         );
                     }
-                    Some(SectionSelection::ChangedLine(_)) | None => {}
+                    Some(SectionSelection::Line(_)) | None => {}
                 }
 
                 if self.is_expanded() {
                     // Draw changed lines.
-                    let y = y + 1;
-                    for (line_idx, line) in lines.iter().enumerate() {
-                        let SectionChangedLine {
-                            is_checked,
-                            change_type,
-                            line,
-                        } = line;
-                        let is_focused = match selection {
-                            Some(SectionSelection::ChangedLine(selected_line_idx)) => {
-                                line_idx == *selected_line_idx
+                    let x = x + 2;
+                    let mut y = y + 1;
+                    match diff_view_mode {
+                        DiffViewMode::Unified => {
+                            for (line_idx, line) in lines.iter().enumerate() {
+                                let num_rows =
+                                    self.draw_changed_line(viewport, x, y, line_idx, line, None);
+                                y += num_rows.unwrap_isize();
+                            }
+                        }
+                        DiffViewMode::Split => {
+                            let (column_xs, column_width) = Self::split_columns(viewport, x);
+                            for (removed_line_idx, added_line_idx) in pair_lines(lines) {
+                                // The row is as tall as the taller of its two
+                                // lines.
+                                let mut num_rows = 1;
+                                for (column_x, line_idx) in column_xs
+                                    .into_iter()
+                                    .zip([removed_line_idx, added_line_idx])
+                                {
+                                    if let Some(line_idx) = line_idx {
+                                        num_rows = num_rows.max(self.draw_changed_line(
+                                            viewport,
+                                            column_x,
+                                            y,
+                                            line_idx,
+                                            &lines[line_idx],
+                                            Some(column_width),
+                                        ));
+                                    }
+                                }
+                                y += num_rows.unwrap_isize();
                             }
-                            Some(SectionSelection::SectionHeader) | None => false,
-                        };
-                        let line_key = LineKey {
-                            commit_idx,
-                            file_idx,
-                            section_idx,
-                            line_idx,
-                        };
-                        let toggle_box = TristateBox {
-                            id: ComponentId::ToggleBox(SelectionKey::Line(line_key)),
-                            icon_style: TristateIconStyle::Check,
-                            tristate: Tristate::from(*is_checked),
-                            is_read_only: *is_read_only,
-                        };
-                        let line_view = SectionLineView {
-                            line_key,
-                            inner: SectionLineViewInner::Changed {
-                                toggle_box,
-                                change_type: *change_type,
-                                line: line.as_ref(),
-                            },
-                        };
-                        let y = y + line_idx.unwrap_isize();
-                        viewport.draw_component(x + 2, y, &line_view);
-                        if is_focused {
-                            highlight_rect(
-                                viewport,
-                                Rect {
-                                    x: viewport.mask_rect().x,
-                                    y,
-                                    width: viewport.mask_rect().width,
-                                    height: 1,
-                                },
-                            );
                         }
                     }
                 }
             }
 
             // ... (Section::FileMode and Section::Binary remain unchanged) ...
-            Section::FileMode { is_checked, mode } => {
+            Section::FileMode {
+                is_checked: _,
+                commit_idx: _,
+                mode,
+            } => {
                 let is_focused = match selection {
                     Some(SectionSelection::SectionHeader) => true,
-                    Some(SectionSelection::ChangedLine(_)) | None => false,
-                };
-                let section_key = SectionKey {
-                    commit_idx,
-                    file_idx,
-                    section_idx,
-                };
-                let selection_key = SelectionKey::Section(section_key);
-                let toggle_box = TristateBox {
-                    id: ComponentId::ToggleBox(selection_key),
-                    icon_style: TristateIconStyle::Check,
-                    tristate: Tristate::from(*is_checked),
-                    is_read_only: *is_read_only,
+                    Some(SectionSelection::Line(_)) | None => false,
                 };
-                let toggle_box_rect = viewport.draw_component(x, y, &toggle_box);
+                let toggle_box_rect = viewport.draw_component(x, y, toggle_box);
                 let x = x + toggle_box_rect.width.unwrap_isize() + 1;
 
                 let text = match mode {
@@ -313,7 +612,10 @@ impl Component for SectionView<'_> {
                     FileMode::Absent => "File deleted".to_owned(),
                 };
 
-                viewport.draw_text(x, y, Span::styled(text, Style::default().fg(Color::Magenta)));
+                viewport.draw_text(x, y, Span::styled(text, theme.section_description));
+                if *is_commented {
+                    draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, theme);
+                }
                 if is_focused {
                     highlight_rect(
                         viewport,
@@ -323,31 +625,22 @@ impl Component for SectionView<'_> {
                             width: viewport.mask_rect().width,
                             height: 1,
                         },
+                        theme,
                     );
                 }
             }
 
             Section::Binary {
-                is_checked,
+                is_checked: _,
+                commit_idx: _,
                 old_description,
                 new_description,
             } => {
                 let is_focused = match selection {
                     Some(SectionSelection::SectionHeader) => true,
-                    Some(SectionSelection::ChangedLine(_)) | None => false,
-                };
-                let section_key = SectionKey {
-                    commit_idx,
-                    file_idx,
-                    section_idx,
-                };
-                let toggle_box = TristateBox {
-                    id: ComponentId::ToggleBox(SelectionKey::Section(section_key)),
-                    icon_style: TristateIconStyle::Check,
-                    tristate: Tristate::from(*is_checked),
-                    is_read_only: *is_read_only,
+                    Some(SectionSelection::Line(_)) | None => false,
                 };
-                let toggle_box_rect = viewport.draw_component(x, y, &toggle_box);
+                let toggle_box_rect = viewport.draw_component(x, y, toggle_box);
                 let x = x + toggle_box_rect.width.unwrap_isize() + 1;
 
                 let text = {
@@ -367,7 +660,10 @@ impl Component for SectionView<'_> {
                     result.push(description.join(" -> "));
                     format!("({})", result.join(" "))
                 };
-                viewport.draw_text(x, y, Span::styled(text, Style::default().fg(Color::Magenta)));
+                viewport.draw_text(x, y, Span::styled(text, theme.section_description));
+                if *is_commented {
+                    draw_comment_marker(viewport, viewport.mask_rect().end_x(), y, theme);
+                }
 
                 if is_focused {
                     highlight_rect(
@@ -378,9 +674,57 @@ impl Component for SectionView<'_> {
                             width: viewport.mask_rect().width,
                             height: 1,
                         },
+                        theme,
                     );
                 }
             }
         }
     }
 }
+
+/// The marker for the lines of an unchanged section which are hidden.
+/// Clicking it reveals the whole section.
+#[derive(Clone, Debug)]
+struct ContextEllipsis<'a> {
+    section_key: SectionKey,
+    diff_view_mode: DiffViewMode,
+    theme: &'a Theme,
+}
+
+impl Component for ContextEllipsis<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::ContextEllipsis(self.section_key)
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
+        let Self {
+            section_key: _,
+            diff_view_mode,
+            theme,
+        } = self;
+        // Fill the whole row so that it can be clicked anywhere.
+        viewport.draw_blank(Rect {
+            x,
+            y,
+            width: (viewport.mask_rect().end_x() - x).clamp_into_usize(),
+            height: 1,
+        });
+
+        let ellipsis = Span::styled("\u{22EE}", theme.ellipsis);
+        // Align with the line numbers.
+        let ellipsis_offset = 4;
+        match diff_view_mode {
+            DiffViewMode::Unified => {
+                viewport.draw_span(x + ellipsis_offset, y, &ellipsis);
+            }
+            DiffViewMode::Split => {
+                let (column_xs, _column_width) = SectionView::split_columns(viewport, x);
+                for column_x in column_xs {
+                    viewport.draw_span(column_x + ellipsis_offset, y, &ellipsis);
+                }
+            }
+        }
+    }
+}
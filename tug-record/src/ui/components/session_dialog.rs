@@ -0,0 +1,71 @@
+use crate::render::{Component, Viewport};
+use crate::ui::components::dialog::Dialog;
+use crate::ui::components::widgets::Button;
+use crate::ui::components::ComponentId;
+use crate::ui::session::SessionPrompt;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+/// A dialog offering to restore the selection from an interrupted session.
+#[derive(Clone, Debug)]
+pub struct SessionDialog<'a> {
+    pub prompt: &'a SessionPrompt,
+}
+
+impl Component for SessionDialog<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::SessionDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self { prompt } = self;
+        let SessionPrompt {
+            session: _,
+            is_exact,
+            num_checked,
+            num_changes,
+        } = prompt;
+        let mut lines = vec![Line::from(format!(
+            "An interrupted session had {num_checked} of {num_changes} changes selected."
+        ))];
+        if !is_exact {
+            lines.extend([
+                Line::from("The changes have shifted since then, so the selection"),
+                Line::from("will be matched up by the contents of each change."),
+            ]);
+        }
+        lines.extend([
+            Line::from(""),
+            Line::from(Span::styled(
+                "Enter restore • Esc discard",
+                Style::default().add_modifier(Modifier::DIM),
+            )),
+        ]);
+        let body = Text::from(lines);
+
+        let restore_button = Button {
+            id: ComponentId::SessionRestoreButton,
+            label: Cow::Borrowed("Restore"),
+            style: Style::default(),
+            is_focused: true,
+        };
+        let discard_button = Button {
+            id: ComponentId::SessionDiscardButton,
+            label: Cow::Borrowed("Discard"),
+            style: Style::default(),
+            is_focused: false,
+        };
+        let buttons = [restore_button, discard_button];
+        let dialog = Dialog {
+            id: self.id(),
+            title: Cow::Borrowed("Restore selection?"),
+            body: Cow::Borrowed(&body),
+            buttons: &buttons,
+        };
+        viewport.draw_component(0, 0, &dialog);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::render::{Component, Rect, Viewport};
+use crate::ui::components::commit_message_view::CommitViewMode;
+use crate::ui::components::line::LineLayout;
+use crate::ui::components::section::DiffViewMode;
+use crate::ui::components::ComponentId;
+use crate::ui::selection_counts::SelectionCounts;
+use crate::ui::theme::Theme;
+use crate::util::UsizeExt;
+use ratatui::text::{Line, Span};
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::rc::Rc;
+use unicode_width::UnicodeWidthStr;
+
+/// A key binding to suggest in the status bar, such as `space toggle`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyHint {
+    pub keys: String,
+    pub description: &'static str,
+}
+
+/// The footer at the bottom of the screen, summarizing the focused item and
+/// how much has been selected.
+#[derive(Clone, Debug)]
+pub struct StatusBar<'a> {
+    /// The path of the focused file or directory, if any.
+    pub path: Option<Cow<'a, str>>,
+    /// The number of the focused section out of the editable sections of its
+    /// file, if a section or line is focused.
+    pub section_num: Option<(usize, usize)>,
+    pub counts: Rc<SelectionCounts>,
+    pub commit_idx: usize,
+    pub num_commits: usize,
+    pub commit_view_mode: CommitViewMode,
+    pub diff_view_mode: DiffViewMode,
+    pub line_layout: LineLayout,
+    pub is_read_only: bool,
+    /// The number of review comments, if in review mode.
+    pub num_comments: Option<usize>,
+    /// Shown at the right, in order of importance. Hints which don't fit are
+    /// omitted, starting from the last.
+    pub key_hints: Vec<KeyHint>,
+    pub theme: &'a Theme,
+}
+
+impl StatusBar<'_> {
+    /// The number of rows taken by the status bar.
+    pub const HEIGHT: u16 = 1;
+
+    const SEPARATOR: &'static str = " │ ";
+
+    /// The parts of the summary shown at the left, before the key hints.
+    fn summary(&self) -> Vec<Span<'_>> {
+        let Self {
+            path,
+            section_num,
+            counts,
+            commit_idx,
+            num_commits,
+            commit_view_mode,
+            diff_view_mode,
+            line_layout,
+            is_read_only,
+            num_comments,
+            key_hints: _,
+            theme,
+        } = self;
+
+        let mut spans = vec![Span::raw(" ")];
+        if let Some(path) = path {
+            spans.push(Span::styled(path.as_ref(), theme.file_path));
+            if let Some((section_num, num_sections)) = section_num {
+                spans.push(Span::raw(format!(" section {section_num}/{num_sections}")));
+            }
+            spans.push(Span::raw(Self::SEPARATOR));
+        }
+
+        let SelectionCounts {
+            num_lines,
+            num_files,
+            ..
+        } = **counts;
+        spans.push(Span::raw(format!(
+            "{}/{num_lines} lines, {}/{num_files} files",
+            counts.num_lines_in_commit(*commit_idx),
+            counts.num_files_in_commit(*commit_idx),
+        )));
+        spans.push(Span::raw(Self::SEPARATOR));
+        spans.push(Span::raw(if *is_read_only {
+            Cow::Borrowed("read-only")
+        } else {
+            Cow::Owned(format!("commit {}/{num_commits}", commit_idx + 1))
+        }));
+        spans.push(Span::raw(Self::SEPARATOR));
+        if let Some(num_comments) = num_comments {
+            spans.push(Span::raw(match num_comments {
+                1 => Cow::Borrowed("1 comment"),
+                num_comments => Cow::Owned(format!("{num_comments} comments")),
+            }));
+            spans.push(Span::raw(Self::SEPARATOR));
+        }
+        spans.push(Span::raw(match (diff_view_mode, commit_view_mode) {
+            (DiffViewMode::Unified, CommitViewMode::Inline) => "unified",
+            (DiffViewMode::Unified, CommitViewMode::Adjacent) => "unified, adjacent",
+            (DiffViewMode::Split, CommitViewMode::Inline) => "split",
+            (DiffViewMode::Split, CommitViewMode::Adjacent) => "split, adjacent",
+        }));
+        match line_layout {
+            LineLayout::Scroll { offset_x: 0 } => {}
+            LineLayout::Scroll { offset_x } => {
+                spans.push(Span::raw(format!(", from column {}", offset_x + 1)));
+            }
+            LineLayout::Wrap => spans.push(Span::raw(", wrapped")),
+        }
+        spans
+    }
+
+    /// The key hints which fit in `width` columns, each preceded by a space.
+    fn key_hints(&self, width: usize) -> Vec<Span<'_>> {
+        let Self {
+            key_hints, theme, ..
+        } = self;
+        let mut spans = Vec::new();
+        let mut hints_width = 0;
+        for KeyHint { keys, description } in key_hints {
+            let hint_width = 1 + keys.width() + 1 + description.width() + 1;
+            if hints_width + hint_width > width {
+                break;
+            }
+            hints_width += hint_width;
+            spans.extend([
+                Span::raw(" "),
+                Span::styled(keys.as_str(), theme.status_bar_key),
+                Span::raw(format!(" {description} ")),
+            ]);
+        }
+        spans
+    }
+}
+
+impl Component for StatusBar<'_> {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::StatusBar
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let viewport_rect = viewport.rect();
+        let rect = Rect {
+            x: viewport_rect.x,
+            y: viewport_rect.end_y() - 1,
+            width: viewport_rect.width,
+            height: 1,
+        };
+        viewport.draw_blank(rect);
+        viewport.set_style(rect, self.theme.status_bar);
+
+        let summary = self.summary();
+        let summary_width: usize = summary.iter().map(|span| span.content.width()).sum();
+        viewport.draw_text(rect.x, rect.y, Line::from(summary));
+
+        // Leave at least one column between the summary and the hints.
+        let key_hints = self.key_hints(rect.width.saturating_sub(summary_width + 1));
+        let key_hints_width: usize = key_hints.iter().map(|span| span.content.width()).sum();
+        viewport.draw_text(
+            rect.end_x() - key_hints_width.unwrap_isize(),
+            rect.y,
+            Line::from(key_hints),
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use crate::render::{Component, Rect, Viewport};
+use crate::ui::theme::Theme;
 use crate::Tristate;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::Span;
 use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::Hash;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(Clone, Debug)]
 pub enum TristateIconStyle {
@@ -13,14 +15,15 @@ pub enum TristateIconStyle {
 }
 
 #[derive(Clone, Debug)]
-pub struct TristateBox<Id> {
+pub struct TristateBox<'a, Id> {
     pub id: Id,
     pub tristate: Tristate,
     pub icon_style: TristateIconStyle,
     pub is_read_only: bool,
+    pub theme: &'a Theme,
 }
 
-impl<Id> TristateBox<Id> {
+impl<Id> TristateBox<'_, Id> {
     pub fn text(&self) -> String {
         let Self {
             tristate,
@@ -45,26 +48,31 @@ impl<Id> TristateBox<Id> {
         }
     }
 
-    pub fn color(&self) -> Color {
+    pub fn style(&self) -> Style {
         let Self {
             tristate,
             icon_style,
+            is_read_only,
+            theme,
             ..
         } = self;
 
+        if *is_read_only {
+            return theme.read_only_box;
+        }
         match icon_style {
-            TristateIconStyle::Expand => Color::Magenta,
+            TristateIconStyle::Expand => theme.expand_box,
             // Render selection state icons.
             TristateIconStyle::Check => match tristate {
-                Tristate::False => Color::DarkGray,
-                Tristate::True => Color::Blue,
-                Tristate::Partial => Color::Yellow,
+                Tristate::False => theme.unchecked_box,
+                Tristate::True => theme.checked_box,
+                Tristate::Partial => theme.partial_box,
             },
         }
     }
 }
 
-impl<Id: Clone + Debug + Eq + Hash> Component for TristateBox<Id> {
+impl<Id: Clone + Debug + Eq + Hash> Component for TristateBox<'_, Id> {
     type Id = Id;
 
     fn id(&self) -> Self::Id {
@@ -72,12 +80,7 @@ impl<Id: Clone + Debug + Eq + Hash> Component for TristateBox<Id> {
     }
 
     fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
-        let style = if self.is_read_only {
-            Style::default().fg(Color::Gray).add_modifier(Modifier::DIM)
-        } else {
-            Style::default().fg(self.color()).add_modifier(Modifier::BOLD)
-        };
-        let span = Span::styled(self.text(), style);
+        let span = Span::styled(self.text(), self.style());
         viewport.draw_span(x, y, &span);
     }
 }
@@ -122,6 +125,40 @@ impl<Id: Clone + Debug + Eq + Hash> Component for Button<'_, Id> {
     }
 }
 
-pub fn highlight_rect<Id: Clone + Debug + Eq + Hash>(viewport: &mut Viewport<Id>, rect: Rect) {
-    viewport.set_style(rect, Style::default().bg(Color::Rgb(38, 38, 38)));
+pub fn highlight_rect<Id: Clone + Debug + Eq + Hash>(
+    viewport: &mut Viewport<Id>,
+    rect: Rect,
+    theme: &Theme,
+) {
+    viewport.set_style(rect, theme.selection_highlight);
+}
+
+/// Draw the marker of an item with a review comment on row `y`, in the column
+/// just before `end_x`.
+pub fn draw_comment_marker<Id: Clone + Debug + Eq + Hash>(
+    viewport: &mut Viewport<Id>,
+    end_x: isize,
+    y: isize,
+    theme: &Theme,
+) {
+    viewport.draw_span(end_x - 1, y, &Span::styled("●", theme.comment_marker));
+}
+
+/// Shorten `s` to at most `width` columns by replacing its start with `…`,
+/// since the end of a path is usually the most informative part.
+pub fn truncate_start(s: &str, width: usize) -> Cow<'_, str> {
+    if s.width() <= width {
+        return Cow::Borrowed(s);
+    }
+    let mut start = s.len();
+    let mut kept_width = 0;
+    for (idx, c) in s.char_indices().rev() {
+        let char_width = c.width().unwrap_or(0);
+        if kept_width + char_width + 1 > width {
+            break;
+        }
+        kept_width += char_width;
+        start = idx;
+    }
+    Cow::Owned(format!("…{}", &s[start..]))
 }
@@ -0,0 +1,98 @@
+//! Asking for confirmation before quitting, so that the selection isn't
+//! accepted or discarded by accident.
+
+use std::path::PathBuf;
+
+use crate::types::{File, FileMode};
+
+/// How a file would be changed by accepting the selection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileChangeKind {
+    /// The file would be created.
+    Created,
+
+    /// The contents of the file would be changed, and possibly its mode.
+    Written,
+
+    /// The file would be deleted.
+    Deleted,
+
+    /// Only the mode of the file would be changed.
+    ModeChanged,
+}
+
+/// A file which would be changed by accepting the selection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileChange {
+    /// The path of the file.
+    pub path: PathBuf,
+
+    /// How the file would be changed.
+    pub kind: FileChangeKind,
+
+    /// The mode of the file before and after, if it would be changed.
+    pub mode_change: Option<(FileMode, FileMode)>,
+}
+
+impl FileChange {
+    /// How `file` would be changed by accepting its selected changes, if at
+    /// all.
+    pub fn new(file: &File) -> Option<Self> {
+        let (selected, _unselected) = file.get_selected_contents();
+        // The selected contents of the file if nothing were selected, i.e.
+        // its contents before any of the changes.
+        let mut unchanged_file = file.clone();
+        unchanged_file.set_checked(false);
+        let (original, _unselected) = unchanged_file.get_selected_contents();
+
+        let kind = match (original.file_mode, selected.file_mode) {
+            (FileMode::Absent, FileMode::Absent) => return None,
+            (FileMode::Absent, FileMode::Unix(_)) => FileChangeKind::Created,
+            (FileMode::Unix(_), FileMode::Absent) => FileChangeKind::Deleted,
+            (FileMode::Unix(_), FileMode::Unix(_)) => {
+                if selected.contents != original.contents {
+                    FileChangeKind::Written
+                } else if selected.file_mode != original.file_mode {
+                    FileChangeKind::ModeChanged
+                } else {
+                    return None;
+                }
+            }
+        };
+        let mode_change = match kind {
+            FileChangeKind::Written | FileChangeKind::ModeChanged => {
+                Some((original.file_mode, selected.file_mode))
+                    .filter(|(old_mode, new_mode)| old_mode != new_mode)
+            }
+            FileChangeKind::Created | FileChangeKind::Deleted => None,
+        };
+        Some(Self {
+            path: file.path.to_path_buf(),
+            kind,
+            mode_change,
+        })
+    }
+}
+
+/// The state of the dialog asking for confirmation before quitting.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfirmPrompt {
+    /// Confirm accepting the selection.
+    Accept {
+        /// The files which would be changed, in order.
+        file_changes: Vec<FileChange>,
+    },
+
+    /// Confirm discarding the selection, which was changed since the recorder
+    /// started.
+    Cancel,
+}
+
+impl ConfirmPrompt {
+    /// The prompt to confirm accepting the selected changes in `files`.
+    pub fn accept(files: &[File]) -> Self {
+        Self::Accept {
+            file_changes: files.iter().filter_map(FileChange::new).collect(),
+        }
+    }
+}
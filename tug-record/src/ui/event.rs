@@ -1,222 +1,249 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
-use super::input::TestingScreenshot;
+//! Input events which drive the UI.
+
+use crossterm::event::{KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 
-#[allow(missing_docs)]
+use super::input::TestingScreenshot;
+use super::keymap::KeyChord;
+
+/// An input to the UI, as returned by [`crate::RecordInput::next_events`].
+///
+/// Real terminal input is converted from `crossterm` events, with key presses
+/// arriving as [`Event::Key`] and being interpreted according to the
+/// [`crate::Keymap`]. Scripts and tests can also produce the interpreted
+/// events directly, such as with [`crate::helpers::TestingInput`]:
+///
+/// ```
+/// use tug_record::helpers::TestingInput;
+/// use tug_record::{Event, KeyChord, TestingScreenshot};
+///
+/// let screenshot = TestingScreenshot::default();
+/// let input = TestingInput::new(
+///     80,
+///     24,
+///     [
+///         Event::FocusNext,
+///         Event::ToggleItem,
+///         // Typed keys are interpreted by the keymap, or used as text input
+///         // if a prompt is open.
+///         Event::from(KeyChord::parse("a").unwrap()),
+///         screenshot.event(),
+///         Event::QuitAccept,
+///     ],
+/// );
+/// ```
+///
+/// New variants may be added in minor releases, but the existing variants
+/// keep their meaning.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Event {
+    /// Do nothing.
     None,
+    /// Quit, keeping the selected changes.
     QuitAccept,
+    /// Quit, discarding the selected changes.
     QuitCancel,
+    /// Quit immediately, discarding the selected changes, such as after
+    /// Ctrl-C.
     QuitInterrupt,
+    /// Close the innermost open mode, such as visual mode, the search
+    /// highlighting or the file filter. Doesn't quit.
     QuitEscape,
+    /// Record the contents of the screen into the given screenshot. Only
+    /// works with a testing terminal.
     TakeScreenshot(TestingScreenshot),
+    /// Clear the terminal and draw the UI from scratch.
     Redraw,
+    /// Scroll so that the focused item is visible.
     EnsureSelectionInViewport,
+    /// Scroll up by one line without moving focus.
     ScrollUp,
+    /// Scroll down by one line without moving focus.
     ScrollDown,
+    /// Scroll up by one screen without moving focus.
     PageUp,
+    /// Scroll down by one screen without moving focus.
     PageDown,
+    /// Scroll the text of long lines to the left, unless lines are wrapped.
+    ScrollLeft,
+    /// Scroll the text of long lines to the right, unless lines are wrapped.
+    ScrollRight,
+    /// Move focus to the previous item.
     FocusPrev,
     /// Move focus to the previous item of the same kind (i.e. file, section, line).
     FocusPrevSameKind,
+    /// Move focus up by one screen.
     FocusPrevPage,
+    /// Move focus to the next item.
     FocusNext,
     /// Move focus to the next item of the same kind.
     FocusNextSameKind,
+    /// Move focus down by one screen.
     FocusNextPage,
+    /// Expand the focused item and move focus to its first child.
     FocusInner,
     /// If `fold_section` is true, and the current section is expanded, the
     /// section should be collapsed without moving focus. Otherwise, move the
     /// focus outwards.
     FocusOuter {
+        /// Whether to collapse the focused section rather than moving focus.
         fold_section: bool,
     },
+    /// Check or uncheck the focused item.
     ToggleItem,
+    /// Check or uncheck the focused item, then move focus to the next item of
+    /// the same kind.
     ToggleItemAndAdvance,
+    /// Invert the checked state of every item.
     ToggleAll,
+    /// Check every item, or uncheck every item if they're all checked.
     ToggleAllUniform,
+    /// Start or stop selecting a range of lines, which extends from the item
+    /// selected when visual mode started to the currently selected item.
+    ToggleVisualMode,
+    /// Check every line in the visual range, or the selected item if visual
+    /// mode isn't active.
+    CheckRange,
+    /// Uncheck every line in the visual range, or the selected item if visual
+    /// mode isn't active.
+    UncheckRange,
+    /// Revert the most recent change to the selection.
+    Undo,
+    /// Re-apply the most recently undone change to the selection.
+    Redo,
+    /// Move the focused item to the commit after the one it's assigned to.
+    MoveItemToNextCommit,
+    /// Move the focused item to the commit before the one it's assigned to.
+    MoveItemToPrevCommit,
+    /// Show and focus the view for the next commit.
+    FocusNextCommit,
+    /// Show and focus the view for the previous commit.
+    FocusPrevCommit,
+    /// Open the search prompt.
+    Search,
+    /// Move focus to the next item matching the search query.
+    SearchNext,
+    /// Move focus to the previous item matching the search query.
+    SearchPrev,
+    /// Open the prompt to show only the files matching a glob or change
+    /// status.
+    FilterFiles,
+    /// Expand or collapse the focused item.
     ExpandItem,
+    /// Expand or collapse every item.
     ExpandAll,
-    ToggleCommitViewMode, // no key binding currently
+    /// Reveal more unchanged lines above the focused section.
+    ExpandContextAbove,
+    /// Reveal more unchanged lines below the focused section.
+    ExpandContextBelow,
+    /// Reveal all of the unchanged lines on both sides of the focused section.
+    ExpandContextWhole,
+    /// Switch between showing only the focused commit and showing every
+    /// commit side by side.
+    ToggleCommitViewMode,
+    /// Switch between the unified and side-by-side layouts of changed lines.
+    ToggleDiffViewMode,
+    /// Switch between cutting off long lines, which can be scrolled
+    /// horizontally, and wrapping them onto several rows.
+    ToggleLineWrap,
+    /// Cycle between hiding the directory tree, showing it inline in the list
+    /// of files, and showing it in a sidebar.
+    ToggleFileTree,
+    /// Edit the message of the focused commit.
     EditCommitMessage,
+    /// Edit the added lines of the focused section by hand.
+    EditAddedLines,
+    /// Add or edit the review comment on the focused file, section or line.
+    /// Only available in review mode (see [`crate::Recorder::with_review_mode`]).
+    EditComment,
+    /// Show the dialog listing every review comment. Only available in review
+    /// mode.
+    ListComments,
+    /// Show the help dialog.
     Help,
+    /// A click with the left mouse button at the given terminal cell.
+    Click {
+        /// The row of the cell, starting from 0 at the top of the terminal.
+        row: usize,
+        /// The column of the cell, starting from 0 at the left of the
+        /// terminal.
+        column: usize,
+    },
+    /// The terminal was resized to the given size.
+    Resize {
+        /// The new width of the terminal, in columns.
+        width: usize,
+        /// The new height of the terminal, in rows.
+        height: usize,
+    },
+    /// No input arrived within the interval set with
+    /// [`crate::Recorder::with_tick_interval`].
+    /// Gives the [`crate::RecordInput`] a chance to do background work in
+    /// [`crate::RecordInput::on_tick`].
+    Tick,
+    /// A key press which hasn't been interpreted yet. It's mapped to one of
+    /// the other events by the key bindings, unless a text prompt is open, in
+    /// which case it's used as text input.
+    Key(KeyChord),
+}
+
+impl From<KeyChord> for Event {
+    /// A press of the given key.
+    fn from(key_chord: KeyChord) -> Self {
+        Self::Key(key_chord)
+    }
 }
 
 impl From<crossterm::event::Event> for Event {
     fn from(event: crossterm::event::Event) -> Self {
-        use crossterm::event::Event;
         match event {
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::QuitCancel,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Esc,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::QuitEscape,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::QuitInterrupt,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::QuitAccept,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('?'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::Help,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Up | KeyCode::Char('y'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
+            crossterm::event::Event::Key(
+                key_event @ KeyEvent {
+                    kind: KeyEventKind::Press,
+                    ..
+                },
+            ) => Self::Key(KeyChord::from(key_event)),
+
+            crossterm::event::Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers: _,
+            }) => Self::Click {
+                row: usize::from(row),
+                column: usize::from(column),
+            },
+            crossterm::event::Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                column: _,
+                row: _,
+                modifiers: _,
             }) => Self::ScrollUp,
-            Event::Key(KeyEvent {
-                code: KeyCode::Down | KeyCode::Char('e'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
+            crossterm::event::Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                column: _,
+                row: _,
+                modifiers: _,
             }) => Self::ScrollDown,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::PageUp | KeyCode::Char('b'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::PageUp,
-            Event::Key(KeyEvent {
-                code: KeyCode::PageDown | KeyCode::Char('f'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::PageDown,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Up | KeyCode::Char('k'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusPrev,
-            Event::Key(KeyEvent {
-                code: KeyCode::Down | KeyCode::Char('j'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusNext,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::PageUp,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusPrevSameKind,
-            Event::Key(KeyEvent {
-                code: KeyCode::PageDown,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusNextSameKind,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Left | KeyCode::Char('h'),
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusOuter {
-                fold_section: false,
+            crossterm::event::Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollLeft,
+                column: _,
+                row: _,
+                modifiers: _,
+            }) => Self::ScrollLeft,
+            crossterm::event::Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollRight,
+                column: _,
+                row: _,
+                modifiers: _,
+            }) => Self::ScrollRight,
+
+            crossterm::event::Event::Resize(width, height) => Self::Resize {
+                width: usize::from(width),
+                height: usize::from(height),
             },
-            Event::Key(KeyEvent {
-                code: KeyCode::Left | KeyCode::Char('h'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusOuter { fold_section: true },
-            Event::Key(KeyEvent {
-                code: KeyCode::Right | KeyCode::Char('l'),
-                // The shift modifier is accepted for continuity with FocusOuter.
-                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusInner,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('u'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusPrevPage,
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('d'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusNextPage,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char(' '),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ToggleItem,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) =>  Self::QuitInterrupt,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('a'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ToggleAll,
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('A'),
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ToggleAllUniform,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('f'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ExpandItem,
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('F'),
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ExpandAll,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('e'),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _event,
-            }) => Self::EditCommitMessage,
 
             _event => Self::None,
         }
     }
 }
-
@@ -0,0 +1,209 @@
+//! Grouping of the files being recorded by directory.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{File, Tristate};
+
+/// How to show the directory tree of the files being recorded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FileTreeMode {
+    /// Show a flat list of files, without any directories.
+    #[default]
+    Off,
+
+    /// Show each directory as a collapsible item in the list of files, with
+    /// its files and subdirectories nested underneath it.
+    Inline,
+
+    /// Show the directory tree in a pane next to the list of files.
+    Sidebar,
+}
+
+impl FileTreeMode {
+    /// The next mode, when cycling through the modes.
+    pub(crate) fn next(self) -> Self {
+        match self {
+            FileTreeMode::Off => FileTreeMode::Inline,
+            FileTreeMode::Inline => FileTreeMode::Sidebar,
+            FileTreeMode::Sidebar => FileTreeMode::Off,
+        }
+    }
+}
+
+/// A directory which contains at least one of the files being recorded.
+#[derive(Clone, Debug)]
+pub struct DirNode {
+    /// The path of the directory relative to its parent directory. Chains of
+    /// directories without any files are merged into a single node, such as
+    /// `src/ui`.
+    pub name: PathBuf,
+
+    /// The number of directories above this one.
+    pub depth: usize,
+
+    /// The index of the parent directory, if any.
+    pub parent_dir_idx: Option<usize>,
+
+    /// The indices of all files in this directory or its subdirectories.
+    pub file_idxs: Vec<usize>,
+}
+
+/// An item of the directory tree, in the order that the tree is displayed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileTreeRow {
+    /// The directory at the given index of [`FileTree::dirs`].
+    Dir(usize),
+
+    /// The file at the given index of [`crate::RecordState::files`].
+    File(usize),
+}
+
+/// The directories containing the files being recorded. Within each
+/// directory, subdirectories are listed before files, and each are sorted by
+/// name.
+#[derive(Clone, Debug, Default)]
+pub struct FileTree {
+    /// Indexed by directory index.
+    pub dirs: Vec<DirNode>,
+
+    /// The directories and files in the order that they're displayed.
+    pub rows: Vec<FileTreeRow>,
+
+    /// The index of the directory directly containing each file, if any.
+    /// Indexed by file index.
+    file_parent_dir_idxs: Vec<Option<usize>>,
+}
+
+#[derive(Default)]
+struct DirBuilder {
+    dirs: BTreeMap<OsString, DirBuilder>,
+    files: Vec<(OsString, usize)>,
+}
+
+impl FileTree {
+    /// Build the directory tree for the given files.
+    pub fn new(files: &[File]) -> Self {
+        let mut root = DirBuilder::default();
+        for (file_idx, file) in files.iter().enumerate() {
+            let mut dir = &mut root;
+            if let Some(parent) = file.path.parent() {
+                for component in parent.iter() {
+                    dir = dir.dirs.entry(component.to_owned()).or_default();
+                }
+            }
+            let file_name = file
+                .path
+                .file_name()
+                .map(|file_name| file_name.to_owned())
+                .unwrap_or_default();
+            dir.files.push((file_name, file_idx));
+        }
+
+        let mut result = Self {
+            dirs: Vec::new(),
+            rows: Vec::new(),
+            file_parent_dir_idxs: vec![None; files.len()],
+        };
+        result.add_dir_contents(root, None, 0);
+        result
+    }
+
+    /// Add the rows for the subdirectories and files of `dir`, which is at
+    /// `dir_idx`, or the root if `None`. Returns the indices of all files in
+    /// `dir`, including in its subdirectories.
+    fn add_dir_contents(
+        &mut self,
+        dir: DirBuilder,
+        dir_idx: Option<usize>,
+        depth: usize,
+    ) -> Vec<usize> {
+        let DirBuilder { dirs, mut files } = dir;
+        let mut file_idxs = Vec::new();
+        for (name, mut subdir) in dirs {
+            let mut name = PathBuf::from(name);
+            while subdir.files.is_empty() && subdir.dirs.len() == 1 {
+                let (subdir_name, subsubdir) = subdir.dirs.pop_first().unwrap();
+                name.push(subdir_name);
+                subdir = subsubdir;
+            }
+
+            let subdir_idx = self.dirs.len();
+            self.dirs.push(DirNode {
+                name,
+                depth,
+                parent_dir_idx: dir_idx,
+                file_idxs: Vec::new(),
+            });
+            self.rows.push(FileTreeRow::Dir(subdir_idx));
+            let subdir_file_idxs = self.add_dir_contents(subdir, Some(subdir_idx), depth + 1);
+            file_idxs.extend(subdir_file_idxs.iter().copied());
+            self.dirs[subdir_idx].file_idxs = subdir_file_idxs;
+        }
+
+        files.sort();
+        for (_name, file_idx) in files {
+            self.rows.push(FileTreeRow::File(file_idx));
+            self.file_parent_dir_idxs[file_idx] = dir_idx;
+            file_idxs.push(file_idx);
+        }
+        file_idxs
+    }
+
+    /// The index of the directory directly containing the file at
+    /// `file_idx`, if any.
+    pub fn file_parent_dir_idx(&self, file_idx: usize) -> Option<usize> {
+        self.file_parent_dir_idxs.get(file_idx).copied().flatten()
+    }
+
+    /// The indices of the directories containing the given directory or file,
+    /// from the innermost to the outermost.
+    pub fn ancestor_dir_idxs(&self, row: FileTreeRow) -> impl Iterator<Item = usize> + '_ {
+        let parent_dir_idx = match row {
+            FileTreeRow::Dir(dir_idx) => self.dirs[dir_idx].parent_dir_idx,
+            FileTreeRow::File(file_idx) => self.file_parent_dir_idx(file_idx),
+        };
+        std::iter::successors(parent_dir_idx, |dir_idx| self.dirs[*dir_idx].parent_dir_idx)
+    }
+
+    /// The full path of the directory at `dir_idx`.
+    pub fn dir_path(&self, dir_idx: usize) -> PathBuf {
+        let mut dir_idxs: Vec<usize> = self.ancestor_dir_idxs(FileTreeRow::Dir(dir_idx)).collect();
+        dir_idxs.reverse();
+        dir_idxs.push(dir_idx);
+        dir_idxs
+            .into_iter()
+            .map(|dir_idx| self.dirs[dir_idx].name.as_path())
+            .collect()
+    }
+
+    /// The number of directories containing the given directory or file.
+    pub fn depth(&self, row: FileTreeRow) -> usize {
+        match row {
+            FileTreeRow::Dir(dir_idx) => self.dirs[dir_idx].depth,
+            FileTreeRow::File(file_idx) => match self.file_parent_dir_idx(file_idx) {
+                Some(dir_idx) => self.dirs[dir_idx].depth + 1,
+                None => 0,
+            },
+        }
+    }
+}
+
+/// Combine the tristates of the files in a directory into the tristate of the
+/// directory.
+pub fn fold_file_tristates(tristates: impl IntoIterator<Item = Tristate>) -> Tristate {
+    tristates
+        .into_iter()
+        .fold(None, |acc, tristate| match (acc, tristate) {
+            (None, tristate) => Some(tristate),
+            (Some(acc_tristate), tristate) if acc_tristate == tristate => Some(tristate),
+            _ => Some(Tristate::Partial),
+        })
+        .unwrap_or(Tristate::False)
+}
+
+/// The last component of `path`, for display in the directory tree.
+pub fn file_name(path: &Path) -> &Path {
+    path.file_name().map(Path::new).unwrap_or(path)
+}
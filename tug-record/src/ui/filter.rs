@@ -0,0 +1,309 @@
+//! Narrowing the list of files by path and change status.
+
+use std::path::Path;
+
+use crate::types::{File, FileMode, Section};
+use crate::ui::components::app::SelectionKey;
+
+/// The kind of change made to a file, for filtering the list of files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FileStatus {
+    /// The file didn't exist before the changes.
+    Added,
+
+    /// The file is deleted by the changes.
+    Deleted,
+
+    /// The file was renamed or copied from another path.
+    Renamed,
+
+    /// The file existed before and after the changes, under the same path.
+    Modified,
+
+    /// The file has binary contents. Unlike the other statuses, a binary file
+    /// also has one of the statuses above.
+    Binary,
+}
+
+impl FileStatus {
+    const ALL: [FileStatus; 5] = [
+        FileStatus::Added,
+        FileStatus::Deleted,
+        FileStatus::Renamed,
+        FileStatus::Modified,
+        FileStatus::Binary,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::Modified => "modified",
+            FileStatus::Binary => "binary",
+        }
+    }
+
+    /// Whether `file` has this status.
+    pub fn matches(self, file: &File) -> bool {
+        let File {
+            old_path,
+            path,
+            file_mode,
+            sections,
+        } = file;
+        let is_added = *file_mode == FileMode::Absent;
+        let is_deleted = sections.iter().any(|section| match section {
+            Section::FileMode { mode, .. } => *mode == FileMode::Absent,
+            Section::Unchanged { .. } | Section::Changed { .. } | Section::Binary { .. } => false,
+        });
+        let is_renamed = old_path
+            .as_ref()
+            .is_some_and(|old_path| old_path.as_ref() != path.as_ref());
+        match self {
+            FileStatus::Added => is_added,
+            FileStatus::Deleted => is_deleted,
+            FileStatus::Renamed => is_renamed,
+            FileStatus::Modified => !is_added && !is_deleted && !is_renamed,
+            FileStatus::Binary => sections.iter().any(|section| match section {
+                Section::Binary { .. } => true,
+                Section::Unchanged { .. } | Section::Changed { .. } | Section::FileMode { .. } => {
+                    false
+                }
+            }),
+        }
+    }
+}
+
+/// A filter on the list of files, entered by the user as a list of
+/// space-separated terms.
+///
+/// Terms starting with `:` select files by status, such as `:added` or
+/// `:binary`; any unambiguous prefix of the status name, such as `:a`, is
+/// accepted. Other terms are glob patterns matched against the path of the
+/// file (or its old path, if it was renamed), where `*` and `?` don't match
+/// `/`, and `**` matches across directories. Patterns without a `/` are
+/// matched against the file name only, so `*.lock` matches lock files in any
+/// directory.
+///
+/// A file is shown if it matches any of the patterns (if there are any) and
+/// any of the statuses (if there are any).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileFilter {
+    text: String,
+    globs: Vec<Vec<char>>,
+    statuses: Vec<Option<FileStatus>>,
+}
+
+impl FileFilter {
+    /// Constructor. Returns `None` if there are no terms, since an empty
+    /// filter would match everything.
+    pub fn new(text: impl Into<String>) -> Option<Self> {
+        let text = text.into();
+        let mut globs = Vec::new();
+        let mut statuses = Vec::new();
+        for term in text.split_whitespace() {
+            match term.strip_prefix(':') {
+                Some(status_name) => {
+                    // An unknown status doesn't match any files, rather than
+                    // being ignored, so that a typo doesn't select every file.
+                    let mut candidates = FileStatus::ALL
+                        .into_iter()
+                        .filter(|status| status.name().starts_with(status_name));
+                    let status = match (candidates.next(), candidates.next()) {
+                        (Some(status), None) => Some(status),
+                        _ => None,
+                    };
+                    statuses.push(status);
+                }
+                None => globs.push(term.chars().collect()),
+            }
+        }
+        if globs.is_empty() && statuses.is_empty() {
+            return None;
+        }
+        Some(Self {
+            text,
+            globs,
+            statuses,
+        })
+    }
+
+    /// The text that the filter was created from.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether `file` should be shown.
+    pub fn matches(&self, file: &File) -> bool {
+        let Self {
+            text: _,
+            globs,
+            statuses,
+        } = self;
+        let matches_glob = globs.is_empty()
+            || globs.iter().any(|glob| {
+                std::iter::once(&file.path)
+                    .chain(&file.old_path)
+                    .any(|path| glob_matches_path(glob, path))
+            });
+        let matches_status =
+            statuses.is_empty() || statuses.iter().flatten().any(|status| status.matches(file));
+        matches_glob && matches_status
+    }
+}
+
+/// Whether `path` matches `glob`, as described in [`FileFilter`].
+pub(crate) fn glob_matches_path(glob: &[char], path: &Path) -> bool {
+    let haystack = if glob.contains(&'/') {
+        path.to_string_lossy()
+    } else {
+        match path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => path.to_string_lossy(),
+        }
+    };
+    let haystack: Vec<char> = haystack.chars().collect();
+    glob_matches(glob, &haystack)
+}
+
+fn glob_matches(glob: &[char], haystack: &[char]) -> bool {
+    GlobMatcher {
+        glob,
+        haystack,
+        memo: vec![None; (glob.len() + 1) * (haystack.len() + 1)],
+    }
+    .matches(0, 0)
+}
+
+/// Matches a glob against a haystack, remembering which suffixes of each have
+/// been compared. Without this, a glob with several stars would try the same
+/// suffixes over and over, taking exponential time to reject a long path.
+struct GlobMatcher<'a> {
+    glob: &'a [char],
+    haystack: &'a [char],
+
+    /// Whether `glob[glob_idx..]` matches `haystack[haystack_idx..]`, indexed
+    /// by `glob_idx * (haystack.len() + 1) + haystack_idx`, once known.
+    memo: Vec<Option<bool>>,
+}
+
+impl GlobMatcher<'_> {
+    fn matches(&mut self, glob_idx: usize, haystack_idx: usize) -> bool {
+        let memo_idx = glob_idx * (self.haystack.len() + 1) + haystack_idx;
+        if let Some(result) = self.memo[memo_idx] {
+            return result;
+        }
+        let result = self.matches_uncached(glob_idx, haystack_idx);
+        self.memo[memo_idx] = Some(result);
+        result
+    }
+
+    fn matches_uncached(&mut self, glob_idx: usize, haystack_idx: usize) -> bool {
+        let haystack = self.haystack;
+        match &self.glob[glob_idx..] {
+            [] => haystack_idx == haystack.len(),
+
+            // `**/` matches any number of directories, including none.
+            ['*', '*', '/', ..] => {
+                self.matches(glob_idx + 3, haystack_idx)
+                    || (haystack_idx..haystack.len())
+                        .any(|idx| haystack[idx] == '/' && self.matches(glob_idx + 3, idx + 1))
+            }
+            ['*', '*', ..] => {
+                (haystack_idx..=haystack.len()).any(|idx| self.matches(glob_idx + 2, idx))
+            }
+            ['*', ..] => {
+                let component_end = haystack[haystack_idx..]
+                    .iter()
+                    .position(|c| *c == '/')
+                    .map_or(haystack.len(), |len| haystack_idx + len);
+                (haystack_idx..=component_end).any(|idx| self.matches(glob_idx + 1, idx))
+            }
+            ['?', ..] => match haystack.get(haystack_idx) {
+                Some(c) if *c != '/' => self.matches(glob_idx + 1, haystack_idx + 1),
+                Some(_) | None => false,
+            },
+            [glob_char, ..] => match haystack.get(haystack_idx) {
+                Some(c) if c == glob_char => self.matches(glob_idx + 1, haystack_idx + 1),
+                Some(_) | None => false,
+            },
+        }
+    }
+}
+
+/// The state of the filter prompt while the user is typing a filter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FilterPrompt {
+    /// The text typed so far.
+    pub text: String,
+
+    /// The selection when the prompt was opened, restored if the filter is
+    /// cancelled.
+    pub origin_selection_key: SelectionKey,
+
+    /// The filter which was active when the prompt was opened, restored if
+    /// the filter is cancelled.
+    pub previous_filter: Option<FileFilter>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, path: &str) -> bool {
+        let glob: Vec<char> = glob.chars().collect();
+        glob_matches_path(&glob, Path::new(path))
+    }
+
+    #[test]
+    fn test_glob_matches_path() {
+        let cases = [
+            // Patterns without a `/` are matched against the file name only.
+            ("*.rs", "main.rs", true),
+            ("*.rs", "src/ui/mod.rs", true),
+            ("*.rs", "src/main.rsx", false),
+            ("Cargo.lock", "foo/Cargo.lock", true),
+            ("foo", "foo/Cargo.lock", false),
+            // Other patterns are matched against the whole path.
+            ("foo/Cargo.lock", "foo/Cargo.lock", true),
+            ("foo/Cargo.lock", "bar/foo/Cargo.lock", false),
+            // `*` and `?` don't match `/`.
+            ("src/*.rs", "src/main.rs", true),
+            ("src/*.rs", "src/ui/mod.rs", false),
+            ("src/*", "src/", true),
+            ("src/?.rs", "src/a.rs", true),
+            ("src/?.rs", "src/ab.rs", false),
+            ("src?main.rs", "src/main.rs", false),
+            ("src/a*b*c", "src/aXbYc", true),
+            ("src/a*b*c", "src/aXbY/c", false),
+            // `**/` matches any number of directories, including none.
+            ("src/**/*.rs", "src/main.rs", true),
+            ("src/**/*.rs", "src/ui/components/app.rs", true),
+            ("src/**/*.rs", "tests/main.rs", false),
+            ("**/mod.rs", "mod.rs", true),
+            ("**/mod.rs", "src/ui/mod.rs", true),
+            ("**/mod.rs", "src/ui/amod.rs", false),
+            // `**` elsewhere matches anything, including `/`.
+            ("src/**", "src/ui/mod.rs", true),
+            ("src/**", "src", false),
+            ("src/**.rs", "src/ui/mod.rs", true),
+            ("src/**.rs", "src/ui/mod.txt", false),
+        ];
+        for (glob, path, expected) in cases {
+            assert_eq!(matches(glob, path), expected, "glob {glob:?} on {path:?}");
+        }
+    }
+
+    #[test]
+    fn test_glob_matches_many_stars() {
+        // These would take exponential time without memoization.
+        let path = format!("{}/b", "a".repeat(200));
+        assert!(!matches(&format!("{}c", "*a".repeat(20)), &path));
+        assert!(!matches(&format!("{}c", "**a".repeat(20)), &path));
+
+        let path = format!("{}b", "a/".repeat(100));
+        assert!(!matches(&format!("{}c", "**/".repeat(20)), &path));
+        assert!(matches(&format!("{}b", "**/".repeat(20)), &path));
+    }
+}
@@ -0,0 +1,243 @@
+//! Syntax highlighting for the lines of a file.
+//!
+//! Highlighting is only performed if compiled with the `syntax-highlighting`
+//! feature. Otherwise, [`SyntaxHighlighter::file_highlights`] always returns
+//! `None` and lines are rendered with the plain added/removed styles.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use ratatui::style::Style;
+
+use crate::ui::theme::Theme;
+use crate::File;
+
+/// A region of a line to render with a specific style.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HighlightedToken {
+    /// The style of the token. Only the foreground color and modifiers are
+    /// set, so that the background of the line shows through.
+    pub style: Style,
+
+    /// The byte range of the token within the line.
+    pub range: Range<usize>,
+}
+
+/// The highlighted tokens for each line of a file.
+#[derive(Debug, Default)]
+pub struct FileHighlights {
+    /// Keyed by section index and line index.
+    lines: HashMap<(usize, usize), Vec<HighlightedToken>>,
+}
+
+impl FileHighlights {
+    /// The tokens of the given line, if it was highlighted.
+    pub fn line(&self, section_idx: usize, line_idx: usize) -> Option<&[HighlightedToken]> {
+        self.lines
+            .get(&(section_idx, line_idx))
+            .map(|tokens| tokens.as_slice())
+    }
+}
+
+/// Computes and caches the syntax highlighting for each file. Files are only
+/// highlighted the first time that they're requested, which is typically when
+/// they're first expanded.
+#[derive(Debug, Default)]
+pub struct SyntaxHighlighter {
+    /// Keyed by file index. A value of `None` means that the file couldn't be
+    /// highlighted, such as because its language wasn't recognized.
+    cache: RefCell<HashMap<usize, Option<Rc<FileHighlights>>>>,
+
+    #[cfg(feature = "syntax-highlighting")]
+    assets: std::cell::OnceCell<imp::Assets>,
+}
+
+impl SyntaxHighlighter {
+    /// The highlighting for the file at `file_idx`, computing it if
+    /// necessary.
+    pub fn file_highlights(
+        &self,
+        file_idx: usize,
+        file: &File,
+        theme: &Theme,
+    ) -> Option<Rc<FileHighlights>> {
+        // Syntax highlighting is disabled by the theme.
+        theme.syntax_theme.as_ref()?;
+        if let Some(highlights) = self.cache.borrow().get(&file_idx) {
+            return highlights.clone();
+        }
+        let highlights = self.compute(file, theme).map(Rc::new);
+        self.cache.borrow_mut().insert(file_idx, highlights.clone());
+        highlights
+    }
+
+    /// Discard the cached highlighting for all files, such as after the theme
+    /// has changed.
+    pub fn invalidate_all(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Discard the cached highlighting for the file at `file_idx`, such as
+    /// after its lines have been edited.
+    pub fn invalidate_file(&self, file_idx: usize) {
+        self.cache.borrow_mut().remove(&file_idx);
+    }
+
+    #[cfg(feature = "syntax-highlighting")]
+    fn compute(&self, file: &File, theme: &Theme) -> Option<FileHighlights> {
+        let assets = self.assets.get_or_init(imp::Assets::load);
+        imp::highlight_file(assets, file, theme)
+    }
+
+    #[cfg(not(feature = "syntax-highlighting"))]
+    fn compute(&self, _file: &File, _theme: &Theme) -> Option<FileHighlights> {
+        None
+    }
+}
+
+#[cfg(feature = "syntax-highlighting")]
+mod imp {
+    use std::fmt::Debug;
+
+    use ratatui::style::{Color, Modifier, Style};
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::{FontStyle, ThemeSet};
+    use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+    use super::{FileHighlights, HighlightedToken};
+    use crate::ui::theme::Theme;
+    use crate::{ChangeType, File, Section};
+
+    pub struct Assets {
+        syntax_set: SyntaxSet,
+        theme_set: ThemeSet,
+    }
+
+    impl Debug for Assets {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Assets").finish_non_exhaustive()
+        }
+    }
+
+    impl Assets {
+        pub fn load() -> Self {
+            Self {
+                syntax_set: SyntaxSet::load_defaults_newlines(),
+                theme_set: ThemeSet::load_defaults(),
+            }
+        }
+
+        fn find_syntax(&self, file: &File) -> Option<&SyntaxReference> {
+            let File { path, .. } = file;
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            let file_name = path.file_name().and_then(|file_name| file_name.to_str());
+            // Syntaxes can also be registered for full file names, such as
+            // `Makefile`.
+            [extension, file_name]
+                .into_iter()
+                .flatten()
+                .find_map(|name| self.syntax_set.find_syntax_by_extension(name))
+        }
+    }
+
+    /// Highlight the old and new versions of the file separately, so that the
+    /// parser state of each line reflects the lines which precede it in that
+    /// version. Unchanged lines use the highlighting from the new version.
+    pub fn highlight_file(assets: &Assets, file: &File, theme: &Theme) -> Option<FileHighlights> {
+        let syntax = assets.find_syntax(file)?;
+        let syntax_theme = assets
+            .theme_set
+            .themes
+            .get(theme.syntax_theme.as_deref()?)?;
+        let mut old_highlighter = HighlightLines::new(syntax, syntax_theme);
+        let mut new_highlighter = HighlightLines::new(syntax, syntax_theme);
+
+        let mut result = FileHighlights::default();
+        for (section_idx, section) in file.sections.iter().enumerate() {
+            match section {
+                Section::Unchanged { lines } => {
+                    for (line_idx, line) in lines.iter().enumerate() {
+                        // Keep the old parser state in sync, but discard the
+                        // result.
+                        highlight_line(&mut old_highlighter, assets, line)?;
+                        let tokens = highlight_line(&mut new_highlighter, assets, line)?;
+                        result.lines.insert((section_idx, line_idx), tokens);
+                    }
+                }
+                Section::Changed { lines } => {
+                    for (line_idx, changed_line) in lines.iter().enumerate() {
+                        let highlighter = match changed_line.change_type {
+                            ChangeType::Added => &mut new_highlighter,
+                            ChangeType::Removed => &mut old_highlighter,
+                        };
+                        let tokens = highlight_line(highlighter, assets, &changed_line.line)?;
+                        result.lines.insert((section_idx, line_idx), tokens);
+                    }
+                }
+                Section::FileMode { .. } | Section::Binary { .. } => {}
+            }
+        }
+        Some(result)
+    }
+
+    fn highlight_line(
+        highlighter: &mut HighlightLines,
+        assets: &Assets,
+        line: &str,
+    ) -> Option<Vec<HighlightedToken>> {
+        // The syntax definitions expect each line to end with a newline.
+        let owned_line;
+        let line_with_newline = if line.ends_with('\n') {
+            line
+        } else {
+            owned_line = format!("{line}\n");
+            &owned_line
+        };
+        let regions = highlighter
+            .highlight_line(line_with_newline, &assets.syntax_set)
+            .ok()?;
+
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        for (style, text) in regions {
+            let end = (start + text.len()).min(line.len());
+            let style = convert_style(style);
+            match tokens.last_mut() {
+                // Merge adjacent tokens with the same style to reduce the
+                // number of spans to render.
+                Some(HighlightedToken {
+                    style: last_style,
+                    range,
+                }) if *last_style == style => range.end = end,
+                Some(_) | None if start < end => tokens.push(HighlightedToken {
+                    style,
+                    range: start..end,
+                }),
+                Some(_) | None => {}
+            }
+            start = end;
+        }
+        Some(tokens)
+    }
+
+    fn convert_style(style: syntect::highlighting::Style) -> Style {
+        let syntect::highlighting::Style {
+            foreground,
+            background: _,
+            font_style,
+        } = style;
+        let mut result = Style::new().fg(Color::Rgb(foreground.r, foreground.g, foreground.b));
+        for (syntect_font_style, modifier) in [
+            (FontStyle::BOLD, Modifier::BOLD),
+            (FontStyle::ITALIC, Modifier::ITALIC),
+            (FontStyle::UNDERLINE, Modifier::UNDERLINED),
+        ] {
+            if font_style.contains(syntect_font_style) {
+                result = result.add_modifier(modifier);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,445 @@
+//! Undo/redo history for changes to the selection state.
+
+use std::collections::VecDeque;
+
+use crate::ui::components::app::SelectionKey;
+use crate::{File, Section};
+
+/// The maximum number of undoable operations to remember.
+const MAX_NUM_ENTRIES: usize = 1000;
+
+/// The maximum number of recorded change states across all remembered
+/// operations. Once exceeded, the oldest operations are forgotten. This keeps
+/// the memory usage bounded even when repeatedly toggling every line of a
+/// large diff. (The most recent operation is always remembered, regardless of
+/// its size.)
+const MAX_NUM_RECORDED_CHANGES: usize = 100_000;
+
+/// Identifies a single selectable change inside a [`crate::RecordState`]: either a
+/// changed line, or a file mode or binary section.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct ChangeKey {
+    file_idx: usize,
+    section_idx: usize,
+    line_idx: Option<usize>,
+}
+
+/// The selection state of a single change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChangeState {
+    /// Whether the change is checked.
+    pub is_checked: bool,
+
+    /// The commit that the change is assigned to, if it's checked.
+    pub commit_idx: usize,
+}
+
+#[derive(Debug)]
+struct HistoryEntry {
+    /// The changes whose state was modified, along with their state before
+    /// and after the operation.
+    changes: Vec<(ChangeKey, ChangeState, ChangeState)>,
+
+    /// The selection at the time of the operation, so that the user can see
+    /// what was undone.
+    selection_key: SelectionKey,
+}
+
+/// Undo/redo stacks of operations which changed the checked state or commit
+/// assignment of changes.
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: VecDeque<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    num_recorded_changes: usize,
+}
+
+impl History {
+    /// Take a snapshot of the selection state of all changes in `files`. Pass
+    /// the result to [`History::record`] after modifying the files.
+    pub fn snapshot(files: &[File]) -> Vec<ChangeState> {
+        let mut result = Vec::new();
+        visit_change_states(files, |_key, state| result.push(state));
+        result
+    }
+
+    /// Record the operation which modified `files` since `before` was taken
+    /// with [`History::snapshot`]. Operations which didn't change anything are
+    /// not recorded.
+    pub fn record(
+        &mut self,
+        before: Vec<ChangeState>,
+        files: &[File],
+        selection_key: SelectionKey,
+    ) {
+        let mut before = before.into_iter();
+        let mut changes = Vec::new();
+        visit_change_states(files, |key, after| {
+            if let Some(before) = before.next() {
+                if before != after {
+                    changes.push((key, before, after));
+                }
+            }
+        });
+        if changes.is_empty() {
+            return;
+        }
+
+        self.num_recorded_changes += changes.len();
+        self.undo_stack.push_back(HistoryEntry {
+            changes,
+            selection_key,
+        });
+        self.redo_stack.clear();
+        while self.undo_stack.len() > 1
+            && (self.undo_stack.len() > MAX_NUM_ENTRIES
+                || self.num_recorded_changes > MAX_NUM_RECORDED_CHANGES)
+        {
+            if let Some(entry) = self.undo_stack.pop_front() {
+                self.num_recorded_changes -= entry.changes.len();
+            }
+        }
+    }
+
+    /// Forget the recorded changes to the file at `file_idx`, such as after its
+    /// sections were edited, since the changes refer to sections and lines by
+    /// index. The changes to other files can still be undone and redone.
+    /// Operations which only changed the file are forgotten entirely.
+    pub fn invalidate_file(&mut self, file_idx: usize) {
+        let Self {
+            undo_stack,
+            redo_stack,
+            num_recorded_changes,
+        } = self;
+        let invalidate_entry = |entry: &mut HistoryEntry| {
+            let HistoryEntry {
+                changes,
+                selection_key,
+            } = entry;
+            changes.retain(|(key, _before, _after)| key.file_idx != file_idx);
+            // The selected section or line may no longer exist, but the file
+            // still does.
+            if let Some(file_key) = selection_key.file_key() {
+                if file_key.file_idx == file_idx {
+                    *selection_key = SelectionKey::File(file_key);
+                }
+            }
+            !changes.is_empty()
+        };
+        undo_stack.retain_mut(invalidate_entry);
+        redo_stack.retain_mut(invalidate_entry);
+        *num_recorded_changes = undo_stack.iter().map(|entry| entry.changes.len()).sum();
+    }
+
+    /// Revert the most recent operation, if any. Returns the selection at the
+    /// time of the operation.
+    pub fn undo(&mut self, files: &mut [File]) -> Option<SelectionKey> {
+        let entry = self.undo_stack.pop_back()?;
+        self.num_recorded_changes -= entry.changes.len();
+        for (key, before, _after) in &entry.changes {
+            set_change_state(files, *key, *before);
+        }
+        let selection_key = entry.selection_key;
+        self.redo_stack.push(entry);
+        Some(selection_key)
+    }
+
+    /// Re-apply the most recently undone operation, if any. Returns the
+    /// selection at the time of the operation.
+    pub fn redo(&mut self, files: &mut [File]) -> Option<SelectionKey> {
+        let entry = self.redo_stack.pop()?;
+        for (key, _before, after) in &entry.changes {
+            set_change_state(files, *key, *after);
+        }
+        let selection_key = entry.selection_key;
+        self.num_recorded_changes += entry.changes.len();
+        self.undo_stack.push_back(entry);
+        Some(selection_key)
+    }
+}
+
+fn visit_change_states(files: &[File], mut f: impl FnMut(ChangeKey, ChangeState)) {
+    for (file_idx, file) in files.iter().enumerate() {
+        for (section_idx, section) in file.sections.iter().enumerate() {
+            match section {
+                Section::Unchanged { .. } => {}
+                Section::Changed { lines } => {
+                    for (line_idx, line) in lines.iter().enumerate() {
+                        f(
+                            ChangeKey {
+                                file_idx,
+                                section_idx,
+                                line_idx: Some(line_idx),
+                            },
+                            ChangeState {
+                                is_checked: line.is_checked,
+                                commit_idx: line.commit_idx,
+                            },
+                        );
+                    }
+                }
+                Section::FileMode {
+                    is_checked,
+                    commit_idx,
+                    mode: _,
+                }
+                | Section::Binary {
+                    is_checked,
+                    commit_idx,
+                    old_description: _,
+                    new_description: _,
+                } => f(
+                    ChangeKey {
+                        file_idx,
+                        section_idx,
+                        line_idx: None,
+                    },
+                    ChangeState {
+                        is_checked: *is_checked,
+                        commit_idx: *commit_idx,
+                    },
+                ),
+            }
+        }
+    }
+}
+
+fn set_change_state(files: &mut [File], key: ChangeKey, state: ChangeState) {
+    let ChangeKey {
+        file_idx,
+        section_idx,
+        line_idx,
+    } = key;
+    let ChangeState {
+        is_checked: new_is_checked,
+        commit_idx: new_commit_idx,
+    } = state;
+    let section = match files
+        .get_mut(file_idx)
+        .and_then(|file| file.sections.get_mut(section_idx))
+    {
+        Some(section) => section,
+        None => return,
+    };
+    match (section, line_idx) {
+        (Section::Changed { lines }, Some(line_idx)) => {
+            if let Some(line) = lines.get_mut(line_idx) {
+                line.is_checked = new_is_checked;
+                line.commit_idx = new_commit_idx;
+            }
+        }
+        (
+            Section::FileMode {
+                is_checked,
+                commit_idx,
+                mode: _,
+            }
+            | Section::Binary {
+                is_checked,
+                commit_idx,
+                old_description: _,
+                new_description: _,
+            },
+            None,
+        ) => {
+            *is_checked = new_is_checked;
+            *commit_idx = new_commit_idx;
+        }
+        (Section::Unchanged { .. }, _)
+        | (Section::Changed { .. }, None)
+        | (Section::FileMode { .. } | Section::Binary { .. }, Some(_)) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    use super::*;
+    use crate::ui::components::file::FileKey;
+    use crate::ui::components::line::LineKey;
+    use crate::{ChangeType, FileMode, SectionChangedLine};
+
+    fn make_files(num_lines: usize) -> Vec<File<'static>> {
+        vec![File {
+            old_path: None,
+            path: Cow::Borrowed(Path::new("foo")),
+            file_mode: FileMode::FILE_DEFAULT,
+            sections: vec![
+                Section::FileMode {
+                    is_checked: false,
+                    commit_idx: 0,
+                    mode: FileMode::Unix(0o100755),
+                },
+                Section::Changed {
+                    lines: (0..num_lines)
+                        .map(|_| SectionChangedLine {
+                            is_checked: false,
+                            commit_idx: 0,
+                            change_type: ChangeType::Added,
+                            line: Cow::Borrowed("foo\n"),
+                        })
+                        .collect(),
+                },
+            ],
+        }]
+    }
+
+    fn line_states(files: &[File]) -> Vec<bool> {
+        History::snapshot(files)
+            .into_iter()
+            .map(
+                |ChangeState {
+                     is_checked,
+                     commit_idx: _,
+                 }| is_checked,
+            )
+            .collect()
+    }
+
+    fn toggle_line(history: &mut History, files: &mut [File], line_idx: usize) {
+        let before = History::snapshot(files);
+        match &mut files[0].sections[1] {
+            Section::Changed { lines } => lines[line_idx].is_checked ^= true,
+            section => panic!("unexpected section: {section:?}"),
+        }
+        history.record(before, files, SelectionKey::None);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut files = make_files(2);
+        let mut history = History::default();
+        assert_eq!(history.undo(&mut files), None);
+        assert_eq!(history.redo(&mut files), None);
+
+        toggle_line(&mut history, &mut files, 0);
+        let before = History::snapshot(&files);
+        files[0].sections[0].set_checked(true);
+        files[0].sections[1].assign_commit_idx(1, 3);
+        history.record(before, &files, SelectionKey::None);
+        assert_eq!(line_states(&files), vec![true, true, true]);
+
+        assert_eq!(history.undo(&mut files), Some(SelectionKey::None));
+        assert_eq!(line_states(&files), vec![false, true, false]);
+        assert_eq!(history.undo(&mut files), Some(SelectionKey::None));
+        assert_eq!(line_states(&files), vec![false, false, false]);
+        assert_eq!(history.undo(&mut files), None);
+
+        assert_eq!(history.redo(&mut files), Some(SelectionKey::None));
+        assert_eq!(history.redo(&mut files), Some(SelectionKey::None));
+        assert_eq!(line_states(&files), vec![true, true, true]);
+        // The commit assignment is restored along with the checked state.
+        assert_eq!(
+            History::snapshot(&files)[2],
+            ChangeState {
+                is_checked: true,
+                commit_idx: 1
+            }
+        );
+        assert_eq!(history.redo(&mut files), None);
+    }
+
+    #[test]
+    fn test_record_clears_redo_stack() {
+        let mut files = make_files(2);
+        let mut history = History::default();
+        toggle_line(&mut history, &mut files, 0);
+        history.undo(&mut files);
+
+        // An operation which changed nothing doesn't count.
+        history.record(History::snapshot(&files), &files, SelectionKey::None);
+        assert_eq!(history.redo(&mut files), Some(SelectionKey::None));
+        history.undo(&mut files);
+
+        toggle_line(&mut history, &mut files, 1);
+        assert_eq!(history.redo(&mut files), None);
+        assert_eq!(line_states(&files), vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_invalidate_file() {
+        let mut files = [make_files(1), make_files(1)].concat();
+        let mut history = History::default();
+        let line_key = |file_idx| {
+            SelectionKey::Line(LineKey {
+                commit_idx: 0,
+                file_idx,
+                section_idx: 1,
+                line_idx: 0,
+            })
+        };
+        let mut toggle_files = |files: &mut [File], file_idxs: &[usize]| {
+            let before = History::snapshot(files);
+            for file_idx in file_idxs {
+                files[*file_idx].sections[1].toggle_all();
+            }
+            history.record(before, files, line_key(file_idxs[0]));
+        };
+        toggle_files(&mut files, &[0, 1]);
+        toggle_files(&mut files, &[0]);
+        toggle_files(&mut files, &[1]);
+        toggle_files(&mut files, &[0]);
+        history.undo(&mut files);
+        assert_eq!(line_states(&files), vec![false, false, false, false]);
+
+        // Only the changes to the other file are remembered, and the
+        // selection is moved to the file itself.
+        history.invalidate_file(0);
+        assert_eq!(history.redo(&mut files), None);
+        assert_eq!(history.undo(&mut files), Some(line_key(1)));
+        assert_eq!(line_states(&files), vec![false, false, false, true]);
+        assert_eq!(
+            history.undo(&mut files),
+            Some(SelectionKey::File(FileKey {
+                commit_idx: 0,
+                file_idx: 0,
+            }))
+        );
+        assert_eq!(line_states(&files), vec![false, false, false, false]);
+        assert_eq!(history.undo(&mut files), None);
+        assert_eq!(history.num_recorded_changes, 0);
+    }
+
+    #[test]
+    fn test_max_num_entries() {
+        let mut files = make_files(1);
+        let mut history = History::default();
+        for _ in 0..MAX_NUM_ENTRIES + 1 {
+            toggle_line(&mut history, &mut files, 0);
+        }
+        let mut num_undos = 0;
+        while history.undo(&mut files).is_some() {
+            num_undos += 1;
+        }
+        assert_eq!(num_undos, MAX_NUM_ENTRIES);
+        // The oldest toggle was forgotten, so it can't be undone.
+        assert_eq!(line_states(&files), vec![false, true]);
+    }
+
+    #[test]
+    fn test_max_num_recorded_changes() {
+        let num_lines = MAX_NUM_RECORDED_CHANGES / 2 + 1;
+        let mut files = make_files(num_lines);
+        let mut history = History::default();
+        for _ in 0..3 {
+            let before = History::snapshot(&files);
+            files[0].sections[1].toggle_all();
+            history.record(before, &files, SelectionKey::None);
+        }
+        // Only the most recent operation fits.
+        assert!(history.undo(&mut files).is_some());
+        assert_eq!(history.undo(&mut files), None);
+
+        // The most recent operation is remembered even if it's too large by
+        // itself.
+        let mut files = make_files(MAX_NUM_RECORDED_CHANGES + 1);
+        let mut history = History::default();
+        let before = History::snapshot(&files);
+        files[0].sections[1].toggle_all();
+        history.record(before, &files, SelectionKey::None);
+        assert!(history.undo(&mut files).is_some());
+        assert!(line_states(&files).iter().all(|is_checked| !is_checked));
+    }
+}
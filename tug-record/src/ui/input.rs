@@ -4,15 +4,23 @@ use super::{event, terminal};
 use std::cell::RefCell;
 use std::fmt::Display;
 use std::rc::Rc;
+use std::time::Duration;
 
-///
 /// A copy of the contents of the screen at a certain point in time.
+///
+/// Produce an event with [`TestingScreenshot::event`] to record the screen
+/// when the event is handled, then read the recorded contents with
+/// [`TestingScreenshot::contents`] or by formatting the screenshot. Clones
+/// share the same contents, so the screenshot can be inspected after the
+/// event has been consumed. Only works with a testing terminal.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TestingScreenshot {
     contents: Rc<RefCell<Option<String>>>,
 }
 
 impl TestingScreenshot {
+    /// Record the contents of the screen, replacing any previously recorded
+    /// contents.
     pub fn set(&self, new_contents: String) {
         let Self { contents } = self;
         *contents.borrow_mut() = Some(new_contents);
@@ -22,6 +30,13 @@ impl TestingScreenshot {
     pub fn event(&self) -> event::Event {
         event::Event::TakeScreenshot(self.clone())
     }
+
+    /// The recorded contents of the screen, with one quoted line per row, or
+    /// `None` if the screenshot's event hasn't been handled yet.
+    pub fn contents(&self) -> Option<String> {
+        let Self { contents } = self;
+        contents.borrow().clone()
+    }
 }
 
 impl Display for TestingScreenshot {
@@ -43,9 +58,41 @@ pub trait RecordInput {
     /// one available event.
     fn next_events(&mut self) -> Result<Vec<event::Event>, RecordError>;
 
+    /// Like [`RecordInput::next_events`], but return [`event::Event::Tick`]
+    /// if no event is available before `timeout` has passed. Used when a tick
+    /// interval is set with [`crate::Recorder::with_tick_interval`]. By
+    /// default, this blocks like [`RecordInput::next_events`], so no ticks are
+    /// produced.
+    fn next_events_timeout(
+        &mut self,
+        _timeout: Duration,
+    ) -> Result<Vec<event::Event>, RecordError> {
+        self.next_events()
+    }
+
+    /// Called when an [`event::Event::Tick`] is handled, such as to poll for
+    /// changes in the background. The returned events are handled after the
+    /// screen is redrawn. By default, nothing is done.
+    fn on_tick(&mut self) -> Result<Vec<event::Event>, RecordError> {
+        Ok(Vec::new())
+    }
+
     /// Open a commit editor and interactively edit the given message.
     ///
-    /// This function will only be invoked if one of the provided `Commit`s had
-    /// a non-`None` commit message.
+    /// Commit messages are normally edited in a dialog inside the UI; this
+    /// function is only invoked when the user asks to continue editing in an
+    /// external editor, with the message as edited so far. Return
+    /// [`RecordError::Cancelled`] to leave the message unchanged.
     fn edit_commit_message(&mut self, message: &str) -> Result<String, RecordError>;
+
+    /// Open an editor and interactively edit the added lines of a section,
+    /// which are provided concatenated, including their trailing newlines.
+    ///
+    /// The edited text replaces the added lines of the section, and is then
+    /// diffed against the section's removed lines again. Return
+    /// [`RecordError::Cancelled`] to leave the section unchanged. By default,
+    /// the lines are returned unchanged.
+    fn edit_added_lines(&mut self, lines: &str) -> Result<String, RecordError> {
+        Ok(lines.to_owned())
+    }
 }
@@ -0,0 +1,743 @@
+//! Configurable key bindings.
+
+use std::fmt::Display;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use thiserror::Error;
+
+use crate::ui::event::Event;
+
+/// A single key press along with its modifiers, such as `ctrl-r`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KeyChord {
+    /// The key which was pressed.
+    pub code: KeyCode,
+
+    /// The modifiers held while pressing the key.
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Constructor. For character keys, the Shift modifier is folded into the
+    /// character itself (`shift-a` is the same as `A`), since terminals
+    /// disagree on whether to report it.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                let mut uppercase = c.to_uppercase();
+                let c = match (uppercase.next(), uppercase.next()) {
+                    (Some(upper), None) => upper,
+                    _ => c,
+                };
+                Self {
+                    code: KeyCode::Char(c),
+                    modifiers: modifiers - KeyModifiers::SHIFT,
+                }
+            }
+            code => Self { code, modifiers },
+        }
+    }
+
+    /// Parse a description of a key chord, such as `j`, `space`, `ctrl-r` or
+    /// `shift-left`.
+    pub fn parse(description: &str) -> Result<Self, KeymapError> {
+        let invalid_key = |reason: &str| KeymapError::InvalidKey {
+            description: description.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = description;
+        loop {
+            // Don't treat a trailing `-` as a separator, so that `ctrl--`
+            // refers to the `-` key.
+            let (modifier, after) = match rest.split_once('-') {
+                Some((modifier, after)) if !after.is_empty() => (modifier, after),
+                _ => break,
+            };
+            modifiers |= match modifier {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            rest = after;
+        }
+
+        let code = match rest {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            rest => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (None, _) => return Err(invalid_key("no key given")),
+                    (Some('f'), Some(_)) => match rest[1..].parse::<u8>() {
+                        Ok(n @ 1..=24) => KeyCode::F(n),
+                        _ => return Err(invalid_key("unknown key name")),
+                    },
+                    (Some(_), Some(_)) => return Err(invalid_key("unknown key name")),
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key_event: KeyEvent) -> Self {
+        let KeyEvent {
+            code,
+            modifiers,
+            kind: _,
+            state: _,
+        } = key_event;
+        Self::new(code, modifiers)
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { code, modifiers } = self;
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if modifiers.contains(modifier) {
+                write!(f, "{name}-")?;
+            }
+        }
+        match code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// A sequence of key chords which must be pressed one after another.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct KeySequence(pub Vec<KeyChord>);
+
+impl KeySequence {
+    /// Parse a whitespace-separated sequence of key chords, such as `g g` or
+    /// `ctrl-x ctrl-c`. See [`KeyChord::parse`].
+    pub fn parse(description: &str) -> Result<Self, KeymapError> {
+        let chords = description
+            .split_whitespace()
+            .map(KeyChord::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if chords.is_empty() {
+            return Err(KeymapError::InvalidKey {
+                description: description.to_owned(),
+                reason: "no key given".to_owned(),
+            });
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self(chords) = self;
+        for (i, chord) in chords.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Two key bindings which can't both be used: either they're bound to the
+/// same key sequence, or one is a prefix of the other, so the longer one could
+/// never be typed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeymapConflict {
+    /// The key sequence of the first binding.
+    pub keys: KeySequence,
+
+    /// The event bound to the first key sequence.
+    pub event: Event,
+
+    /// The key sequence of the second binding, which is equal to or starts
+    /// with [`KeymapConflict::keys`].
+    pub other_keys: KeySequence,
+
+    /// The event bound to the second key sequence.
+    pub other_event: Event,
+}
+
+impl Display for KeymapConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            keys,
+            event,
+            other_keys,
+            other_event,
+        } = self;
+        if keys == other_keys {
+            write!(f, "`{keys}` is bound to both {event:?} and {other_event:?}")
+        } else {
+            write!(
+                f,
+                "`{keys}` ({event:?}) is a prefix of `{other_keys}` ({other_event:?})"
+            )
+        }
+    }
+}
+
+/// An error when constructing a [`Keymap`].
+#[derive(Debug, Error)]
+pub enum KeymapError {
+    /// A key description couldn't be parsed.
+    #[error("invalid key {description:?}: {reason}")]
+    InvalidKey {
+        /// The key description as written.
+        description: String,
+
+        /// Why the description is invalid.
+        reason: String,
+    },
+
+    /// An action name didn't correspond to any bindable event.
+    #[error("unknown action {0:?}")]
+    UnknownAction(String),
+
+    /// The keymap description was malformed.
+    #[error("invalid keymap: {0}")]
+    InvalidFormat(String),
+
+    /// The TOML keymap description couldn't be parsed.
+    #[cfg(feature = "toml")]
+    #[error("failed to parse keymap: {0}")]
+    ParseToml(#[source] toml::de::Error),
+
+    /// Some key bindings conflict with each other.
+    #[error("conflicting key bindings: {}", display_conflicts(.0))]
+    Conflicts(Vec<KeymapConflict>),
+}
+
+fn display_conflicts(conflicts: &[KeymapConflict]) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| conflict.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// The default key bindings, as pairs of key sequence and action name.
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("q", "quit-cancel"),
+    ("esc", "quit-escape"),
+    ("ctrl-c", "quit-interrupt"),
+    ("c", "quit-accept"),
+    ("?", "help"),
+    ("ctrl-up", "scroll-up"),
+    ("ctrl-y", "scroll-up"),
+    ("ctrl-down", "scroll-down"),
+    ("ctrl-e", "scroll-down"),
+    ("ctrl-pageup", "page-up"),
+    ("ctrl-b", "page-up"),
+    ("ctrl-pagedown", "page-down"),
+    ("ctrl-f", "page-down"),
+    ("ctrl-left", "scroll-left"),
+    ("z h", "scroll-left"),
+    ("ctrl-right", "scroll-right"),
+    ("z l", "scroll-right"),
+    ("up", "focus-prev"),
+    ("k", "focus-prev"),
+    ("down", "focus-next"),
+    ("j", "focus-next"),
+    ("pageup", "focus-prev-same-kind"),
+    ("pagedown", "focus-next-same-kind"),
+    ("shift-left", "focus-outer"),
+    ("H", "focus-outer"),
+    ("left", "focus-outer-and-fold"),
+    ("h", "focus-outer-and-fold"),
+    ("right", "focus-inner"),
+    ("l", "focus-inner"),
+    // Accepted for continuity with `focus-outer`.
+    ("shift-right", "focus-inner"),
+    ("L", "focus-inner"),
+    ("ctrl-u", "focus-prev-page"),
+    ("ctrl-d", "focus-next-page"),
+    ("space", "toggle-item"),
+    ("enter", "toggle-item-and-advance"),
+    ("a", "toggle-all"),
+    ("A", "toggle-all-uniform"),
+    ("v", "toggle-visual-mode"),
+    ("+", "check-range"),
+    ("-", "uncheck-range"),
+    ("u", "undo"),
+    ("ctrl-r", "redo"),
+    (">", "move-item-to-next-commit"),
+    ("<", "move-item-to-prev-commit"),
+    ("]", "focus-next-commit"),
+    ("[", "focus-prev-commit"),
+    ("/", "search"),
+    ("n", "search-next"),
+    ("N", "search-prev"),
+    ("=", "filter-files"),
+    ("f", "expand-item"),
+    ("F", "expand-all"),
+    ("{", "expand-context-above"),
+    ("}", "expand-context-below"),
+    ("x", "expand-context-whole"),
+    ("tab", "toggle-commit-view-mode"),
+    ("s", "toggle-diff-view-mode"),
+    ("w", "toggle-line-wrap"),
+    ("t", "toggle-file-tree"),
+    ("e", "edit-commit-message"),
+    ("E", "edit-added-lines"),
+    ("m", "edit-comment"),
+    ("M", "list-comments"),
+];
+
+/// The event for the action with the given name, if it can be bound to a key.
+fn action_event(name: &str) -> Option<Event> {
+    let event = match name {
+        "quit-accept" => Event::QuitAccept,
+        "quit-cancel" => Event::QuitCancel,
+        "quit-interrupt" => Event::QuitInterrupt,
+        "quit-escape" => Event::QuitEscape,
+        "redraw" => Event::Redraw,
+        "scroll-up" => Event::ScrollUp,
+        "scroll-down" => Event::ScrollDown,
+        "page-up" => Event::PageUp,
+        "page-down" => Event::PageDown,
+        "scroll-left" => Event::ScrollLeft,
+        "scroll-right" => Event::ScrollRight,
+        "focus-prev" => Event::FocusPrev,
+        "focus-prev-same-kind" => Event::FocusPrevSameKind,
+        "focus-prev-page" => Event::FocusPrevPage,
+        "focus-next" => Event::FocusNext,
+        "focus-next-same-kind" => Event::FocusNextSameKind,
+        "focus-next-page" => Event::FocusNextPage,
+        "focus-inner" => Event::FocusInner,
+        "focus-outer" => Event::FocusOuter {
+            fold_section: false,
+        },
+        "focus-outer-and-fold" => Event::FocusOuter { fold_section: true },
+        "toggle-item" => Event::ToggleItem,
+        "toggle-item-and-advance" => Event::ToggleItemAndAdvance,
+        "toggle-all" => Event::ToggleAll,
+        "toggle-all-uniform" => Event::ToggleAllUniform,
+        "toggle-visual-mode" => Event::ToggleVisualMode,
+        "check-range" => Event::CheckRange,
+        "uncheck-range" => Event::UncheckRange,
+        "undo" => Event::Undo,
+        "redo" => Event::Redo,
+        "move-item-to-next-commit" => Event::MoveItemToNextCommit,
+        "move-item-to-prev-commit" => Event::MoveItemToPrevCommit,
+        "focus-next-commit" => Event::FocusNextCommit,
+        "focus-prev-commit" => Event::FocusPrevCommit,
+        "search" => Event::Search,
+        "search-next" => Event::SearchNext,
+        "search-prev" => Event::SearchPrev,
+        "filter-files" => Event::FilterFiles,
+        "expand-item" => Event::ExpandItem,
+        "expand-all" => Event::ExpandAll,
+        "expand-context-above" => Event::ExpandContextAbove,
+        "expand-context-below" => Event::ExpandContextBelow,
+        "expand-context-whole" => Event::ExpandContextWhole,
+        "toggle-commit-view-mode" => Event::ToggleCommitViewMode,
+        "toggle-diff-view-mode" => Event::ToggleDiffViewMode,
+        "toggle-line-wrap" => Event::ToggleLineWrap,
+        "toggle-file-tree" => Event::ToggleFileTree,
+        "edit-commit-message" => Event::EditCommitMessage,
+        "edit-added-lines" => Event::EditAddedLines,
+        "edit-comment" => Event::EditComment,
+        "list-comments" => Event::ListComments,
+        "help" => Event::Help,
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// The result of looking up a key sequence in a [`Keymap`].
+#[derive(Debug)]
+pub(crate) enum KeymapLookup {
+    /// The key sequence is bound to this event.
+    Event(Event),
+
+    /// The key sequence is the start of at least one longer binding.
+    Prefix,
+
+    /// The key sequence isn't bound to anything.
+    Unbound,
+}
+
+/// Maps key sequences to the [`Event`]s they produce.
+///
+/// Actions are referred to by kebab-case names, such as `focus-next` or
+/// `toggle-item`, and keys by descriptions such as `j`, `ctrl-r` or `g g`
+/// (see [`KeyChord::parse`] and [`KeySequence::parse`]).
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeySequence, Event)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        for (keys, action) in DEFAULT_BINDINGS {
+            keymap
+                .bind_action(keys, action)
+                .expect("default key bindings should be valid");
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// A keymap with no bindings at all.
+    pub fn empty() -> Self {
+        Self {
+            bindings: Default::default(),
+        }
+    }
+
+    /// Bind the key sequence described by `keys` to `event`, in addition to
+    /// any existing bindings. Call [`Keymap::validate`] afterwards to check
+    /// for conflicts.
+    pub fn bind(&mut self, keys: &str, event: Event) -> Result<(), KeymapError> {
+        let keys = KeySequence::parse(keys)?;
+        self.bindings.push((keys, event));
+        Ok(())
+    }
+
+    /// Bind the key sequence described by `keys` to the action named
+    /// `action`.
+    pub fn bind_action(&mut self, keys: &str, action: &str) -> Result<(), KeymapError> {
+        let event =
+            action_event(action).ok_or_else(|| KeymapError::UnknownAction(action.to_owned()))?;
+        self.bind(keys, event)
+    }
+
+    /// Remove all bindings which produce `event`.
+    pub fn unbind_event(&mut self, event: &Event) {
+        self.bindings
+            .retain(|(_keys, bound_event)| bound_event != event);
+    }
+
+    /// The key sequences bound to `event`, in the order they were bound.
+    pub fn keys_for_event<'a>(&'a self, event: &'a Event) -> impl Iterator<Item = &'a KeySequence> {
+        self.bindings
+            .iter()
+            .filter(move |(_keys, bound_event)| bound_event == event)
+            .map(|(keys, _event)| keys)
+    }
+
+    /// Check that no key sequence is bound more than once, and that no key
+    /// sequence is a prefix of another one.
+    pub fn validate(&self) -> Result<(), KeymapError> {
+        let mut conflicts = Vec::new();
+        for (i, (keys, event)) in self.bindings.iter().enumerate() {
+            for (j, (other_keys, other_event)) in self.bindings.iter().enumerate() {
+                let KeySequence(chords) = keys;
+                let KeySequence(other_chords) = other_keys;
+                let is_conflict = if chords == other_chords {
+                    // Report each pair only once, and tolerate binding the
+                    // same keys to the same event twice.
+                    i < j && event != other_event
+                } else {
+                    other_chords.starts_with(chords)
+                };
+                if is_conflict {
+                    conflicts.push(KeymapConflict {
+                        keys: keys.clone(),
+                        event: event.clone(),
+                        other_keys: other_keys.clone(),
+                        other_event: other_event.clone(),
+                    });
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(KeymapError::Conflicts(conflicts))
+        }
+    }
+
+    /// Load a keymap from a TOML description. Each key is the name of an
+    /// action, and each value is a key sequence or a list of key sequences
+    /// which replace the default bindings for that action. Actions which
+    /// aren't mentioned keep their default bindings. For example:
+    ///
+    /// ```toml
+    /// focus-next = ["j", "down", "ctrl-n"]
+    /// focus-prev = ["k", "up", "ctrl-p"]
+    /// toggle-commit-view-mode = "g v"
+    /// undo = []
+    /// ```
+    ///
+    /// The resulting keymap is validated with [`Keymap::validate`].
+    #[cfg(feature = "toml")]
+    pub fn from_toml(description: &str) -> Result<Self, KeymapError> {
+        let table: toml::Table = description.parse().map_err(KeymapError::ParseToml)?;
+        let mut keymap = Self::default();
+        for (action, value) in table {
+            let event =
+                action_event(&action).ok_or_else(|| KeymapError::UnknownAction(action.clone()))?;
+            let keys = match value {
+                toml::Value::String(keys) => vec![keys],
+                toml::Value::Array(values) => values
+                    .into_iter()
+                    .map(|value| match value {
+                        toml::Value::String(keys) => Ok(keys),
+                        value => Err(KeymapError::InvalidFormat(format!(
+                            "expected key sequence for action {action:?}, got: {value}"
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()?,
+                value => {
+                    return Err(KeymapError::InvalidFormat(format!(
+                        "expected key sequence or list of key sequences for action {action:?}, got: {value}"
+                    )))
+                }
+            };
+            keymap.unbind_event(&event);
+            for keys in keys {
+                keymap.bind(&keys, event.clone())?;
+            }
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    pub(crate) fn lookup(&self, keys: &[KeyChord]) -> KeymapLookup {
+        let mut is_prefix = false;
+        for (KeySequence(chords), event) in &self.bindings {
+            if chords == keys {
+                return KeymapLookup::Event(event.clone());
+            }
+            if chords.starts_with(keys) {
+                is_prefix = true;
+            }
+        }
+        if is_prefix {
+            KeymapLookup::Prefix
+        } else {
+            KeymapLookup::Unbound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn chord_descriptions(keys: &str) -> String {
+        KeySequence::parse(keys).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_key_chord() {
+        assert_eq!(
+            KeyChord::parse("j").unwrap(),
+            KeyChord::new(KeyCode::Char('j'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl-r").unwrap(),
+            KeyChord::new(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::parse("shift-left").unwrap(),
+            KeyChord::new(KeyCode::Left, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            KeyChord::parse("f12").unwrap(),
+            KeyChord::new(KeyCode::F(12), KeyModifiers::NONE)
+        );
+        // Shift is folded into characters.
+        assert_eq!(
+            KeyChord::parse("shift-a").unwrap(),
+            KeyChord::parse("A").unwrap()
+        );
+        assert_eq!(
+            KeyChord::new(
+                KeyCode::Char('a'),
+                KeyModifiers::SHIFT | KeyModifiers::CONTROL
+            ),
+            KeyChord::parse("ctrl-A").unwrap()
+        );
+        // A trailing `-` is the key itself rather than a separator.
+        assert_eq!(
+            KeyChord::parse("ctrl--").unwrap(),
+            KeyChord::new(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::parse("-").unwrap(),
+            KeyChord::new(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+    }
+
+    #[test]
+    fn test_parse_key_chord_errors() {
+        let error = |description: &str| KeyChord::parse(description).unwrap_err().to_string();
+        assert_eq!(error(""), r#"invalid key "": no key given"#);
+        assert_eq!(error("foo"), r#"invalid key "foo": unknown key name"#);
+        assert_eq!(error("f0"), r#"invalid key "f0": unknown key name"#);
+        assert_eq!(error("f25"), r#"invalid key "f25": unknown key name"#);
+        assert_eq!(
+            error("hyper-x"),
+            r#"invalid key "hyper-x": unknown key name"#
+        );
+        assert_eq!(
+            KeySequence::parse("  ").unwrap_err().to_string(),
+            r#"invalid key "  ": no key given"#
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        assert_eq!(chord_descriptions("j"), "j");
+        assert_eq!(chord_descriptions("space"), "space");
+        assert_eq!(chord_descriptions("shift-a"), "A");
+        assert_eq!(chord_descriptions("alt-ctrl-x"), "ctrl-alt-x");
+        assert_eq!(chord_descriptions("ctrl--"), "ctrl--");
+        assert_eq!(chord_descriptions("  g   g "), "g g");
+        assert_eq!(chord_descriptions("ctrl-x ctrl-c"), "ctrl-x ctrl-c");
+        for (keys, _action) in DEFAULT_BINDINGS {
+            assert_eq!(&chord_descriptions(keys), keys);
+        }
+    }
+
+    #[test]
+    fn test_default_keymap_is_valid() {
+        Keymap::default().validate().unwrap();
+        for (_keys, action) in DEFAULT_BINDINGS {
+            assert!(action_event(action).is_some(), "unknown action: {action}");
+        }
+    }
+
+    #[test]
+    fn test_validate_conflicts() -> Result<(), KeymapError> {
+        let mut keymap = Keymap::empty();
+        keymap.bind("j", Event::FocusNext)?;
+        // Binding the same keys to the same event twice is harmless.
+        keymap.bind("j", Event::FocusNext)?;
+        keymap.bind("g g", Event::FocusPrev)?;
+        keymap.validate()?;
+
+        let mut duplicate = keymap.clone();
+        duplicate.bind("j", Event::ToggleItem)?;
+        assert_eq!(
+            duplicate.validate().unwrap_err().to_string(),
+            "conflicting key bindings: `j` is bound to both FocusNext and ToggleItem; \
+             `j` is bound to both FocusNext and ToggleItem"
+        );
+
+        let mut prefix = keymap.clone();
+        prefix.bind("g", Event::ToggleItem)?;
+        assert_matches!(
+            prefix.validate(),
+            Err(KeymapError::Conflicts(conflicts)) if conflicts == vec![KeymapConflict {
+                keys: KeySequence::parse("g")?,
+                event: Event::ToggleItem,
+                other_keys: KeySequence::parse("g g")?,
+                other_event: Event::FocusPrev,
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup() -> Result<(), KeymapError> {
+        let mut keymap = Keymap::empty();
+        keymap.bind("g g", Event::FocusPrev)?;
+        keymap.bind("j", Event::FocusNext)?;
+        let KeySequence(g_g) = KeySequence::parse("g g")?;
+        let KeySequence(j) = KeySequence::parse("j")?;
+        assert_matches!(keymap.lookup(&g_g), KeymapLookup::Event(Event::FocusPrev));
+        assert_matches!(keymap.lookup(&g_g[..1]), KeymapLookup::Prefix);
+        assert_matches!(keymap.lookup(&j), KeymapLookup::Event(Event::FocusNext));
+        assert_matches!(keymap.lookup(&[g_g[0], j[0]]), KeymapLookup::Unbound);
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() -> Result<(), KeymapError> {
+        let keymap = Keymap::from_toml(
+            r#"
+            focus-next = ["ctrl-n", "down"]
+            toggle-commit-view-mode = "g v"
+            undo = []
+            "#,
+        )?;
+        let keys = |event: &Event| -> Vec<String> {
+            keymap
+                .keys_for_event(event)
+                .map(ToString::to_string)
+                .collect()
+        };
+        assert_eq!(keys(&Event::FocusNext), vec!["ctrl-n", "down"]);
+        assert_eq!(keys(&Event::ToggleCommitViewMode), vec!["g v"]);
+        assert!(keys(&Event::Undo).is_empty());
+        // Unmentioned actions keep their default bindings.
+        assert_eq!(keys(&Event::FocusPrev), vec!["up", "k"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml_errors() {
+        let error = |description: &str| Keymap::from_toml(description).unwrap_err();
+        assert_matches!(error("focus-next = "), KeymapError::ParseToml(_));
+        assert_eq!(error(r#"fly = "j""#).to_string(), r#"unknown action "fly""#);
+        assert_eq!(
+            error("focus-next = 1").to_string(),
+            r#"invalid keymap: expected key sequence or list of key sequences for action "focus-next", got: 1"#
+        );
+        assert_eq!(
+            error(r#"focus-next = ["j", 1]"#).to_string(),
+            r#"invalid keymap: expected key sequence for action "focus-next", got: 1"#
+        );
+        assert_eq!(
+            error(r#"focus-next = "ctrl-hyper""#).to_string(),
+            r#"invalid key "ctrl-hyper": unknown key name"#
+        );
+        // `j` is still bound to `focus-next` by default.
+        assert_eq!(
+            error(r#"toggle-item = "j""#).to_string(),
+            "conflicting key bindings: `j` is bound to both FocusNext and ToggleItem"
+        );
+    }
+}
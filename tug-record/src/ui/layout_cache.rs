@@ -0,0 +1,201 @@
+//! The sizes of the files in each commit view as of when they were last drawn,
+//! so that files far outside of the viewport can be skipped instead of being
+//! laid out and drawn again every frame.
+
+use std::collections::HashMap;
+
+use crate::render::{DrawnRect, DrawnRects, RectSize};
+use crate::ui::components::app::SelectionKey;
+use crate::ui::components::file::FileKey;
+use crate::ui::components::ComponentId;
+
+/// Caches the size of each file view, so that the position of every file can
+/// be worked out without drawing the files before it. A file's size only
+/// changes when it's expanded or collapsed, when its contents change, or when
+/// the layout of the whole UI changes, so the cached sizes must be discarded
+/// then.
+#[derive(Debug, Default)]
+pub struct LayoutCache {
+    /// The size of each file view when it was last drawn.
+    file_sizes: HashMap<FileKey, RectSize>,
+
+    /// The number of rows of the viewport when the UI was last drawn, or
+    /// `None` if it hasn't been drawn yet.
+    viewport_height: Option<usize>,
+}
+
+impl LayoutCache {
+    /// The size of the file view for `file_key`, if it's known.
+    pub fn file_size(&self, file_key: FileKey) -> Option<RectSize> {
+        self.file_sizes.get(&file_key).copied()
+    }
+
+    /// The number of rows of the viewport when the UI was last drawn.
+    pub fn viewport_height(&self) -> Option<usize> {
+        self.viewport_height
+    }
+
+    /// Remember the sizes of the file views which were just drawn in a
+    /// viewport `viewport_height` rows tall.
+    pub fn update(&mut self, drawn_rects: &DrawnRects<ComponentId>, viewport_height: usize) {
+        self.viewport_height = Some(viewport_height);
+        for (id, DrawnRect { rect, timestamp: _ }) in drawn_rects {
+            if let ComponentId::SelectableItem(SelectionKey::File(file_key)) = id {
+                self.file_sizes.insert(*file_key, RectSize::from(*rect));
+            }
+        }
+    }
+
+    /// Discard the cached size of the file containing `selection_key`, such
+    /// as after it was expanded or collapsed.
+    pub fn invalidate_item(&mut self, selection_key: SelectionKey) {
+        if let Some(file_key) = selection_key.file_key() {
+            self.invalidate_file(file_key.file_idx);
+        }
+    }
+
+    /// Discard the cached sizes of the file at `file_idx` in every commit
+    /// view, such as after its lines have been edited.
+    pub fn invalidate_file(&mut self, file_idx: usize) {
+        self.file_sizes
+            .retain(|file_key, _size| file_key.file_idx != file_idx);
+    }
+
+    /// Discard all of the cached sizes, such as after the terminal was
+    /// resized.
+    pub fn invalidate(&mut self) {
+        self.file_sizes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Rect;
+    use crate::ui::components::section::SectionKey;
+
+    /// The rects of file views drawn `height` rows tall, keyed by their
+    /// commit and file indices.
+    fn make_drawn_rects(file_heights: &[((usize, usize), usize)]) -> DrawnRects<ComponentId> {
+        file_heights
+            .iter()
+            .map(|&((commit_idx, file_idx), height)| {
+                let id = ComponentId::SelectableItem(SelectionKey::File(FileKey {
+                    commit_idx,
+                    file_idx,
+                }));
+                let rect = Rect {
+                    x: 0,
+                    y: 0,
+                    width: 80,
+                    height,
+                };
+                (id, DrawnRect { rect, timestamp: 0 })
+            })
+            .collect()
+    }
+
+    /// A cache of the file views in `file_heights`, drawn in a viewport 24
+    /// rows tall.
+    fn make_cache(file_heights: &[((usize, usize), usize)]) -> LayoutCache {
+        let mut layout_cache = LayoutCache::default();
+        layout_cache.update(&make_drawn_rects(file_heights), 24);
+        layout_cache
+    }
+
+    /// The cached height of each file view, in order, or `None` if it isn't
+    /// known.
+    fn cached_heights(layout_cache: &LayoutCache) -> Vec<Option<usize>> {
+        [(0, 0), (0, 1), (1, 0), (1, 1)]
+            .into_iter()
+            .map(|(commit_idx, file_idx)| {
+                layout_cache
+                    .file_size(FileKey {
+                        commit_idx,
+                        file_idx,
+                    })
+                    .map(|RectSize { width: _, height }| height)
+            })
+            .collect()
+    }
+
+    const FILE_HEIGHTS: [((usize, usize), usize); 4] =
+        [((0, 0), 1), ((0, 1), 2), ((1, 0), 3), ((1, 1), 4)];
+
+    #[test]
+    fn test_update() {
+        let mut layout_cache = LayoutCache::default();
+        assert_eq!(layout_cache.viewport_height(), None);
+        assert_eq!(cached_heights(&layout_cache), vec![None; 4]);
+
+        layout_cache.update(&Default::default(), 24);
+        assert_eq!(layout_cache.viewport_height(), Some(24));
+
+        let layout_cache = make_cache(&FILE_HEIGHTS);
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![Some(1), Some(2), Some(3), Some(4)]
+        );
+    }
+
+    #[test]
+    fn test_invalidate_item() {
+        // Expanding or collapsing a section changes the size of its file in
+        // every commit view, since the file's lines may be split across them.
+        let mut layout_cache = make_cache(&FILE_HEIGHTS);
+        layout_cache.invalidate_item(SelectionKey::Section(SectionKey {
+            commit_idx: 0,
+            file_idx: 1,
+            section_idx: 0,
+        }));
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![Some(1), None, Some(3), None]
+        );
+
+        let mut layout_cache = make_cache(&FILE_HEIGHTS);
+        layout_cache.invalidate_item(SelectionKey::File(FileKey {
+            commit_idx: 1,
+            file_idx: 0,
+        }));
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![None, Some(2), None, Some(4)]
+        );
+
+        // Items outside of any file don't affect the file sizes.
+        let mut layout_cache = make_cache(&FILE_HEIGHTS);
+        layout_cache.invalidate_item(SelectionKey::None);
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![Some(1), Some(2), Some(3), Some(4)]
+        );
+    }
+
+    #[test]
+    fn test_invalidate_file() {
+        let mut layout_cache = make_cache(&FILE_HEIGHTS);
+        layout_cache.invalidate_file(0);
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![None, Some(2), None, Some(4)]
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut layout_cache = make_cache(&FILE_HEIGHTS);
+        layout_cache.invalidate();
+        assert_eq!(cached_heights(&layout_cache), vec![None; 4]);
+        // The viewport height is only replaced by the next update.
+        assert_eq!(layout_cache.viewport_height(), Some(24));
+
+        // Sizes drawn again after invalidating are cached as usual.
+        layout_cache.update(&make_drawn_rects(&[((0, 1), 5)]), 30);
+        assert_eq!(
+            cached_heights(&layout_cache),
+            vec![None, Some(5), None, None]
+        );
+        assert_eq!(layout_cache.viewport_height(), Some(30));
+    }
+}
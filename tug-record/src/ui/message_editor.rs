@@ -0,0 +1,242 @@
+//! Editing a commit message or a review comment in a dialog, without leaving
+//! the UI.
+
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::ui::review::CommentKey;
+
+/// The recommended maximum length of the subject line of a commit message.
+/// Longer subjects are shown with a warning.
+pub const SUBJECT_SOFT_LIMIT: usize = 50;
+
+/// The length past which the subject line of a commit message is likely to be
+/// truncated by other tools.
+pub const SUBJECT_HARD_LIMIT: usize = 72;
+
+/// What the text being edited belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageTarget {
+    /// The message of the commit at the given index.
+    Commit(usize),
+
+    /// The review comment on the given item.
+    Comment(CommentKey),
+}
+
+/// The state of the message editor while it's open.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MessageEditor {
+    /// What the text being edited belongs to.
+    pub target: MessageTarget,
+
+    /// The message as edited so far.
+    text: String,
+
+    /// The byte offset of the cursor in `text`. Always on a character
+    /// boundary.
+    cursor: usize,
+}
+
+impl MessageEditor {
+    /// Constructor. For a commit message, the cursor starts at the end of the
+    /// subject line, since that's usually what's edited. For a comment, it
+    /// starts at the end of the text.
+    pub fn new(target: MessageTarget, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = match target {
+            MessageTarget::Commit(_) => text.find('\n').unwrap_or(text.len()),
+            MessageTarget::Comment(_) => text.len(),
+        };
+        Self {
+            target,
+            text,
+            cursor,
+        }
+    }
+
+    /// The message as edited so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte offset of the cursor in the text.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the whole text, such as after editing it in an external editor.
+    /// The cursor is moved as for [`MessageEditor::new`].
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        *self = Self::new(self.target, text);
+    }
+
+    /// The number of characters in the subject line.
+    pub fn subject_len(&self) -> usize {
+        self.line_range(0)
+            .map_or(0, |range| self.text[range].chars().count())
+    }
+
+    /// Insert `c` before the cursor.
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Delete the character before the cursor.
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Delete the character after the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Delete from the start of the word before the cursor up to the cursor.
+    pub fn delete_word_backward(&mut self) {
+        let end = self.cursor;
+        self.move_word_left();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Move back by one character.
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    /// Move forward by one character.
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    /// Move to the start of the current or previous word.
+    pub fn move_word_left(&mut self) {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end_matches(|c: char| !c.is_alphanumeric());
+        self.cursor = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !c.is_alphanumeric())
+            .map_or(0, |(idx, c)| idx + c.len_utf8());
+    }
+
+    /// Move to the end of the current or next word.
+    pub fn move_word_right(&mut self) {
+        let after = &self.text[self.cursor..];
+        let word_start = after
+            .find(|c: char| c.is_alphanumeric())
+            .unwrap_or(after.len());
+        let word_len = after[word_start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(after.len() - word_start);
+        self.cursor += word_start + word_len;
+    }
+
+    /// Move to the previous line, keeping the same column if possible.
+    pub fn move_up(&mut self) {
+        let (line_idx, column) = self.line_and_column();
+        match line_idx.checked_sub(1) {
+            Some(line_idx) => self.move_to(line_idx, column),
+            None => self.cursor = 0,
+        }
+    }
+
+    /// Move to the next line, keeping the same column if possible.
+    pub fn move_down(&mut self) {
+        let (line_idx, column) = self.line_and_column();
+        if self.line_range(line_idx + 1).is_some() {
+            self.move_to(line_idx + 1, column);
+        } else {
+            self.cursor = self.text.len();
+        }
+    }
+
+    /// Move to the start of the current line.
+    pub fn move_home(&mut self) {
+        let (line_idx, _column) = self.line_and_column();
+        self.move_to(line_idx, 0);
+    }
+
+    /// Move to the end of the current line.
+    pub fn move_end(&mut self) {
+        let (line_idx, _column) = self.line_and_column();
+        self.move_to(line_idx, usize::MAX);
+    }
+
+    /// The byte range of the line at `line_idx`, excluding its newline.
+    fn line_range(&self, line_idx: usize) -> Option<Range<usize>> {
+        let mut start = 0;
+        for (idx, line) in self.text.split('\n').enumerate() {
+            if idx == line_idx {
+                return Some(start..start + line.len());
+            }
+            start += line.len() + 1;
+        }
+        None
+    }
+
+    /// The index of the line containing the cursor, and the number of
+    /// characters between the start of that line and the cursor.
+    fn line_and_column(&self) -> (usize, usize) {
+        let before = &self.text[..self.cursor];
+        let line_idx = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        (line_idx, before[line_start..].chars().count())
+    }
+
+    /// Move to the given character column of the line at `line_idx`, or the
+    /// end of the line if it's shorter.
+    fn move_to(&mut self, line_idx: usize, column: usize) {
+        if let Some(range) = self.line_range(line_idx) {
+            let line = &self.text[range.clone()];
+            self.cursor = range.start
+                + line
+                    .char_indices()
+                    .nth(column)
+                    .map_or(line.len(), |(idx, _)| idx);
+        }
+    }
+}
+
+/// Split `line` into rows no wider than `width` columns, breaking after
+/// whitespace where possible. Returns the byte range of each row; there's
+/// always at least one row, even if `line` is empty.
+pub fn wrap_line(line: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row_start = 0;
+    let mut row_width = 0;
+    // The end of the last whitespace in the current row, where the row can
+    // be broken without splitting a word.
+    let mut break_idx = None;
+    for (idx, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && idx > row_start {
+            let row_end = break_idx.unwrap_or(idx);
+            rows.push(row_start..row_end);
+            row_start = row_end;
+            row_width = line[row_start..idx]
+                .chars()
+                .map(|c| c.width().unwrap_or(0))
+                .sum();
+            break_idx = None;
+        }
+        row_width += char_width;
+        if c.is_whitespace() {
+            break_idx = Some(idx + c.len_utf8());
+        }
+    }
+    rows.push(row_start..line.len());
+    rows
+}
@@ -1,26 +1,64 @@
 use components::section;
-use std::collections::HashSet;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::ops::Range;
 use std::{iter, panic};
 use tracing::warn;
 
 pub mod components;
+pub mod confirm;
 pub mod event;
+pub mod file_tree;
+pub mod filter;
+pub mod highlight;
+pub mod history;
 pub mod input;
+pub mod keymap;
+pub mod layout_cache;
+pub mod message_editor;
 pub mod recorder;
+pub mod review;
+pub mod search;
+pub mod section_edit;
+pub mod selection_counts;
+pub mod session;
 pub mod terminal;
+pub mod theme;
+pub mod word_diff;
 
-use crate::render::{DrawnRect, DrawnRects, Rect};
-use crate::types::{ChangeType, Commit, RecordError, RecordState, Tristate};
+use crate::render::{is_nearly_visible, DrawnRect, DrawnRects, Rect};
+use crate::types::{ChangeType, Commit, RecordError, RecordState, ReviewComment, Tristate};
 use crate::ui::components::app::{AppDebugInfo, AppView, SelectionKey};
+use crate::ui::components::comment_list_dialog::CommentListDialog;
 use crate::ui::components::commit_message_view::{CommitMessageView, CommitViewMode};
-use crate::ui::components::commit_view::CommitView;
+use crate::ui::components::commit_view::{CommitView, FileListItem};
+use crate::ui::components::confirm_dialog::ConfirmDialog;
+use crate::ui::components::dir::{DirKey, DirView, FileTreeSidebar, FileTreeSidebarRow};
 use crate::ui::components::file::{FileKey, FileView};
 use crate::ui::components::help_dialog::HelpDialog;
-use crate::ui::components::line::LineKey;
+use crate::ui::components::line::{self, LineKey, LineLayout};
+use crate::ui::components::message_editor_dialog::MessageEditorDialog;
+use crate::ui::components::search_bar::SearchBar;
+use crate::ui::components::session_dialog::SessionDialog;
+use crate::ui::components::status_bar::{KeyHint, StatusBar};
 use crate::ui::components::widgets::{TristateBox, TristateIconStyle};
 use crate::ui::components::{help_dialog, ComponentId};
+use crate::ui::confirm::ConfirmPrompt;
+use crate::ui::file_tree::{FileTree, FileTreeMode, FileTreeRow};
+use crate::ui::filter::{FileFilter, FilterPrompt};
+use crate::ui::highlight::SyntaxHighlighter;
+use crate::ui::history::{ChangeState, History};
 use crate::ui::input::TestingScreenshot;
+use crate::ui::keymap::{KeyChord, Keymap, KeymapLookup};
+use crate::ui::layout_cache::LayoutCache;
+use crate::ui::message_editor::{MessageEditor, MessageTarget};
+use crate::ui::review::{CommentKey, CommentList};
+use crate::ui::search::{SearchPrompt, SearchQuery};
+use crate::ui::selection_counts::SelectionCountsCache;
+use crate::ui::session::SessionPrompt;
+use crate::ui::theme::Theme;
+use crate::ui::word_diff::WordDiffCache;
 use crate::util::UsizeExt;
 use crate::{File, FileMode, Section, SectionChangedLine};
 
@@ -30,10 +68,21 @@ enum StateUpdate {
     QuitAccept,
     QuitCancel,
     SetHelpDialog(Option<HelpDialog>),
+    /// Open or close the dialog asking for confirmation before quitting.
+    SetConfirmPrompt(Option<ConfirmPrompt>),
     TakeScreenshot(TestingScreenshot),
     Redraw,
+    /// The terminal was resized; see [`event::Event::Resize`].
+    Resize {
+        width: usize,
+        height: usize,
+    },
+    Tick,
     EnsureSelectionInViewport,
     ScrollTo(isize),
+    /// Scroll the text of long lines so that the given number of columns are
+    /// hidden to the left.
+    ScrollHorizontallyTo(usize),
     SelectItem {
         selection_key: SelectionKey,
         ensure_in_viewport: bool,
@@ -42,30 +91,122 @@ enum StateUpdate {
     ToggleItemAndAdvance(SelectionKey, SelectionKey),
     ToggleAll,
     ToggleAllUniform,
+    SetVisualAnchor(Option<SelectionKey>),
+    ApplyToRange(RangeAction),
+    MoveItemToCommit(SelectionKey, usize),
+    FocusCommit(usize),
+    Undo,
+    Redo,
+    StartSearch,
+    UpdateSearchPrompt(String),
+    FinishSearch {
+        accept: bool,
+    },
+    ClearSearch,
+    SelectSearchMatch(SelectionKey),
+    StartFilter,
+    UpdateFilterPrompt(String),
+    FinishFilter {
+        accept: bool,
+    },
+    ClearFilter,
     SetExpandItem(SelectionKey, bool),
+    /// Reveal more lines of the given unchanged sections.
+    ExpandContext(Vec<(section::SectionKey, section::ContextExpansion)>),
     ToggleExpandItem(SelectionKey),
     ToggleExpandAll,
     ToggleCommitViewMode,
+    ToggleDiffViewMode,
+    ToggleLineWrap,
+    SetFileTreeMode(FileTreeMode),
     EditCommitMessage {
         commit_idx: usize,
     },
+    UpdateMessageEditor(MessageEditor),
+    FinishMessageEditor {
+        accept: bool,
+    },
+    /// Continue editing the commit message in the editor provided by the
+    /// `RecordInput`.
+    EditMessageExternally,
+    EditAddedLines(section::SectionKey),
+    /// Open the editor for the review comment on the given item.
+    EditComment(CommentKey),
+    /// Open or close the dialog listing all of the review comments.
+    SetCommentList(Option<CommentList>),
+    /// Close the comment list and select the item with the given comment.
+    JumpToComment(CommentKey),
+    /// Close the prompt to restore a saved session, restoring its selection
+    /// if `restore` is set.
+    FinishSessionPrompt {
+        restore: bool,
+    },
+}
+
+/// How to change the checked state of each line in the visual range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RangeAction {
+    Check,
+    Uncheck,
+    Flip,
 }
 
 #[allow(clippy::enum_variant_names)]
 enum ToggleSideEffects {
-    ToggledModeChangeSection(section::SectionKey, FileMode, FileMode, bool),
-    ToggledChangedSection(section::SectionKey, bool),
-    ToggledChangedLine(LineKey, bool),
+    ToggledModeChangeSection(section::SectionKey, FileMode, FileMode, usize),
+    ToggledChangedSection(section::SectionKey, usize),
+    ToggledChangedLine(LineKey, usize),
 }
 
 /// Holds the state of the UI, such as selection, expansion, and dialogs.
 struct UiState {
     commit_view_mode: CommitViewMode,
+    diff_view_mode: section::DiffViewMode,
+    file_tree_mode: FileTreeMode,
     expanded_items: HashSet<SelectionKey>,
+    /// The number of unchanged lines shown on each side of a changed section.
+    num_context_lines: usize,
+    /// The lines revealed beyond `num_context_lines` in each unchanged
+    /// section, indexed by file and section index. Unlike expanded items,
+    /// these are shared between the views of all commits.
+    context_expansions: HashMap<(usize, usize), section::ContextExpansion>,
     selection_key: SelectionKey,
     focused_commit_idx: usize,
     help_dialog: Option<help_dialog::HelpDialog>,
+    /// The commit message or review comment being edited, if the editor is
+    /// open.
+    message_editor: Option<MessageEditor>,
+    /// The prompt to restore the selection of an interrupted session, if one
+    /// was found.
+    session_prompt: Option<SessionPrompt>,
     scroll_offset_y: isize,
+    /// The number of columns of the text of each line which are scrolled out
+    /// of view to the left. Ignored while lines are wrapped.
+    scroll_offset_x: usize,
+    /// Whether to wrap long lines onto several rows instead of cutting them
+    /// off.
+    wrap_lines: bool,
+    search_prompt: Option<SearchPrompt>,
+    search_query: Option<SearchQuery>,
+    filter_prompt: Option<FilterPrompt>,
+    /// Only files matching this filter are shown, if set.
+    file_filter: Option<FileFilter>,
+    /// The other end of the visual range, if visual mode is active. The range
+    /// consists of the lines between this item and the selected item.
+    visual_anchor: Option<SelectionKey>,
+    /// The keys typed so far of a multi-key binding.
+    pending_keys: Vec<KeyChord>,
+    /// Whether to show the status bar at the bottom of the screen.
+    show_status_bar: bool,
+    /// Whether to ask for confirmation before accepting the selection, or
+    /// before discarding it if it was changed.
+    confirm_quit: bool,
+    /// The dialog asking for confirmation before quitting, if it's open.
+    confirm_prompt: Option<ConfirmPrompt>,
+    /// Whether comments can be left on files, sections and lines.
+    is_review_mode: bool,
+    /// The dialog listing all of the review comments, if it's open.
+    comment_list: Option<CommentList>,
 }
 
 /// Represents the application's state, combining the data model (`RecordState`)
@@ -74,28 +215,72 @@ struct UiState {
 struct App<'state> {
     state: RecordState<'state>,
     ui: UiState,
+    history: History,
+    keymap: Keymap,
+    theme: Theme,
+    highlighter: SyntaxHighlighter,
+    word_diffs: WordDiffCache,
+    selection_counts: SelectionCountsCache,
+    layout_cache: LayoutCache,
+    file_tree: FileTree,
+    /// The selection and commits when the recorder started, to tell whether
+    /// anything would be lost by quitting.
+    initial_selection: Vec<ChangeState>,
+    initial_commits: Vec<Commit>,
+    initial_comments: Vec<ReviewComment>,
+    /// The index in `state.comments` of the comment on each item.
+    comment_idxs: HashMap<CommentKey, usize>,
 }
 
 impl<'state> App<'state> {
     fn new(mut state: RecordState<'state>) -> Self {
-        // Ensure that there are at least two commits.
-        state.commits.extend(
-            iter::repeat_with(Commit::default).take(2_usize.saturating_sub(state.commits.len())),
-        );
-        if state.commits.len() > 2 {
-            unimplemented!("more than two commits");
-        }
-
+        normalize_commits(&mut state);
+        let file_tree = FileTree::new(&state.files);
+        let initial_selection = History::snapshot(&state.files);
+        let initial_commits = state.commits.clone();
+        let comment_idxs = review::index_comments(&state.files, &mut state.comments);
+        let initial_comments = state.comments.clone();
         let mut app = Self {
             state,
             ui: UiState {
                 commit_view_mode: CommitViewMode::Inline,
+                diff_view_mode: section::DiffViewMode::Unified,
+                file_tree_mode: FileTreeMode::Off,
                 expanded_items: Default::default(),
+                num_context_lines: section::DEFAULT_NUM_CONTEXT_LINES,
+                context_expansions: Default::default(),
                 selection_key: SelectionKey::None,
                 focused_commit_idx: 0,
                 help_dialog: None,
+                message_editor: None,
+                session_prompt: None,
                 scroll_offset_y: 0,
+                scroll_offset_x: 0,
+                wrap_lines: false,
+                search_prompt: None,
+                search_query: None,
+                filter_prompt: None,
+                file_filter: None,
+                visual_anchor: None,
+                pending_keys: Default::default(),
+                show_status_bar: true,
+                confirm_quit: false,
+                confirm_prompt: None,
+                is_review_mode: false,
+                comment_list: None,
             },
+            history: Default::default(),
+            keymap: Default::default(),
+            theme: Theme::default().honor_no_color(),
+            highlighter: Default::default(),
+            word_diffs: Default::default(),
+            selection_counts: Default::default(),
+            layout_cache: Default::default(),
+            file_tree,
+            initial_selection,
+            initial_commits,
+            initial_comments,
+            comment_idxs,
         };
         app.ui.selection_key = app.first_selection_key();
         app.expand_initial_items();
@@ -107,182 +292,755 @@ impl<'state> App<'state> {
         let RecordState {
             is_read_only,
             commits,
+            comments,
             files,
         } = &self.state;
-        let commit_views = match self.ui.commit_view_mode {
-            CommitViewMode::Inline => {
-                vec![CommitView {
-                    debug_info: None,
-                    commit_message_view: CommitMessageView {
-                        commit_idx: self.ui.focused_commit_idx,
-                        commit: &commits[self.ui.focused_commit_idx],
-                    },
-                    file_views: self.make_file_views(
-                        self.ui.focused_commit_idx,
-                        files,
-                        &debug_info,
-                        *is_read_only,
-                    ),
-                }]
+        let make_commit_view = |commit_idx: usize| {
+            let commit_message_view = CommitMessageView {
+                commit_idx,
+                commit: &commits[commit_idx],
+                theme: &self.theme,
+            };
+            let file_list_items = self.make_file_list_items(
+                commit_idx,
+                commit_message_view.height(),
+                files,
+                &debug_info,
+                *is_read_only,
+            );
+            CommitView {
+                debug_info: None,
+                commit_message_view,
+                file_list_items,
+                is_filtered: self.ui.file_filter.is_some(),
             }
-
-            CommitViewMode::Adjacent => commits
-                .iter()
-                .enumerate()
-                .map(|(commit_idx, commit)| CommitView {
-                    debug_info: None,
-                    commit_message_view: CommitMessageView { commit_idx, commit },
-                    file_views: self.make_file_views(commit_idx, files, &debug_info, *is_read_only),
-                })
-                .collect(),
+        };
+        let commit_views = match self.ui.commit_view_mode {
+            CommitViewMode::Inline => vec![make_commit_view(self.ui.focused_commit_idx)],
+            CommitViewMode::Adjacent => (0..commits.len()).map(make_commit_view).collect(),
         };
         AppView {
             debug_info: None,
             commit_view_mode: self.ui.commit_view_mode,
             commit_views,
+            file_tree_sidebar: match self.ui.file_tree_mode {
+                FileTreeMode::Off | FileTreeMode::Inline => None,
+                FileTreeMode::Sidebar => Some(self.make_file_tree_sidebar(files, *is_read_only)),
+            },
             help_dialog: self.ui.help_dialog.clone(),
+            message_editor_dialog: self.ui.message_editor.as_ref().map(|editor| {
+                MessageEditorDialog {
+                    editor,
+                    comment_location: match editor.target {
+                        MessageTarget::Commit(_) => None,
+                        MessageTarget::Comment(comment_key) => {
+                            review::make_review_comment(files, comment_key, String::new())
+                                .map(|comment| review::comment_location(&comment))
+                        }
+                    },
+                    theme: &self.theme,
+                }
+            }),
+            comment_list_dialog: self.ui.comment_list.as_ref().map(|comment_list| {
+                CommentListDialog {
+                    comments,
+                    focused_idx: comment_list.focused_idx,
+                    theme: &self.theme,
+                }
+            }),
+            session_dialog: self
+                .ui
+                .session_prompt
+                .as_ref()
+                .map(|prompt| SessionDialog { prompt }),
+            confirm_dialog: self
+                .ui
+                .confirm_prompt
+                .as_ref()
+                .map(|prompt| ConfirmDialog { prompt }),
+            search_bar: match (&self.ui.search_prompt, &self.ui.filter_prompt) {
+                (Some(search_prompt), _) => Some(SearchBar {
+                    prefix: "/",
+                    text: &search_prompt.text,
+                }),
+                (None, Some(filter_prompt)) => Some(SearchBar {
+                    prefix: "Filter: ",
+                    text: &filter_prompt.text,
+                }),
+                (None, None) => None,
+            },
+        }
+    }
+
+    /// Generates the status bar, if it's shown.
+    fn status_bar(&self) -> Option<StatusBar<'_>> {
+        if !self.ui.show_status_bar {
+            return None;
+        }
+
+        let RecordState {
+            is_read_only,
+            commits,
+            comments,
+            files,
+        } = &self.state;
+        let (path, section_num) = match self.ui.selection_key {
+            SelectionKey::None => (None, None),
+            SelectionKey::Dir(DirKey {
+                commit_idx: _,
+                dir_idx,
+            }) => {
+                let path = self.file_tree.dir_path(dir_idx);
+                (Some(path.to_string_lossy().into_owned().into()), None)
+            }
+            SelectionKey::File(FileKey {
+                commit_idx: _,
+                file_idx,
+            }) => (Some(files[file_idx].path.to_string_lossy()), None),
+            SelectionKey::Section(section::SectionKey {
+                commit_idx: _,
+                file_idx,
+                section_idx,
+            })
+            | SelectionKey::Line(LineKey {
+                commit_idx: _,
+                file_idx,
+                section_idx,
+                line_idx: _,
+            }) => {
+                let sections = &files[file_idx].sections;
+                let section_num = sections[section_idx].is_editable().then(|| {
+                    let num_editable =
+                        |sections: &[Section]| sections.iter().filter(|s| s.is_editable()).count();
+                    (
+                        num_editable(&sections[..=section_idx]),
+                        num_editable(sections),
+                    )
+                });
+                (Some(files[file_idx].path.to_string_lossy()), section_num)
+            }
+        };
+
+        Some(StatusBar {
+            path,
+            section_num,
+            counts: self.selection_counts.get(files, commits.len()),
+            commit_idx: self.ui.focused_commit_idx,
+            num_commits: commits.len(),
+            commit_view_mode: self.ui.commit_view_mode,
+            diff_view_mode: self.ui.diff_view_mode,
+            line_layout: self.line_layout(),
+            is_read_only: *is_read_only,
+            num_comments: self.ui.is_review_mode.then_some(comments.len()),
+            key_hints: self.key_hints(),
+            theme: &self.theme,
+        })
+    }
+
+    /// The key bindings most relevant to the focused item, in order of
+    /// importance, for the status bar.
+    fn key_hints(&self) -> Vec<KeyHint> {
+        let is_read_only = self.state.is_read_only;
+        let selection_key = self.ui.selection_key;
+        let expand_description = if self.ui.expanded_items.contains(&selection_key) {
+            "fold"
+        } else {
+            "expand"
+        };
+        let mut hints = Vec::new();
+        let mut hint = |event: event::Event, description: &'static str| {
+            if let Some(keys) = self.keymap.keys_for_event(&event).next() {
+                hints.push(KeyHint {
+                    keys: keys.to_string(),
+                    description,
+                });
+            }
+        };
+
+        if self.ui.visual_anchor.is_some() {
+            hint(event::Event::CheckRange, "check range");
+            hint(event::Event::UncheckRange, "uncheck range");
+            hint(event::Event::QuitEscape, "exit range");
+        } else {
+            match selection_key {
+                SelectionKey::None => {}
+                SelectionKey::Dir(_) | SelectionKey::File(_) => {
+                    if !is_read_only {
+                        hint(event::Event::ToggleItem, "toggle");
+                    }
+                    hint(event::Event::ExpandItem, expand_description);
+                }
+                SelectionKey::Section(section_key) => match self.section(section_key) {
+                    Ok(Section::Unchanged { .. }) => {
+                        hint(event::Event::ExpandContextWhole, "reveal context");
+                    }
+                    Ok(Section::Changed { lines }) => {
+                        if !is_read_only {
+                            hint(event::Event::ToggleItem, "toggle");
+                        }
+                        hint(event::Event::ExpandItem, expand_description);
+                        let has_added_lines = lines
+                            .iter()
+                            .any(|line| line.change_type == ChangeType::Added);
+                        if !is_read_only && has_added_lines {
+                            hint(event::Event::EditAddedLines, "edit");
+                        }
+                    }
+                    Ok(Section::FileMode { .. } | Section::Binary { .. }) => {
+                        if !is_read_only {
+                            hint(event::Event::ToggleItem, "toggle");
+                        }
+                    }
+                    Err(_) => {}
+                },
+                SelectionKey::Line(LineKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                    line_idx: _,
+                }) => {
+                    let section_key = section::SectionKey {
+                        commit_idx,
+                        file_idx,
+                        section_idx,
+                    };
+                    if !is_read_only && !self.is_unchanged_section(section_key) {
+                        hint(event::Event::ToggleItem, "toggle");
+                        hint(event::Event::ToggleItemAndAdvance, "toggle & next");
+                        hint(event::Event::ToggleVisualMode, "select range");
+                    }
+                }
+            }
+        }
+
+        if self.ui.is_review_mode {
+            if CommentKey::from_selection_key(selection_key).is_some() {
+                hint(event::Event::EditComment, "comment");
+            }
+            hint(event::Event::ListComments, "comments");
+        }
+        if !is_read_only {
+            hint(event::Event::QuitAccept, "confirm");
+        } else if self.ui.is_review_mode {
+            hint(event::Event::QuitAccept, "finish");
         }
+        hint(event::Event::Help, "help");
+        hints
     }
 
-    fn make_file_views(
+    /// Arrange the file views for the commit at `commit_idx` according to the
+    /// file tree mode, adding directory views if the tree is shown inline.
+    /// The list starts `y` rows below the top of the commit view. Files far
+    /// outside of the viewport are only laid out if their size isn't known,
+    /// so that very large diffs can be drawn quickly.
+    fn make_file_list_items(
         &'state self,
         commit_idx: usize,
+        y: usize,
         files: &'state [File<'state>],
         debug_info: &Option<AppDebugInfo>,
         is_read_only: bool,
-    ) -> Vec<FileView<'state>> {
-        files
-            .iter()
-            .enumerate()
-            .map(|(file_idx, file)| {
-                let file_key = FileKey {
-                    commit_idx,
-                    file_idx,
-                };
-                let file_toggled = self.file_tristate(file_key).unwrap();
-                let file_expanded = self.file_expanded(file_key);
-                let is_focused = match self.ui.selection_key {
-                    SelectionKey::None | SelectionKey::Section(_) | SelectionKey::Line(_) => false,
-                    SelectionKey::File(selected_file_key) => file_key == selected_file_key,
-                };
-                FileView {
-                    debug: debug_info.is_some(),
-                    file_key,
-                    toggle_box: TristateBox {
-                        id: ComponentId::ToggleBox(SelectionKey::File(file_key)),
-                        icon_style: TristateIconStyle::Check,
-                        tristate: file_toggled,
+    ) -> Vec<FileListItem<'state>> {
+        // The depth and tree row of each item, in the order that they're
+        // shown. The sidebar lists every file in the main view, regardless of
+        // which directories are collapsed in the sidebar.
+        let rows: Vec<(usize, FileTreeRow)> = match self.ui.file_tree_mode {
+            FileTreeMode::Off => (0..files.len())
+                .filter(|file_idx| self.is_file_visible(*file_idx))
+                .map(|file_idx| (0, FileTreeRow::File(file_idx)))
+                .collect(),
+            FileTreeMode::Inline => self
+                .file_tree
+                .rows
+                .iter()
+                .copied()
+                .filter(|row| self.is_file_tree_row_visible(*row))
+                .filter(|row| !self.is_hidden_in_file_tree(commit_idx, *row))
+                .map(|row| (self.file_tree.depth(row), row))
+                .collect(),
+            FileTreeMode::Sidebar => self
+                .file_tree
+                .rows
+                .iter()
+                .copied()
+                .filter(|row| match row {
+                    FileTreeRow::Dir(_) => false,
+                    FileTreeRow::File(file_idx) => self.is_file_visible(*file_idx),
+                })
+                .map(|row| (0, row))
+                .collect(),
+        };
+
+        let visual_line_ranges = self.visual_line_ranges();
+        let selected_file_key = self.ui.selection_key.file_key();
+        // Where the next item starts, as long as the sizes of all of the items
+        // before it are known.
+        let mut y = Some(y.unwrap_isize());
+        rows.into_iter()
+            .map(|(depth, row)| match row {
+                FileTreeRow::Dir(dir_idx) => {
+                    y = y.map(|y| y + DirView::HEIGHT.unwrap_isize());
+                    FileListItem::Dir {
+                        depth,
+                        dir_view: self.make_dir_view(
+                            DirKey {
+                                commit_idx,
+                                dir_idx,
+                            },
+                            is_read_only,
+                        ),
+                    }
+                }
+                FileTreeRow::File(file_idx) => {
+                    let file_key = FileKey {
+                        commit_idx,
+                        file_idx,
+                    };
+                    let size = self.layout_cache.file_size(file_key);
+                    let file_y = y;
+                    y = y.zip(size).map(|(y, size)| y + size.height.unwrap_isize());
+
+                    // The selected item is always drawn, so that it can be
+                    // scrolled into view.
+                    if let (Some(file_y), Some(size), Some(viewport_height)) =
+                        (file_y, size, self.layout_cache.viewport_height())
+                    {
+                        if selected_file_key != Some(file_key)
+                            && !is_nearly_visible(
+                                self.ui.scroll_offset_y,
+                                viewport_height,
+                                file_y,
+                                size.height,
+                            )
+                        {
+                            return FileListItem::Offscreen {
+                                depth,
+                                file_key,
+                                size,
+                            };
+                        }
+                    }
+
+                    let mut file_view = self.make_file_view(
+                        file_key,
+                        &files[file_idx],
+                        &visual_line_ranges,
+                        debug_info,
                         is_read_only,
-                    },
-                    expand_box: TristateBox {
-                        id: ComponentId::ExpandBox(SelectionKey::File(file_key)),
-                        icon_style: TristateIconStyle::Expand,
-                        tristate: file_expanded,
-                        is_read_only: false,
-                    },
-                    is_header_selected: is_focused,
-                    old_path: file.old_path.as_deref(),
-                    path: &file.path,
-                    section_views: {
-                        let mut section_views = Vec::new();
-                        let total_num_sections = file.sections.len();
-                        let total_num_editable_sections = file
-                            .sections
-                            .iter()
-                            .filter(|section| section.is_editable())
-                            .count();
+                    );
+                    // The directories are already shown above the file.
+                    if self.is_file_tree_mode_inline() {
+                        file_view.path = file_tree::file_name(file_view.path);
+                    }
+                    FileListItem::File { depth, file_view }
+                }
+            })
+            .collect()
+    }
 
-                        let mut line_num = 1;
-                        let mut editable_section_num = 0;
-                        for (section_idx, section) in file.sections.iter().enumerate() {
-                            let section_key = section::SectionKey {
+    fn make_dir_view(&'state self, dir_key: DirKey, is_read_only: bool) -> DirView<'state> {
+        let DirKey {
+            commit_idx: _,
+            dir_idx,
+        } = dir_key;
+        DirView {
+            dir_key,
+            name: &self.file_tree.dirs[dir_idx].name,
+            toggle_box: TristateBox {
+                is_read_only,
+                id: ComponentId::ToggleBox(SelectionKey::Dir(dir_key)),
+                tristate: self.dir_tristate(dir_key).unwrap(),
+                icon_style: TristateIconStyle::Check,
+                theme: &self.theme,
+            },
+            expand_box: TristateBox {
+                is_read_only: false,
+                id: ComponentId::ExpandBox(SelectionKey::Dir(dir_key)),
+                tristate: Tristate::from(
+                    self.ui.expanded_items.contains(&SelectionKey::Dir(dir_key)),
+                ),
+                icon_style: TristateIconStyle::Expand,
+                theme: &self.theme,
+            },
+            is_selected: self.ui.selection_key == SelectionKey::Dir(dir_key),
+            theme: &self.theme,
+        }
+    }
+
+    /// The directory tree of the focused commit view, shown in a sidebar.
+    fn make_file_tree_sidebar(
+        &'state self,
+        files: &'state [File<'state>],
+        is_read_only: bool,
+    ) -> FileTreeSidebar<'state> {
+        let commit_idx = self.ui.focused_commit_idx;
+        let selected_file_idx = match self.ui.selection_key {
+            SelectionKey::None | SelectionKey::Dir(_) => None,
+            SelectionKey::File(FileKey { file_idx, .. })
+            | SelectionKey::Section(section::SectionKey { file_idx, .. })
+            | SelectionKey::Line(LineKey { file_idx, .. }) => Some(file_idx),
+        };
+        let rows = self
+            .file_tree
+            .rows
+            .iter()
+            .filter(|row| self.is_file_tree_row_visible(**row))
+            .filter(|row| !self.is_hidden_in_file_tree(commit_idx, **row))
+            .map(|row| {
+                let depth = self.file_tree.depth(*row);
+                match *row {
+                    FileTreeRow::Dir(dir_idx) => {
+                        let dir_key = DirKey {
+                            commit_idx,
+                            dir_idx,
+                        };
+                        let DirView {
+                            dir_key: _,
+                            name,
+                            toggle_box,
+                            expand_box,
+                            is_selected,
+                            theme,
+                        } = self.make_dir_view(dir_key, is_read_only);
+                        FileTreeSidebarRow {
+                            selection_key: SelectionKey::Dir(dir_key),
+                            name,
+                            depth,
+                            toggle_box: TristateBox {
+                                id: ComponentId::SidebarToggleBox(SelectionKey::Dir(dir_key)),
+                                ..toggle_box
+                            },
+                            expand_box: Some(expand_box),
+                            is_selected,
+                            theme,
+                        }
+                    }
+                    FileTreeRow::File(file_idx) => {
+                        let file_key = FileKey {
+                            commit_idx,
+                            file_idx,
+                        };
+                        FileTreeSidebarRow {
+                            selection_key: SelectionKey::File(file_key),
+                            name: file_tree::file_name(&files[file_idx].path),
+                            depth,
+                            toggle_box: TristateBox {
+                                is_read_only,
+                                id: ComponentId::SidebarToggleBox(SelectionKey::File(file_key)),
+                                tristate: self.file_tristate(file_key).unwrap(),
+                                icon_style: TristateIconStyle::Check,
+                                theme: &self.theme,
+                            },
+                            expand_box: None,
+                            is_selected: selected_file_idx == Some(file_idx),
+                            theme: &self.theme,
+                        }
+                    }
+                }
+            })
+            .collect();
+        FileTreeSidebar {
+            rows,
+            theme: &self.theme,
+        }
+    }
+
+    fn make_file_view(
+        &'state self,
+        file_key: FileKey,
+        file: &'state File<'state>,
+        visual_line_ranges: &HashMap<section::SectionKey, Range<usize>>,
+        debug_info: &Option<AppDebugInfo>,
+        is_read_only: bool,
+    ) -> FileView<'state> {
+        let FileKey {
+            commit_idx,
+            file_idx,
+        } = file_key;
+        let file_toggled = self.file_tristate(file_key).unwrap();
+        let file_expanded = self.file_expanded(file_key);
+        let is_focused = match self.ui.selection_key {
+            SelectionKey::None
+            | SelectionKey::Dir(_)
+            | SelectionKey::Section(_)
+            | SelectionKey::Line(_) => false,
+            SelectionKey::File(selected_file_key) => file_key == selected_file_key,
+        };
+        // Only highlight files once they're expanded, since it can be
+        // expensive.
+        let highlights = match file_expanded {
+            Tristate::False => None,
+            Tristate::Partial | Tristate::True => {
+                self.highlighter
+                    .file_highlights(file_idx, file, &self.theme)
+            }
+        };
+        FileView {
+            debug: debug_info.is_some(),
+            file_key,
+            toggle_box: TristateBox {
+                id: ComponentId::ToggleBox(SelectionKey::File(file_key)),
+                icon_style: TristateIconStyle::Check,
+                tristate: file_toggled,
+                is_read_only,
+                theme: &self.theme,
+            },
+            expand_box: TristateBox {
+                id: ComponentId::ExpandBox(SelectionKey::File(file_key)),
+                icon_style: TristateIconStyle::Expand,
+                tristate: file_expanded,
+                is_read_only: false,
+                theme: &self.theme,
+            },
+            is_header_selected: is_focused,
+            is_commented: self
+                .comment_idxs
+                .contains_key(&CommentKey::File { file_idx }),
+            old_path: file.old_path.as_deref(),
+            path: &file.path,
+            section_views: {
+                let mut section_views = Vec::new();
+                let total_num_sections = file.sections.len();
+                let total_num_editable_sections = file
+                    .sections
+                    .iter()
+                    .filter(|section| section.is_editable())
+                    .count();
+
+                let start_line_nums = review::section_start_line_nums(file);
+                let mut editable_section_num = 0;
+                for (section_idx, section) in file.sections.iter().enumerate() {
+                    let section_key = section::SectionKey {
+                        commit_idx,
+                        file_idx,
+                        section_idx,
+                    };
+                    let section_toggled = self.section_tristate(section_key).unwrap();
+                    let section_expanded = Tristate::from(
+                        self.ui
+                            .expanded_items
+                            .contains(&SelectionKey::Section(section_key)),
+                    );
+                    if section.is_editable() {
+                        editable_section_num += 1;
+                    }
+                    section_views.push(section::SectionView {
+                        is_read_only,
+                        section_key,
+                        toggle_box: TristateBox {
+                            is_read_only,
+                            id: ComponentId::ToggleBox(SelectionKey::Section(section_key)),
+                            tristate: section_toggled,
+                            icon_style: TristateIconStyle::Check,
+                            theme: &self.theme,
+                        },
+                        expand_box: TristateBox {
+                            is_read_only: false,
+                            id: ComponentId::ExpandBox(SelectionKey::Section(section_key)),
+                            tristate: section_expanded,
+                            icon_style: TristateIconStyle::Expand,
+                            theme: &self.theme,
+                        },
+                        selection: match self.ui.selection_key {
+                            SelectionKey::None | SelectionKey::Dir(_) | SelectionKey::File(_) => {
+                                None
+                            }
+                            SelectionKey::Section(selected_section_key) => {
+                                if selected_section_key == section_key {
+                                    Some(section::SectionSelection::SectionHeader)
+                                } else {
+                                    None
+                                }
+                            }
+                            SelectionKey::Line(LineKey {
                                 commit_idx,
                                 file_idx,
                                 section_idx,
-                            };
-                            let section_toggled = self.section_tristate(section_key).unwrap();
-                            let section_expanded = Tristate::from(
-                                self.ui
+                                line_idx,
+                            }) => {
+                                let selected_section_key = section::SectionKey {
+                                    commit_idx,
+                                    file_idx,
+                                    section_idx,
+                                };
+                                if selected_section_key == section_key {
+                                    Some(section::SectionSelection::Line(line_idx))
+                                } else {
+                                    None
+                                }
+                            }
+                        },
+                        visual_line_range: visual_line_ranges
+                            .get(&section_key)
+                            .cloned()
+                            .unwrap_or_default(),
+                        total_num_sections,
+                        editable_section_num,
+                        total_num_editable_sections,
+                        section,
+                        line_start_num: start_line_nums[section_idx].0,
+                        new_line_start_num: start_line_nums[section_idx].1,
+                        diff_view_mode: self.ui.diff_view_mode,
+                        line_layout: self.line_layout(),
+                        num_context_lines: self.ui.num_context_lines,
+                        context_expansion: self
+                            .ui
+                            .context_expansions
+                            .get(&(file_idx, section_idx))
+                            .copied()
+                            .unwrap_or_default(),
+                        num_commits: self.state.commits.len(),
+                        search_query: self.ui.search_query.as_ref(),
+                        highlights: highlights.clone(),
+                        word_diff: match section {
+                            Section::Changed { lines }
+                                if self
+                                    .ui
                                     .expanded_items
-                                    .contains(&SelectionKey::Section(section_key)),
-                            );
-                            if section.is_editable() {
-                                editable_section_num += 1;
+                                    .contains(&SelectionKey::Section(section_key)) =>
+                            {
+                                Some(self.word_diffs.section_word_diff(
+                                    file_idx,
+                                    section_idx,
+                                    lines,
+                                ))
                             }
-                            section_views.push(section::SectionView {
-                                is_read_only,
-                                section_key,
-                                toggle_box: TristateBox {
-                                    is_read_only,
-                                    id: ComponentId::ToggleBox(SelectionKey::Section(section_key)),
-                                    tristate: section_toggled,
-                                    icon_style: TristateIconStyle::Check,
-                                },
-                                expand_box: TristateBox {
-                                    is_read_only: false,
-                                    id: ComponentId::ExpandBox(SelectionKey::Section(section_key)),
-                                    tristate: section_expanded,
-                                    icon_style: TristateIconStyle::Expand,
-                                },
-                                selection: match self.ui.selection_key {
-                                    SelectionKey::None | SelectionKey::File(_) => None,
-                                    SelectionKey::Section(selected_section_key) => {
-                                        if selected_section_key == section_key {
-                                            Some(section::SectionSelection::SectionHeader)
-                                        } else {
-                                            None
-                                        }
-                                    }
-                                    SelectionKey::Line(LineKey {
-                                        commit_idx,
-                                        file_idx,
-                                        section_idx,
-                                        line_idx,
-                                    }) => {
-                                        let selected_section_key = section::SectionKey {
-                                            commit_idx,
-                                            file_idx,
-                                            section_idx,
-                                        };
-                                        if selected_section_key == section_key {
-                                            Some(section::SectionSelection::ChangedLine(line_idx))
-                                        } else {
-                                            None
-                                        }
-                                    }
-                                },
-                                total_num_sections,
-                                editable_section_num,
-                                total_num_editable_sections,
-                                section,
-                                line_start_num: line_num,
-                            });
-
-                            line_num += match section {
-                                Section::Unchanged { lines } => lines.len(),
-                                Section::Changed { lines } => lines
-                                    .iter()
-                                    .filter(|changed_line| match changed_line.change_type {
-                                        ChangeType::Added => false,
-                                        ChangeType::Removed => true,
-                                    })
-                                    .count(),
-                                Section::FileMode { .. } | Section::Binary { .. } => 0,
-                            };
-                        }
-                        section_views
-                    },
+                            Section::Unchanged { .. }
+                            | Section::Changed { .. }
+                            | Section::FileMode { .. }
+                            | Section::Binary { .. } => None,
+                        },
+                        is_commented: self.comment_idxs.contains_key(&CommentKey::Section {
+                            file_idx,
+                            section_idx,
+                        }),
+                        commented_line_idxs: self.commented_line_idxs(file_idx, section_idx),
+                        theme: &self.theme,
+                    });
+                }
+                section_views
+            },
+            theme: &self.theme,
+        }
+    }
+
+    /// How to show the text of lines too long to fit in the view.
+    fn line_layout(&self) -> LineLayout {
+        if self.ui.wrap_lines {
+            LineLayout::Wrap
+        } else {
+            LineLayout::Scroll {
+                offset_x: self.ui.scroll_offset_x,
+            }
+        }
+    }
+
+    /// The number of columns taken by the text of the longest line.
+    fn max_line_width(&self) -> usize {
+        self.state
+            .files
+            .iter()
+            .flat_map(|file| &file.sections)
+            .filter_map(|section| match section {
+                Section::Unchanged { lines } => lines
+                    .iter()
+                    .map(|line| line::line_display_width(line))
+                    .max(),
+                Section::Changed { lines } => lines
+                    .iter()
+                    .map(|line| line::line_display_width(&line.line))
+                    .max(),
+                Section::FileMode { .. } | Section::Binary { .. } => None,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The indices of the lines with a review comment in the section at
+    /// `section_idx` of the file at `file_idx`.
+    fn commented_line_idxs(&self, file_idx: usize, section_idx: usize) -> HashSet<usize> {
+        self.comment_idxs
+            .keys()
+            .filter_map(|comment_key| match *comment_key {
+                CommentKey::Line {
+                    file_idx: comment_file_idx,
+                    section_idx: comment_section_idx,
+                    line_idx,
+                } if (comment_file_idx, comment_section_idx) == (file_idx, section_idx) => {
+                    Some(line_idx)
+                }
+                CommentKey::File { .. } | CommentKey::Section { .. } | CommentKey::Line { .. } => {
+                    None
                 }
             })
             .collect()
     }
 
+    /// Interpret a key press according to the keymap, keeping track of the
+    /// keys typed so far of multi-key bindings. Other events, and key presses
+    /// while a text prompt is open, are returned unchanged.
+    fn resolve_key_event(&mut self, event: event::Event) -> event::Event {
+        let key_chord = match event {
+            event::Event::Key(key_chord)
+                if self.ui.search_prompt.is_none()
+                    && self.ui.filter_prompt.is_none()
+                    && self.ui.message_editor.is_none()
+                    && self.ui.session_prompt.is_none()
+                    && self.ui.confirm_prompt.is_none()
+                    && self.ui.comment_list.is_none() =>
+            {
+                key_chord
+            }
+            event => return event,
+        };
+
+        let mut keys = std::mem::take(&mut self.ui.pending_keys);
+        let had_pending_keys = !keys.is_empty();
+        keys.push(key_chord);
+        match self.keymap.lookup(&keys) {
+            KeymapLookup::Event(event) => event,
+            KeymapLookup::Prefix => {
+                self.ui.pending_keys = keys;
+                event::Event::None
+            }
+            // The pending keys didn't form a binding, so interpret the latest
+            // key on its own.
+            KeymapLookup::Unbound if had_pending_keys => {
+                self.resolve_key_event(event::Event::Key(key_chord))
+            }
+            KeymapLookup::Unbound => event::Event::None,
+        }
+    }
+
     fn handle_event(
         &self,
         event: event::Event,
         term_height: usize,
         drawn_rects: &DrawnRects<ComponentId>,
     ) -> Result<StateUpdate, RecordError> {
+        if let event::Event::Key(key_chord) = event {
+            if self.ui.session_prompt.is_some() {
+                return Ok(Self::handle_session_prompt_key(key_chord));
+            }
+            if let Some(confirm_prompt) = &self.ui.confirm_prompt {
+                return Ok(self.handle_confirm_prompt_key(confirm_prompt, key_chord));
+            }
+            if let Some(message_editor) = &self.ui.message_editor {
+                return Ok(self.handle_message_editor_key(message_editor, key_chord));
+            }
+            if let Some(comment_list) = &self.ui.comment_list {
+                return Ok(self.handle_comment_list_key(comment_list, key_chord));
+            }
+            return Ok(match (&self.ui.search_prompt, &self.ui.filter_prompt) {
+                (Some(search_prompt), _) => self.handle_search_prompt_key(search_prompt, key_chord),
+                (None, Some(filter_prompt)) => {
+                    self.handle_filter_prompt_key(filter_prompt, key_chord)
+                }
+                // Should have been resolved by `resolve_key_event`.
+                (None, None) => StateUpdate::None,
+            });
+        }
+
         // If the help dialog is open, certain keys will close it.
         if self.ui.help_dialog.is_some()
             && matches!(
@@ -292,21 +1050,55 @@ impl<'state> App<'state> {
                     | event::Event::QuitCancel
                     | event::Event::ToggleItem
                     | event::Event::ToggleItemAndAdvance
-            ) {
-                return Ok(StateUpdate::SetHelpDialog(None));
+            )
+        {
+            return Ok(StateUpdate::SetHelpDialog(None));
+        }
+
+        // Repeating the action being confirmed confirms it.
+        if let Some(confirm_prompt) = &self.ui.confirm_prompt {
+            match (confirm_prompt, &event) {
+                (ConfirmPrompt::Accept { .. }, event::Event::QuitAccept) => {
+                    return Ok(StateUpdate::QuitAccept);
+                }
+                (ConfirmPrompt::Cancel, event::Event::QuitCancel) => {
+                    return Ok(StateUpdate::QuitCancel);
+                }
+                (_, event::Event::QuitEscape) => return Ok(StateUpdate::SetConfirmPrompt(None)),
+                _ => {}
             }
+        }
 
         let state_update = match event {
             event::Event::None => StateUpdate::None,
             event::Event::Redraw => StateUpdate::Redraw,
+            event::Event::Resize { width, height } => StateUpdate::Resize { width, height },
+            event::Event::Tick => StateUpdate::Tick,
             event::Event::EnsureSelectionInViewport => StateUpdate::EnsureSelectionInViewport,
 
             event::Event::Help => StateUpdate::SetHelpDialog(Some(HelpDialog())),
 
-            // Confirm changes and quit.
-            event::Event::QuitAccept => StateUpdate::QuitAccept,
+            // Confirm changes and quit, asking first if configured. (If the
+            // help dialog is open, it's closed instead.)
+            event::Event::QuitAccept => {
+                if self.ui.confirm_quit && !self.state.is_read_only && self.ui.help_dialog.is_none()
+                {
+                    StateUpdate::SetConfirmPrompt(Some(ConfirmPrompt::accept(&self.state.files)))
+                } else {
+                    StateUpdate::QuitAccept
+                }
+            }
+            // Cancel changes and quit, asking first if configured and there
+            // are changes to lose.
+            event::Event::QuitCancel => {
+                if self.ui.confirm_quit && self.is_modified() {
+                    StateUpdate::SetConfirmPrompt(Some(ConfirmPrompt::Cancel))
+                } else {
+                    StateUpdate::QuitCancel
+                }
+            }
             // Cancel changes and quit immediately.
-            event::Event::QuitCancel | event::Event::QuitInterrupt => StateUpdate::QuitCancel,
+            event::Event::QuitInterrupt => StateUpdate::QuitCancel,
 
             event::Event::TakeScreenshot(screenshot) => StateUpdate::TakeScreenshot(screenshot),
             event::Event::ScrollUp => {
@@ -325,6 +1117,21 @@ impl<'state> App<'state> {
                     .scroll_offset_y
                     .saturating_add(term_height.unwrap_isize()),
             ),
+            event::Event::ScrollLeft => match self.line_layout() {
+                LineLayout::Scroll { offset_x } => StateUpdate::ScrollHorizontallyTo(
+                    offset_x.saturating_sub(line::HORIZONTAL_SCROLL_STEP),
+                ),
+                LineLayout::Wrap => StateUpdate::None,
+            },
+            event::Event::ScrollRight => match self.line_layout() {
+                // Keep at least the end of the longest line in view.
+                LineLayout::Scroll { offset_x } => StateUpdate::ScrollHorizontallyTo(
+                    offset_x
+                        .saturating_add(line::HORIZONTAL_SCROLL_STEP)
+                        .min(self.max_line_width().saturating_sub(1)),
+                ),
+                LineLayout::Wrap => StateUpdate::None,
+            },
             event::Event::FocusPrev => {
                 let (keys, index) = self.find_selection();
                 let selection_key = self.select_prev(&keys, index);
@@ -379,83 +1186,715 @@ impl<'state> App<'state> {
                     ensure_in_viewport: true,
                 }
             }
+            event::Event::ToggleItem | event::Event::ToggleItemAndAdvance
+                if self.ui.visual_anchor.is_some() =>
+            {
+                StateUpdate::ApplyToRange(RangeAction::Flip)
+            }
             event::Event::ToggleItem => StateUpdate::ToggleItem(self.ui.selection_key),
             event::Event::ToggleItemAndAdvance => {
                 let advanced_key = self.advance_to_next_of_kind();
                 StateUpdate::ToggleItemAndAdvance(self.ui.selection_key, advanced_key)
             }
             event::Event::ToggleAll => StateUpdate::ToggleAll,
+            event::Event::ToggleVisualMode => match self.ui.visual_anchor {
+                Some(_) => StateUpdate::SetVisualAnchor(None),
+                None => StateUpdate::SetVisualAnchor(Some(self.ui.selection_key)),
+            },
+            event::Event::CheckRange => StateUpdate::ApplyToRange(RangeAction::Check),
+            event::Event::UncheckRange => StateUpdate::ApplyToRange(RangeAction::Uncheck),
             event::Event::ToggleAllUniform => StateUpdate::ToggleAllUniform,
+            event::Event::Undo => StateUpdate::Undo,
+            event::Event::Redo => StateUpdate::Redo,
+            event::Event::MoveItemToNextCommit => {
+                match self
+                    .item_commit_idxs(self.ui.selection_key)?
+                    .into_iter()
+                    .max()
+                {
+                    Some(commit_idx) => StateUpdate::MoveItemToCommit(
+                        self.ui.selection_key,
+                        (commit_idx + 1).min(self.state.commits.len() - 1),
+                    ),
+                    None => StateUpdate::None,
+                }
+            }
+            event::Event::MoveItemToPrevCommit => {
+                match self
+                    .item_commit_idxs(self.ui.selection_key)?
+                    .into_iter()
+                    .min()
+                {
+                    Some(commit_idx) => StateUpdate::MoveItemToCommit(
+                        self.ui.selection_key,
+                        commit_idx.saturating_sub(1),
+                    ),
+                    None => StateUpdate::None,
+                }
+            }
+            event::Event::FocusNextCommit => StateUpdate::FocusCommit(
+                (self.ui.focused_commit_idx + 1).min(self.state.commits.len() - 1),
+            ),
+            event::Event::FocusPrevCommit => {
+                StateUpdate::FocusCommit(self.ui.focused_commit_idx.saturating_sub(1))
+            }
+            event::Event::Search => StateUpdate::StartSearch,
+            event::Event::SearchNext | event::Event::SearchPrev => {
+                let select_previous = matches!(event, event::Event::SearchPrev);
+                match self.ui.search_query.as_ref().and_then(|search_query| {
+                    self.find_search_match(
+                        search_query,
+                        self.ui.selection_key,
+                        select_previous,
+                        /*include_start=*/ false,
+                    )
+                }) {
+                    Some(selection_key) => StateUpdate::SelectSearchMatch(selection_key),
+                    None => StateUpdate::None,
+                }
+            }
+            event::Event::FilterFiles => StateUpdate::StartFilter,
             event::Event::ExpandItem => StateUpdate::ToggleExpandItem(self.ui.selection_key),
+            event::Event::ExpandContextAbove => {
+                self.expand_context_around_selection(section::ContextExpansion {
+                    num_leading_lines: 0,
+                    num_trailing_lines: section::NUM_CONTEXT_LINES_PER_EXPANSION,
+                })?
+            }
+            event::Event::ExpandContextBelow => {
+                self.expand_context_around_selection(section::ContextExpansion {
+                    num_leading_lines: section::NUM_CONTEXT_LINES_PER_EXPANSION,
+                    num_trailing_lines: 0,
+                })?
+            }
+            event::Event::ExpandContextWhole => {
+                self.expand_context_around_selection(section::ContextExpansion::whole())?
+            }
             event::Event::ExpandAll => StateUpdate::ToggleExpandAll,
             event::Event::EditCommitMessage => StateUpdate::EditCommitMessage {
                 commit_idx: self.ui.focused_commit_idx,
             },
+            event::Event::EditAddedLines => match self.ui.selection_key {
+                _ if self.state.is_read_only => StateUpdate::None,
+                SelectionKey::None | SelectionKey::Dir(_) | SelectionKey::File(_) => {
+                    StateUpdate::None
+                }
+                SelectionKey::Section(section_key) => StateUpdate::EditAddedLines(section_key),
+                SelectionKey::Line(LineKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                    line_idx: _,
+                }) => StateUpdate::EditAddedLines(section::SectionKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                }),
+            },
+            event::Event::EditComment => {
+                match CommentKey::from_selection_key(self.ui.selection_key) {
+                    Some(comment_key) if self.ui.is_review_mode => {
+                        StateUpdate::EditComment(comment_key)
+                    }
+                    Some(_) | None => StateUpdate::None,
+                }
+            }
+            event::Event::ListComments => {
+                if self.ui.is_review_mode {
+                    // Start at the comment on the selected item, if any.
+                    let focused_idx = CommentKey::from_selection_key(self.ui.selection_key)
+                        .and_then(|comment_key| self.comment_idxs.get(&comment_key))
+                        .copied()
+                        .unwrap_or_default();
+                    StateUpdate::SetCommentList(Some(CommentList { focused_idx }))
+                } else {
+                    StateUpdate::None
+                }
+            }
 
             event::Event::ToggleCommitViewMode => StateUpdate::ToggleCommitViewMode,
+            event::Event::ToggleDiffViewMode => StateUpdate::ToggleDiffViewMode,
+            event::Event::ToggleLineWrap => StateUpdate::ToggleLineWrap,
+            event::Event::ToggleFileTree => {
+                StateUpdate::SetFileTreeMode(self.ui.file_tree_mode.next())
+            }
+
+            event::Event::Click { row, column } => {
+                let component_id = self.find_component_at(drawn_rects, row, column);
+                self.click_component(component_id)
+            }
+
+            // generally ignore escape key, except to leave visual mode, to
+            // clear the search highlighting or to clear the file filter
+            event::Event::QuitEscape => {
+                if self.ui.visual_anchor.is_some() {
+                    StateUpdate::SetVisualAnchor(None)
+                } else if self.ui.search_query.is_some() {
+                    StateUpdate::ClearSearch
+                } else if self.ui.file_filter.is_some() {
+                    StateUpdate::ClearFilter
+                } else {
+                    StateUpdate::None
+                }
+            }
 
-            // generally ignore escape key
-            event::Event::QuitEscape => StateUpdate::None,
+            // Already handled above.
+            event::Event::Key(_) => StateUpdate::None,
         };
         Ok(state_update)
     }
 
-    fn first_selection_key(&self) -> SelectionKey {
-        match self.state.files.iter().enumerate().next() {
-            Some((file_idx, _)) => SelectionKey::File(FileKey {
-                commit_idx: self.ui.focused_commit_idx,
+    /// The topmost component drawn at the given terminal cell.
+    fn find_component_at(
+        &self,
+        drawn_rects: &DrawnRects<ComponentId>,
+        row: usize,
+        column: usize,
+    ) -> ComponentId {
+        let x = column.unwrap_isize();
+        let y = row.unwrap_isize() + self.ui.scroll_offset_y;
+        drawn_rects
+            .iter()
+            .filter(|(_id, DrawnRect { rect, timestamp: _ })| rect.contains_point(x, y))
+            // Components drawn later are drawn on top of earlier ones, and
+            // children are drawn after their parents.
+            .max_by_key(|(id, DrawnRect { rect: _, timestamp })| (*timestamp, **id))
+            .map(|(id, _drawn_rect)| *id)
+            .unwrap_or(ComponentId::App)
+    }
+
+    fn click_component(&self, component_id: ComponentId) -> StateUpdate {
+        if self.ui.help_dialog.is_some() {
+            return match component_id {
+                ComponentId::HelpDialogQuitButton => StateUpdate::SetHelpDialog(None),
+                _ => StateUpdate::None,
+            };
+        }
+        if self.ui.session_prompt.is_some() {
+            return match component_id {
+                ComponentId::SessionRestoreButton => {
+                    StateUpdate::FinishSessionPrompt { restore: true }
+                }
+                ComponentId::SessionDiscardButton => {
+                    StateUpdate::FinishSessionPrompt { restore: false }
+                }
+                _ => StateUpdate::None,
+            };
+        }
+        if let Some(confirm_prompt) = &self.ui.confirm_prompt {
+            return match component_id {
+                ComponentId::ConfirmButton => Self::confirm(confirm_prompt),
+                ComponentId::ConfirmBackButton => StateUpdate::SetConfirmPrompt(None),
+                _ => StateUpdate::None,
+            };
+        }
+        if self.ui.message_editor.is_some() {
+            return match component_id {
+                ComponentId::MessageEditorSaveButton => {
+                    StateUpdate::FinishMessageEditor { accept: true }
+                }
+                ComponentId::MessageEditorCancelButton => {
+                    StateUpdate::FinishMessageEditor { accept: false }
+                }
+                _ => StateUpdate::None,
+            };
+        }
+        if self.ui.comment_list.is_some() {
+            return match component_id {
+                ComponentId::CommentListCloseButton => StateUpdate::SetCommentList(None),
+                _ => StateUpdate::None,
+            };
+        }
+
+        match component_id {
+            ComponentId::App
+            | ComponentId::AppFiles
+            | ComponentId::CommitViews
+            | ComponentId::CommitMessageView
+            | ComponentId::HelpDialog
+            | ComponentId::HelpDialogQuitButton
+            | ComponentId::MessageEditorDialog
+            | ComponentId::MessageEditorSaveButton
+            | ComponentId::MessageEditorCancelButton
+            | ComponentId::SessionDialog
+            | ComponentId::SessionRestoreButton
+            | ComponentId::SessionDiscardButton
+            | ComponentId::ConfirmDialog
+            | ComponentId::ConfirmButton
+            | ComponentId::ConfirmBackButton
+            | ComponentId::CommentListDialog
+            | ComponentId::CommentListCloseButton
+            | ComponentId::FileTreeSidebar
+            | ComponentId::SearchBar
+            | ComponentId::StatusBar => StateUpdate::None,
+            ComponentId::FileViewHeader(file_key) => self.click_item(SelectionKey::File(file_key)),
+            ComponentId::SelectableItem(selection_key) => self.click_item(selection_key),
+            ComponentId::ToggleBox(selection_key) => {
+                // Toggling is relative to the focused commit, so a toggle box
+                // in the view for another commit only selects the item.
+                if selection_key.commit_idx() == Some(self.ui.focused_commit_idx) {
+                    StateUpdate::ToggleItemAndAdvance(selection_key, selection_key)
+                } else {
+                    self.click_item(selection_key)
+                }
+            }
+            ComponentId::ExpandBox(selection_key) => StateUpdate::ToggleExpandItem(selection_key),
+            ComponentId::ContextEllipsis(section_key) => {
+                StateUpdate::ExpandContext(vec![(section_key, section::ContextExpansion::whole())])
+            }
+            ComponentId::SidebarRow(selection_key) => match selection_key {
+                // Directories in the sidebar aren't drawn in the main view
+                // unless the tree is also shown inline, so they can only be
+                // expanded or collapsed.
+                SelectionKey::Dir(_) if !self.all_selection_keys().contains(&selection_key) => {
+                    StateUpdate::ToggleExpandItem(selection_key)
+                }
+                selection_key => StateUpdate::SelectItem {
+                    selection_key,
+                    ensure_in_viewport: true,
+                },
+            },
+            ComponentId::SidebarToggleBox(selection_key) => StateUpdate::ToggleItem(selection_key),
+            ComponentId::CommitEditMessageButton(commit_idx) => {
+                StateUpdate::EditCommitMessage { commit_idx }
+            }
+        }
+    }
+
+    /// Reveal more of the unchanged sections around the selected section:
+    /// `expansion.num_trailing_lines` more lines of the one above it, and
+    /// `expansion.num_leading_lines` more lines of the one below it.
+    fn expand_context_around_selection(
+        &self,
+        expansion: section::ContextExpansion,
+    ) -> Result<StateUpdate, RecordError> {
+        let section_key = match self.ui.selection_key {
+            SelectionKey::None | SelectionKey::Dir(_) | SelectionKey::File(_) => {
+                return Ok(StateUpdate::None);
+            }
+            SelectionKey::Section(section_key) => section_key,
+            SelectionKey::Line(LineKey {
+                commit_idx,
                 file_idx,
-            }),
-            None => SelectionKey::None,
+                section_idx,
+                line_idx: _,
+            }) => section::SectionKey {
+                commit_idx,
+                file_idx,
+                section_idx,
+            },
+        };
+        let section::ContextExpansion {
+            num_leading_lines,
+            num_trailing_lines,
+        } = expansion;
+        let file = self.file(FileKey {
+            commit_idx: section_key.commit_idx,
+            file_idx: section_key.file_idx,
+        })?;
+        let is_unchanged = |section_idx: usize| match file.sections.get(section_idx) {
+            Some(Section::Unchanged { .. }) => true,
+            Some(Section::Changed { .. } | Section::FileMode { .. } | Section::Binary { .. })
+            | None => false,
+        };
+
+        let mut expansions = Vec::new();
+        if let Some(section_idx) = section_key.section_idx.checked_sub(1) {
+            if is_unchanged(section_idx) && num_trailing_lines > 0 {
+                expansions.push((
+                    section::SectionKey {
+                        section_idx,
+                        ..section_key
+                    },
+                    section::ContextExpansion {
+                        num_leading_lines: 0,
+                        num_trailing_lines,
+                    },
+                ));
+            }
+        }
+        let section_idx = section_key.section_idx + 1;
+        if is_unchanged(section_idx) && num_leading_lines > 0 {
+            expansions.push((
+                section::SectionKey {
+                    section_idx,
+                    ..section_key
+                },
+                section::ContextExpansion {
+                    num_leading_lines,
+                    num_trailing_lines: 0,
+                },
+            ));
+        }
+        Ok(StateUpdate::ExpandContext(expansions))
+    }
+
+    fn click_item(&self, selection_key: SelectionKey) -> StateUpdate {
+        // Unchanged lines are drawn as items, but can only be selected in
+        // review mode.
+        let is_selectable = selection_key
+            .commit_idx()
+            .map(|commit_idx| {
+                self.all_selection_keys_for_commit(commit_idx)
+                    .contains(&selection_key)
+            })
+            .unwrap_or(false);
+        if is_selectable {
+            StateUpdate::SelectItem {
+                selection_key,
+                ensure_in_viewport: false,
+            }
+        } else {
+            StateUpdate::None
+        }
+    }
+
+    fn handle_search_prompt_key(
+        &self,
+        search_prompt: &SearchPrompt,
+        key_chord: KeyChord,
+    ) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        match (code, modifiers) {
+            (KeyCode::Enter, _) => StateUpdate::FinishSearch { accept: true },
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                StateUpdate::FinishSearch { accept: false }
+            }
+            (KeyCode::Backspace, _) => {
+                let mut text = search_prompt.text.clone();
+                if text.pop().is_some() {
+                    StateUpdate::UpdateSearchPrompt(text)
+                } else {
+                    StateUpdate::FinishSearch { accept: false }
+                }
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                let mut text = search_prompt.text.clone();
+                text.push(c);
+                StateUpdate::UpdateSearchPrompt(text)
+            }
+            _ => StateUpdate::None,
+        }
+    }
+
+    fn handle_session_prompt_key(key_chord: KeyChord) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        match (code, modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => StateUpdate::QuitCancel,
+            (KeyCode::Enter | KeyCode::Char('y' | 'r'), _) => {
+                StateUpdate::FinishSessionPrompt { restore: true }
+            }
+            (KeyCode::Esc | KeyCode::Char('n' | 'd' | 'q'), _) => {
+                StateUpdate::FinishSessionPrompt { restore: false }
+            }
+            _ => StateUpdate::None,
+        }
+    }
+
+    fn handle_confirm_prompt_key(
+        &self,
+        confirm_prompt: &ConfirmPrompt,
+        key_chord: KeyChord,
+    ) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        match (code, modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => StateUpdate::QuitCancel,
+            (KeyCode::Enter | KeyCode::Char('y'), _) => Self::confirm(confirm_prompt),
+            (KeyCode::Esc | KeyCode::Char('n'), _) => StateUpdate::SetConfirmPrompt(None),
+            // Pressing the key for the action being confirmed again also
+            // confirms it.
+            _ => match self.keymap.lookup(&[key_chord]) {
+                KeymapLookup::Event(event::Event::QuitAccept)
+                    if matches!(confirm_prompt, ConfirmPrompt::Accept { .. }) =>
+                {
+                    Self::confirm(confirm_prompt)
+                }
+                KeymapLookup::Event(event::Event::QuitCancel)
+                    if matches!(confirm_prompt, ConfirmPrompt::Cancel) =>
+                {
+                    Self::confirm(confirm_prompt)
+                }
+                KeymapLookup::Event(_) | KeymapLookup::Prefix | KeymapLookup::Unbound => {
+                    StateUpdate::None
+                }
+            },
+        }
+    }
+
+    /// Carry out the action which `confirm_prompt` asks to confirm.
+    fn confirm(confirm_prompt: &ConfirmPrompt) -> StateUpdate {
+        match confirm_prompt {
+            ConfirmPrompt::Accept { .. } => StateUpdate::QuitAccept,
+            ConfirmPrompt::Cancel => StateUpdate::QuitCancel,
+        }
+    }
+
+    fn handle_message_editor_key(
+        &self,
+        message_editor: &MessageEditor,
+        key_chord: KeyChord,
+    ) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        let mut message_editor = message_editor.clone();
+        let is_word_motion = modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match (code, modifiers) {
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                return StateUpdate::FinishMessageEditor { accept: true };
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                return StateUpdate::FinishMessageEditor { accept: false };
+            }
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => match message_editor.target {
+                MessageTarget::Commit(_) => return StateUpdate::EditMessageExternally,
+                MessageTarget::Comment(_) => return StateUpdate::None,
+            },
+            (KeyCode::Enter, _) => message_editor.insert('\n'),
+            (KeyCode::Backspace, _) if is_word_motion => message_editor.delete_word_backward(),
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => message_editor.delete_word_backward(),
+            (KeyCode::Backspace, _) => message_editor.backspace(),
+            (KeyCode::Delete, _) => message_editor.delete(),
+            (KeyCode::Left, _) if is_word_motion => message_editor.move_word_left(),
+            (KeyCode::Right, _) if is_word_motion => message_editor.move_word_right(),
+            (KeyCode::Left, _) => message_editor.move_left(),
+            (KeyCode::Right, _) => message_editor.move_right(),
+            (KeyCode::Up, _) => message_editor.move_up(),
+            (KeyCode::Down, _) => message_editor.move_down(),
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                message_editor.move_home()
+            }
+            (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
+                message_editor.move_end()
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                message_editor.insert(c)
+            }
+            _ => return StateUpdate::None,
+        }
+        StateUpdate::UpdateMessageEditor(message_editor)
+    }
+
+    fn handle_comment_list_key(
+        &self,
+        comment_list: &CommentList,
+        key_chord: KeyChord,
+    ) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        let CommentList { focused_idx } = *comment_list;
+        match (code, modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => StateUpdate::QuitCancel,
+            (KeyCode::Esc | KeyCode::Char('q' | 'M'), _) => StateUpdate::SetCommentList(None),
+            (KeyCode::Up | KeyCode::Char('k'), _) => {
+                StateUpdate::SetCommentList(Some(CommentList {
+                    focused_idx: focused_idx.saturating_sub(1),
+                }))
+            }
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                StateUpdate::SetCommentList(Some(CommentList {
+                    focused_idx: (focused_idx + 1).min(self.state.comments.len().saturating_sub(1)),
+                }))
+            }
+            (KeyCode::Enter, _) => {
+                // Comments whose items couldn't be found can't be jumped to.
+                let comment_key = self
+                    .comment_idxs
+                    .iter()
+                    .find(|(_comment_key, comment_idx)| **comment_idx == focused_idx)
+                    .map(|(comment_key, _comment_idx)| *comment_key);
+                match comment_key {
+                    Some(comment_key) => StateUpdate::JumpToComment(comment_key),
+                    None => StateUpdate::None,
+                }
+            }
+            _ => StateUpdate::None,
         }
     }
 
+    fn handle_filter_prompt_key(
+        &self,
+        filter_prompt: &FilterPrompt,
+        key_chord: KeyChord,
+    ) -> StateUpdate {
+        let KeyChord { code, modifiers } = key_chord;
+        match (code, modifiers) {
+            (KeyCode::Enter, _) => StateUpdate::FinishFilter { accept: true },
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                StateUpdate::FinishFilter { accept: false }
+            }
+            (KeyCode::Backspace, _) => {
+                let mut text = filter_prompt.text.clone();
+                if text.pop().is_some() {
+                    StateUpdate::UpdateFilterPrompt(text)
+                } else {
+                    StateUpdate::FinishFilter { accept: false }
+                }
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                let mut text = filter_prompt.text.clone();
+                text.push(c);
+                StateUpdate::UpdateFilterPrompt(text)
+            }
+            _ => StateUpdate::None,
+        }
+    }
+
+    fn first_selection_key(&self) -> SelectionKey {
+        self.all_selection_keys()
+            .into_iter()
+            .next()
+            .unwrap_or(SelectionKey::None)
+    }
+
+    /// Whether the file isn't hidden by the file filter.
+    fn is_file_visible(&self, file_idx: usize) -> bool {
+        match &self.ui.file_filter {
+            None => true,
+            Some(file_filter) => self
+                .state
+                .files
+                .get(file_idx)
+                .is_some_and(|file| file_filter.matches(file)),
+        }
+    }
+
+    /// Whether the directory or file isn't hidden by the file filter. A
+    /// directory is hidden if all of its files are.
+    fn is_file_tree_row_visible(&self, row: FileTreeRow) -> bool {
+        match row {
+            FileTreeRow::Dir(dir_idx) => self.file_tree.dirs[dir_idx]
+                .file_idxs
+                .iter()
+                .any(|file_idx| self.is_file_visible(*file_idx)),
+            FileTreeRow::File(file_idx) => self.is_file_visible(file_idx),
+        }
+    }
+
+    /// Whether the directory or file is inside a collapsed directory.
+    fn is_hidden_in_file_tree(&self, commit_idx: usize, row: FileTreeRow) -> bool {
+        self.file_tree.ancestor_dir_idxs(row).any(|dir_idx| {
+            !self.ui.expanded_items.contains(&SelectionKey::Dir(DirKey {
+                commit_idx,
+                dir_idx,
+            }))
+        })
+    }
+
+    /// Whether the file is hidden in the main view because it's inside a
+    /// collapsed directory of the inline directory tree.
+    fn is_file_hidden_inline(&self, commit_idx: usize, file_idx: usize) -> bool {
+        match self.ui.file_tree_mode {
+            FileTreeMode::Off | FileTreeMode::Sidebar => false,
+            FileTreeMode::Inline => {
+                self.is_hidden_in_file_tree(commit_idx, FileTreeRow::File(file_idx))
+            }
+        }
+    }
+
+    /// All selection keys for the focused commit, in the order that they're
+    /// rendered.
     fn all_selection_keys(&self) -> Vec<SelectionKey> {
+        self.all_selection_keys_for_commit(self.ui.focused_commit_idx)
+    }
+
+    fn all_selection_keys_for_commit(&self, commit_idx: usize) -> Vec<SelectionKey> {
         let mut result = Vec::new();
-        for (commit_idx, _) in self.state.commits.iter().enumerate() {
-            if commit_idx > 0 {
-                // TODO: implement adjacent `CommitView s.
-                continue;
+        match self.ui.file_tree_mode {
+            FileTreeMode::Off => {
+                for file_idx in 0..self.state.files.len() {
+                    if self.is_file_visible(file_idx) {
+                        self.push_file_selection_keys(&mut result, commit_idx, file_idx);
+                    }
+                }
             }
-            for (file_idx, file) in self.state.files.iter().enumerate() {
-                result.push(SelectionKey::File(FileKey {
-                    commit_idx,
-                    file_idx,
-                }));
-                for (section_idx, section) in file.sections.iter().enumerate() {
-                    match section {
-                        Section::Unchanged { .. } => {}
-                        Section::Changed { lines } => {
-                            result.push(SelectionKey::Section(section::SectionKey {
-                                commit_idx,
-                                file_idx,
-                                section_idx,
-                            }));
-                            for (line_idx, _line) in lines.iter().enumerate() {
-                                result.push(SelectionKey::Line(LineKey {
-                                    commit_idx,
-                                    file_idx,
-                                    section_idx,
-                                    line_idx,
-                                }));
-                            }
-                        }
-                        Section::FileMode {
-                            is_checked: _,
-                            mode: _,
-                        }
-                        | Section::Binary { .. } => {
-                            result.push(SelectionKey::Section(section::SectionKey {
+            FileTreeMode::Inline | FileTreeMode::Sidebar => {
+                for row in &self.file_tree.rows {
+                    if !self.is_file_tree_row_visible(*row) {
+                        continue;
+                    }
+                    match *row {
+                        FileTreeRow::Dir(dir_idx) => match self.ui.file_tree_mode {
+                            FileTreeMode::Inline => result.push(SelectionKey::Dir(DirKey {
                                 commit_idx,
-                                file_idx,
-                                section_idx,
-                            }));
+                                dir_idx,
+                            })),
+                            FileTreeMode::Off | FileTreeMode::Sidebar => {}
+                        },
+                        FileTreeRow::File(file_idx) => {
+                            self.push_file_selection_keys(&mut result, commit_idx, file_idx);
                         }
                     }
                 }
             }
         }
-        result
+        result
+    }
+
+    /// Add the selection keys for the file and its sections and lines, in the
+    /// order that they're rendered.
+    fn push_file_selection_keys(
+        &self,
+        result: &mut Vec<SelectionKey>,
+        commit_idx: usize,
+        file_idx: usize,
+    ) {
+        let file = &self.state.files[file_idx];
+        result.push(SelectionKey::File(FileKey {
+            commit_idx,
+            file_idx,
+        }));
+        for (section_idx, section) in file.sections.iter().enumerate() {
+            match section {
+                // Unchanged lines can only be selected to comment on them.
+                Section::Unchanged { lines } if self.ui.is_review_mode => {
+                    let (leading_line_idxs, trailing_line_idxs) = section::context_line_ranges(
+                        section_idx,
+                        file.sections.len(),
+                        lines.len(),
+                        self.ui.num_context_lines,
+                        self.ui
+                            .context_expansions
+                            .get(&(file_idx, section_idx))
+                            .copied()
+                            .unwrap_or_default(),
+                    );
+                    result.extend(leading_line_idxs.chain(trailing_line_idxs).map(|line_idx| {
+                        SelectionKey::Line(LineKey {
+                            commit_idx,
+                            file_idx,
+                            section_idx,
+                            line_idx,
+                        })
+                    }));
+                }
+                Section::Unchanged { .. } => {}
+                Section::Changed { lines } => {
+                    result.push(SelectionKey::Section(section::SectionKey {
+                        commit_idx,
+                        file_idx,
+                        section_idx,
+                    }));
+                    for (line_idx, _line) in lines.iter().enumerate() {
+                        result.push(SelectionKey::Line(LineKey {
+                            commit_idx,
+                            file_idx,
+                            section_idx,
+                            line_idx,
+                        }));
+                    }
+                }
+                Section::FileMode {
+                    is_checked: _,
+                    commit_idx: _,
+                    mode: _,
+                }
+                | Section::Binary { .. } => {
+                    result.push(SelectionKey::Section(section::SectionKey {
+                        commit_idx,
+                        file_idx,
+                        section_idx,
+                    }));
+                }
+            }
+        }
     }
 
     fn find_selection(&self) -> (Vec<SelectionKey>, Option<usize>) {
@@ -466,16 +1905,22 @@ impl<'state> App<'state> {
             .cloned()
             .filter(|key| match key {
                 SelectionKey::None => false,
-                SelectionKey::File(_) => true,
+                SelectionKey::Dir(dir_key) => !self
+                    .is_hidden_in_file_tree(dir_key.commit_idx, FileTreeRow::Dir(dir_key.dir_idx)),
+                SelectionKey::File(file_key) => {
+                    !self.is_file_hidden_inline(file_key.commit_idx, file_key.file_idx)
+                }
                 SelectionKey::Section(section_key) => {
                     let file_key = FileKey {
                         commit_idx: section_key.commit_idx,
                         file_idx: section_key.file_idx,
                     };
-                    match self.file_expanded(file_key) {
+                    let is_file_expanded = match self.file_expanded(file_key) {
                         Tristate::False => false,
                         Tristate::Partial | Tristate::True => true,
-                    }
+                    };
+                    is_file_expanded
+                        && !self.is_file_hidden_inline(file_key.commit_idx, file_key.file_idx)
                 }
                 SelectionKey::Line(line_key) => {
                     let file_key = FileKey {
@@ -487,13 +1932,17 @@ impl<'state> App<'state> {
                         file_idx: line_key.file_idx,
                         section_idx: line_key.section_idx,
                     };
+                    // Unchanged lines are shown whenever their file is.
+                    let is_section_expanded = self.is_unchanged_section(section_key)
+                        || self
+                            .ui
+                            .expanded_items
+                            .contains(&SelectionKey::Section(section_key));
                     self.ui
                         .expanded_items
                         .contains(&SelectionKey::File(file_key))
-                        && self
-                            .ui
-                            .expanded_items
-                            .contains(&SelectionKey::Section(section_key))
+                        && is_section_expanded
+                        && !self.is_file_hidden_inline(line_key.commit_idx, line_key.file_idx)
                 }
             })
             .collect();
@@ -507,6 +1956,117 @@ impl<'state> App<'state> {
         (visible_keys, index)
     }
 
+    /// The items in the focused commit view which match `search_query`.
+    ///
+    /// Unchanged lines can't usually be selected, so a match in an unchanged
+    /// section yields the adjacent changed section instead, which shows the
+    /// unchanged lines as context when it's expanded.
+    fn search_match_keys(&self, search_query: &SearchQuery) -> HashSet<SelectionKey> {
+        let commit_idx = self.ui.focused_commit_idx;
+        let mut result = HashSet::new();
+        for (file_idx, file) in self.state.files.iter().enumerate() {
+            let File {
+                old_path,
+                path,
+                file_mode: _,
+                sections,
+            } = file;
+            let file_key = FileKey {
+                commit_idx,
+                file_idx,
+            };
+            if iter::once(path)
+                .chain(old_path)
+                .any(|path| search_query.is_match(&path.to_string_lossy()))
+            {
+                result.insert(SelectionKey::File(file_key));
+            }
+
+            let editable_section_key = |section_idx: Option<usize>| {
+                let section_idx = section_idx?;
+                let section = sections.get(section_idx)?;
+                section
+                    .is_editable()
+                    .then_some(SelectionKey::Section(section::SectionKey {
+                        commit_idx,
+                        file_idx,
+                        section_idx,
+                    }))
+            };
+            for (section_idx, section) in sections.iter().enumerate() {
+                match section {
+                    Section::Unchanged { lines } => {
+                        let prev_section_key = editable_section_key(section_idx.checked_sub(1));
+                        let next_section_key = editable_section_key(Some(section_idx + 1));
+                        for (line_idx, line) in lines.iter().enumerate() {
+                            if !search_query.is_match(line) {
+                                continue;
+                            }
+                            // Prefer the section that the line is closest to.
+                            let context_section_key = if line_idx < lines.len() / 2 {
+                                prev_section_key.or(next_section_key)
+                            } else {
+                                next_section_key.or(prev_section_key)
+                            };
+                            result.insert(
+                                context_section_key.unwrap_or(SelectionKey::File(file_key)),
+                            );
+                        }
+                    }
+                    Section::Changed { lines } => {
+                        for (line_idx, line) in lines.iter().enumerate() {
+                            if search_query.is_match(&line.line) {
+                                result.insert(SelectionKey::Line(LineKey {
+                                    commit_idx,
+                                    file_idx,
+                                    section_idx,
+                                    line_idx,
+                                }));
+                            }
+                        }
+                    }
+                    Section::FileMode { .. } | Section::Binary { .. } => {}
+                }
+            }
+        }
+        result
+    }
+
+    /// Find the next item matching `search_query`, starting from `start_key`
+    /// and wrapping around at the end. If `select_previous` is true, search
+    /// backwards instead. If `include_start` is true, `start_key` itself may
+    /// be returned if it matches.
+    fn find_search_match(
+        &self,
+        search_query: &SearchQuery,
+        start_key: SelectionKey,
+        select_previous: bool,
+        include_start: bool,
+    ) -> Option<SelectionKey> {
+        let match_keys = self.search_match_keys(search_query);
+        if match_keys.is_empty() {
+            return None;
+        }
+
+        let keys = self.all_selection_keys();
+        let num_keys = keys.len();
+        let (start_index, include_start) = match keys.iter().position(|key| key == &start_key) {
+            Some(start_index) => (start_index, include_start),
+            None => (0, true),
+        };
+        let offset = if include_start { 0 } else { 1 };
+        (0..num_keys)
+            .map(|i| {
+                if select_previous {
+                    (start_index + 2 * num_keys - i - offset) % num_keys
+                } else {
+                    (start_index + i + offset) % num_keys
+                }
+            })
+            .map(|index| keys[index])
+            .find(|key| match_keys.contains(key))
+    }
+
     fn select_prev(&self, keys: &[SelectionKey], index: Option<usize>) -> SelectionKey {
         match index {
             None => self.first_selection_key(),
@@ -571,12 +2131,15 @@ impl<'state> App<'state> {
         };
         let target_y = original_y.saturating_sub(term_height.unwrap_isize() / 2);
         while index > 0 {
+            // Items far outside of the viewport aren't drawn, so stop at the
+            // last one which was.
+            let selection_key_y = match self.drawn_selection_key_y(drawn_rects, keys[index - 1]) {
+                Some(selection_key_y) => selection_key_y,
+                None => break,
+            };
             index -= 1;
-            let selection_key_y = self.selection_key_y(drawn_rects, keys[index]);
-            if let Some(selection_key_y) = selection_key_y {
-                if selection_key_y <= target_y {
-                    break;
-                }
+            if selection_key_y <= target_y {
+                break;
             }
         }
         keys[index]
@@ -599,12 +2162,15 @@ impl<'state> App<'state> {
         };
         let target_y = original_y.saturating_add(term_height.unwrap_isize() / 2);
         while index + 1 < keys.len() {
+            // Items far outside of the viewport aren't drawn, so stop at the
+            // last one which was.
+            let selection_key_y = match self.drawn_selection_key_y(drawn_rects, keys[index + 1]) {
+                Some(selection_key_y) => selection_key_y,
+                None => break,
+            };
             index += 1;
-            let selection_key_y = self.selection_key_y(drawn_rects, keys[index]);
-            if let Some(selection_key_y) = selection_key_y {
-                if selection_key_y >= target_y {
-                    break;
-                }
+            if selection_key_y >= target_y {
+                break;
             }
         }
         keys[index]
@@ -620,11 +2186,20 @@ impl<'state> App<'state> {
                     (SelectionKey::None, _) => true,
                     (_, SelectionKey::None) => false, // shouldn't happen
 
-                    (SelectionKey::File(_), SelectionKey::File(_)) => false,
+                    // Directories always contain at least one item, which
+                    // comes right after them.
+                    (SelectionKey::Dir(_), SelectionKey::Dir(_) | SelectionKey::File(_)) => true,
+                    (SelectionKey::Dir(_), SelectionKey::Section(_) | SelectionKey::Line(_)) => {
+                        false // shouldn't happen
+                    }
+
+                    (SelectionKey::File(_), SelectionKey::Dir(_))
+                    | (SelectionKey::File(_), SelectionKey::File(_)) => false,
                     (SelectionKey::File(_), SelectionKey::Section(_)) => true,
                     (SelectionKey::File(_), SelectionKey::Line(_)) => false, // shouldn't happen
 
-                    (SelectionKey::Section(_), SelectionKey::File(_))
+                    (SelectionKey::Section(_), SelectionKey::Dir(_))
+                    | (SelectionKey::Section(_), SelectionKey::File(_))
                     | (SelectionKey::Section(_), SelectionKey::Section(_)) => false,
                     (SelectionKey::Section(_), SelectionKey::Line(_)) => true,
 
@@ -635,11 +2210,47 @@ impl<'state> App<'state> {
     }
 
     fn select_outer(&self, fold_section: bool) -> StateUpdate {
+        // In the inline directory tree, a collapsed item's parent directory
+        // is the next item out.
+        let select_parent_dir = |commit_idx: usize, row: FileTreeRow| {
+            let parent_dir_idx = match self.ui.file_tree_mode {
+                FileTreeMode::Off | FileTreeMode::Sidebar => None,
+                FileTreeMode::Inline => self.file_tree.ancestor_dir_idxs(row).next(),
+            };
+            match parent_dir_idx {
+                Some(dir_idx) => StateUpdate::SelectItem {
+                    selection_key: SelectionKey::Dir(DirKey {
+                        commit_idx,
+                        dir_idx,
+                    }),
+                    ensure_in_viewport: true,
+                },
+                None => StateUpdate::None,
+            }
+        };
         match self.ui.selection_key {
             SelectionKey::None => StateUpdate::None,
-            selection_key @ SelectionKey::File(_) => {
-                StateUpdate::SetExpandItem(selection_key, false)
+            selection_key @ SelectionKey::Dir(DirKey {
+                commit_idx,
+                dir_idx,
+            }) => {
+                if self.ui.expanded_items.contains(&selection_key) {
+                    StateUpdate::SetExpandItem(selection_key, false)
+                } else {
+                    select_parent_dir(commit_idx, FileTreeRow::Dir(dir_idx))
+                }
             }
+            selection_key @ SelectionKey::File(FileKey {
+                commit_idx,
+                file_idx,
+            }) => match select_parent_dir(commit_idx, FileTreeRow::File(file_idx)) {
+                select_parent_dir @ StateUpdate::SelectItem { .. }
+                    if !self.ui.expanded_items.contains(&selection_key) =>
+                {
+                    select_parent_dir
+                }
+                _ => StateUpdate::SetExpandItem(selection_key, false),
+            },
             selection_key @ SelectionKey::Section(section::SectionKey {
                 commit_idx,
                 file_idx,
@@ -664,14 +2275,27 @@ impl<'state> App<'state> {
                 file_idx,
                 section_idx,
                 line_idx: _,
-            }) => StateUpdate::SelectItem {
-                selection_key: SelectionKey::Section(section::SectionKey {
+            }) => {
+                let section_key = section::SectionKey {
                     commit_idx,
                     file_idx,
                     section_idx,
-                }),
-                ensure_in_viewport: true,
-            },
+                };
+                // An unchanged section has no header to select, so the file
+                // is the next item out.
+                let selection_key = if self.is_unchanged_section(section_key) {
+                    SelectionKey::File(FileKey {
+                        commit_idx,
+                        file_idx,
+                    })
+                } else {
+                    SelectionKey::Section(section_key)
+                };
+                StateUpdate::SelectItem {
+                    selection_key,
+                    ensure_in_viewport: true,
+                }
+            }
         }
     }
 
@@ -686,20 +2310,37 @@ impl<'state> App<'state> {
             .copied()
             .find(|key| match (self.ui.selection_key, key) {
                 (SelectionKey::None, _)
+                | (SelectionKey::Dir(_), SelectionKey::Dir(_))
                 | (SelectionKey::File(_), SelectionKey::File(_))
                 | (SelectionKey::Section(_), SelectionKey::Section(_))
                 | (SelectionKey::Line(_), SelectionKey::Line(_)) => true,
                 (
+                    SelectionKey::Dir(_),
+                    SelectionKey::None
+                    | SelectionKey::File(_)
+                    | SelectionKey::Section(_)
+                    | SelectionKey::Line(_),
+                )
+                | (
                     SelectionKey::File(_),
-                    SelectionKey::None | SelectionKey::Section(_) | SelectionKey::Line(_),
+                    SelectionKey::None
+                    | SelectionKey::Dir(_)
+                    | SelectionKey::Section(_)
+                    | SelectionKey::Line(_),
                 )
                 | (
                     SelectionKey::Section(_),
-                    SelectionKey::None | SelectionKey::File(_) | SelectionKey::Line(_),
+                    SelectionKey::None
+                    | SelectionKey::Dir(_)
+                    | SelectionKey::File(_)
+                    | SelectionKey::Line(_),
                 )
                 | (
                     SelectionKey::Line(_),
-                    SelectionKey::None | SelectionKey::File(_) | SelectionKey::Section(_),
+                    SelectionKey::None
+                    | SelectionKey::Dir(_)
+                    | SelectionKey::File(_)
+                    | SelectionKey::Section(_),
                 ) => false,
             })
             .unwrap_or(self.ui.selection_key)
@@ -714,6 +2355,18 @@ impl<'state> App<'state> {
         Some(rect.y)
     }
 
+    /// Like [`App::selection_key_y`], but for an item which may not have
+    /// been drawn because it's far outside of the viewport.
+    fn drawn_selection_key_y(
+        &self,
+        drawn_rects: &DrawnRects<ComponentId>,
+        selection_key: SelectionKey,
+    ) -> Option<isize> {
+        let DrawnRect { rect, timestamp: _ } =
+            drawn_rects.get(&ComponentId::SelectableItem(selection_key))?;
+        Some(rect.y)
+    }
+
     fn selection_rect(
         &self,
         drawn_rects: &DrawnRects<ComponentId>,
@@ -721,9 +2374,10 @@ impl<'state> App<'state> {
     ) -> Option<Rect> {
         let id = match selection_key {
             SelectionKey::None => return None,
-            SelectionKey::File(_) | SelectionKey::Section(_) | SelectionKey::Line(_) => {
-                ComponentId::SelectableItem(selection_key)
-            }
+            SelectionKey::Dir(_)
+            | SelectionKey::File(_)
+            | SelectionKey::Section(_)
+            | SelectionKey::Line(_) => ComponentId::SelectableItem(selection_key),
         };
         match drawn_rects.get(&id) {
             Some(DrawnRect { rect, timestamp: _ }) => Some(*rect),
@@ -747,7 +2401,7 @@ impl<'state> App<'state> {
         selection_key: SelectionKey,
     ) -> Option<isize> {
         let sticky_file_header_height = match selection_key {
-            SelectionKey::None | SelectionKey::File(_) => 0,
+            SelectionKey::None | SelectionKey::Dir(_) | SelectionKey::File(_) => 0,
             SelectionKey::Section(_) | SelectionKey::Line(_) => 1,
         };
         let top_margin = sticky_file_header_height;
@@ -792,50 +2446,478 @@ impl<'state> App<'state> {
         Some(result)
     }
 
+    /// The commit that an item should be moved to when it's toggled off in the
+    /// view for the commit at `commit_idx`.
+    fn other_commit_idx(&self, commit_idx: usize) -> usize {
+        if commit_idx + 1 < self.state.commits.len() {
+            commit_idx + 1
+        } else {
+            commit_idx.saturating_sub(1)
+        }
+    }
+
+    /// The indexes of the commits that each change inside the given item is
+    /// assigned to.
+    fn item_commit_idxs(&self, selection: SelectionKey) -> Result<Vec<usize>, RecordError> {
+        let num_commits = self.state.commits.len();
+        let commit_idxs = match selection {
+            SelectionKey::None => Vec::new(),
+            SelectionKey::Dir(dir_key) => {
+                let mut commit_idxs = Vec::new();
+                for file_key in self.dir_file_keys(dir_key)? {
+                    commit_idxs.extend(self.item_commit_idxs(SelectionKey::File(file_key))?);
+                }
+                commit_idxs
+            }
+            SelectionKey::File(file_key) => self
+                .file(file_key)?
+                .sections
+                .iter()
+                .flat_map(|section| section.assigned_commit_idxs(num_commits))
+                .collect(),
+            SelectionKey::Section(section_key) => {
+                self.section(section_key)?.assigned_commit_idxs(num_commits)
+            }
+            SelectionKey::Line(line_key) => {
+                let LineKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                    line_idx,
+                } = line_key;
+                let section_key = section::SectionKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                };
+                match self.section(section_key)? {
+                    Section::Changed { lines } => match lines.get(line_idx) {
+                        Some(line) => vec![line.assigned_commit_idx(num_commits)],
+                        None => {
+                            return Err(RecordError::Bug(format!(
+                                "Out-of-bounds line key: {line_key:?}"
+                            )))
+                        }
+                    },
+                    Section::Unchanged { .. }
+                    | Section::FileMode { .. }
+                    | Section::Binary { .. } => Vec::new(),
+                }
+            }
+        };
+        Ok(commit_idxs)
+    }
+
+    /// Whether the selection, the commit messages or the review comments have
+    /// changed since the recorder started.
+    fn is_modified(&self) -> bool {
+        let is_selection_modified = !self.state.is_read_only
+            && (History::snapshot(&self.state.files) != self.initial_selection
+                || self.state.commits != self.initial_commits);
+        is_selection_modified || self.state.comments != self.initial_comments
+    }
+
+    /// Apply an operation which may change the checked state of changes, and
+    /// record it so that it can be undone.
+    fn with_history(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), RecordError>,
+    ) -> Result<(), RecordError> {
+        if self.state.is_read_only {
+            return f(self);
+        }
+
+        let before = History::snapshot(&self.state.files);
+        let selection_key = self.ui.selection_key;
+        let result = f(self);
+        self.history
+            .record(before, &self.state.files, selection_key);
+        self.selection_counts.invalidate();
+        result
+    }
+
+    /// Revert the most recent change to the checked state, if any, and select
+    /// the item that was selected when the change was made. Returns whether
+    /// anything was undone.
+    fn undo(&mut self) -> bool {
+        match self.history.undo(&mut self.state.files) {
+            Some(selection_key) => {
+                self.selection_counts.invalidate();
+                self.select_history_item(selection_key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-apply the most recently undone change to the checked state, if any.
+    /// Returns whether anything was redone.
+    fn redo(&mut self) -> bool {
+        match self.history.redo(&mut self.state.files) {
+            Some(selection_key) => {
+                self.selection_counts.invalidate();
+                self.select_history_item(selection_key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn select_history_item(&mut self, selection_key: SelectionKey) {
+        let selection_key = selection_key.with_commit_idx(self.ui.focused_commit_idx);
+        self.ui.selection_key = selection_key;
+        self.expand_item_ancestors(selection_key);
+    }
+
+    /// Open the editor for the message of the commit at `commit_idx`, if the
+    /// commit has a message to edit.
+    fn open_message_editor(&mut self, commit_idx: usize) {
+        let message = self
+            .state
+            .commits
+            .get(commit_idx)
+            .and_then(|commit| commit.message.as_ref());
+        if let Some(message) = message {
+            self.ui.message_editor = Some(MessageEditor::new(
+                MessageTarget::Commit(commit_idx),
+                message.clone(),
+            ));
+        }
+    }
+
+    /// Open the editor for the review comment on the item at `comment_key`,
+    /// starting with its existing comment, if any.
+    fn open_comment_editor(&mut self, comment_key: CommentKey) {
+        let text = match self.comment_idxs.get(&comment_key) {
+            Some(comment_idx) => self.state.comments[*comment_idx].comment.clone(),
+            None => String::new(),
+        };
+        self.ui.message_editor = Some(MessageEditor::new(
+            MessageTarget::Comment(comment_key),
+            text,
+        ));
+    }
+
+    /// Close the message editor, saving the edited commit message or review
+    /// comment if `accept` is set.
+    fn finish_message_editor(&mut self, accept: bool) {
+        let message_editor = match self.ui.message_editor.take() {
+            Some(message_editor) => message_editor,
+            None => return,
+        };
+        if !accept {
+            return;
+        }
+        match message_editor.target {
+            MessageTarget::Commit(commit_idx) => {
+                if let Some(commit) = self.state.commits.get_mut(commit_idx) {
+                    commit.message = Some(message_editor.text().to_owned());
+                }
+            }
+            MessageTarget::Comment(comment_key) => {
+                self.set_comment(comment_key, message_editor.text());
+            }
+        }
+    }
+
+    /// Replace the review comment on the item at `comment_key` with `text`,
+    /// or remove it if `text` is blank.
+    fn set_comment(&mut self, comment_key: CommentKey, text: &str) {
+        let comment_idx = self.comment_idxs.get(&comment_key).copied();
+        match (comment_idx, text.trim().is_empty()) {
+            (Some(comment_idx), true) => {
+                self.state.comments.remove(comment_idx);
+            }
+            (Some(comment_idx), false) => {
+                self.state.comments[comment_idx].comment = text.to_owned();
+            }
+            (None, true) => return,
+            (None, false) => {
+                let comment =
+                    review::make_review_comment(&self.state.files, comment_key, text.to_owned());
+                self.state.comments.extend(comment);
+            }
+        }
+        self.index_comments();
+    }
+
+    /// Sort the review comments and find the item which each is attached to,
+    /// after they or the changes have been edited.
+    fn index_comments(&mut self) {
+        self.comment_idxs = review::index_comments(&self.state.files, &mut self.state.comments);
+    }
+
+    /// Close the comment list and select the item with the comment at
+    /// `comment_key` in the focused commit.
+    fn jump_to_comment(&mut self, comment_key: CommentKey) {
+        self.ui.comment_list = None;
+        if !self.is_file_visible(comment_key.file_idx()) {
+            self.set_file_filter(None);
+        }
+        let selection_key = comment_key.selection_key(self.ui.focused_commit_idx);
+        self.ui.selection_key = selection_key;
+        self.expand_item_ancestors(selection_key);
+    }
+
+    /// The current selection, to be saved in case the recorder is
+    /// interrupted. `state_hash` identifies the changes being recorded, as
+    /// computed by [`session::hash_files`] when the recorder started.
+    #[cfg(feature = "serde")]
+    fn session(&self, state_hash: u64) -> session::Session<'_> {
+        let mut expanded_items: Vec<SelectionKey> =
+            self.ui.expanded_items.iter().copied().collect();
+        expanded_items.sort();
+        session::Session::new(
+            state_hash,
+            &self.state.files,
+            self.ui.focused_commit_idx,
+            self.ui.selection_key,
+            expanded_items,
+        )
+    }
+
+    /// Offer to restore the selection of a saved session, unless restoring
+    /// it wouldn't change anything.
+    #[cfg(feature = "serde")]
+    fn open_session_prompt(&mut self, session: session::Session<'static>, state_hash: u64) {
+        if self.state.is_read_only {
+            return;
+        }
+
+        let mut files = self.state.files.clone();
+        session.restore_selection(&mut files);
+        let before = History::snapshot(&self.state.files);
+        let after = History::snapshot(&files);
+        if before == after {
+            return;
+        }
+
+        let num_checked = after.iter().filter(|state| state.is_checked).count();
+        self.ui.session_prompt = Some(SessionPrompt {
+            is_exact: session.state_hash == state_hash,
+            session,
+            num_checked,
+            num_changes: after.len(),
+        });
+    }
+
+    /// Close the prompt to restore a saved session. If `restore` is set, the
+    /// selection is restored, along with the UI state if the session was
+    /// saved for the same changes.
+    fn finish_session_prompt(&mut self, restore: bool) -> Result<(), RecordError> {
+        let SessionPrompt {
+            session,
+            is_exact,
+            num_checked: _,
+            num_changes: _,
+        } = match self.ui.session_prompt.take() {
+            Some(prompt) => prompt,
+            None => return Ok(()),
+        };
+        if !restore {
+            return Ok(());
+        }
+
+        self.with_history(|app| {
+            session.restore_selection(&mut app.state.files);
+            normalize_commits(&mut app.state);
+            Ok(())
+        })?;
+        if is_exact && session.focused_commit_idx < self.state.commits.len() {
+            self.ui.expanded_items = session.expanded_items.into_iter().collect();
+            self.layout_cache.invalidate();
+            self.ui.focused_commit_idx = session.focused_commit_idx;
+            self.ui.selection_key = session.selection_key;
+            self.expand_item_ancestors(session.selection_key);
+        }
+        Ok(())
+    }
+
+    fn start_search(&mut self) {
+        self.ui.search_prompt = Some(SearchPrompt {
+            text: String::new(),
+            origin_selection_key: self.ui.selection_key,
+            origin_scroll_offset_y: self.ui.scroll_offset_y,
+            previous_query: self.ui.search_query.clone(),
+        });
+    }
+
+    /// Update the text of the search prompt, and select the first match at or
+    /// after the item which was selected when the prompt was opened.
+    fn update_search_prompt(&mut self, text: String) {
+        let search_prompt = match &mut self.ui.search_prompt {
+            Some(search_prompt) => search_prompt,
+            None => return,
+        };
+        search_prompt.text = text;
+        let origin_selection_key = search_prompt.origin_selection_key;
+        self.ui.search_query = SearchQuery::new(search_prompt.text.clone());
+
+        let selection_key = self
+            .ui
+            .search_query
+            .as_ref()
+            .and_then(|search_query| {
+                self.find_search_match(
+                    search_query,
+                    origin_selection_key,
+                    /*select_previous=*/ false,
+                    /*include_start=*/ true,
+                )
+            })
+            .unwrap_or(origin_selection_key);
+        self.select_search_match(selection_key);
+    }
+
+    /// Close the search prompt. If `accept` is false, the selection, scroll
+    /// position and query from before the search are restored.
+    fn finish_search(&mut self, accept: bool) {
+        let SearchPrompt {
+            text: _,
+            origin_selection_key,
+            origin_scroll_offset_y,
+            previous_query,
+        } = match self.ui.search_prompt.take() {
+            Some(search_prompt) => search_prompt,
+            None => return,
+        };
+        if !accept {
+            self.ui.selection_key = origin_selection_key;
+            self.ui.scroll_offset_y = origin_scroll_offset_y;
+            self.ui.search_query = previous_query;
+        }
+    }
+
+    fn select_search_match(&mut self, selection_key: SelectionKey) {
+        self.ui.selection_key = selection_key;
+        self.expand_item_ancestors(selection_key);
+        // The match may be in the context lines of the section, which are
+        // only shown if the section is expanded.
+        if let SelectionKey::Section(_) = selection_key {
+            self.set_expand_item(selection_key, true);
+        }
+    }
+
+    fn start_filter(&mut self) {
+        self.ui.filter_prompt = Some(FilterPrompt {
+            text: self
+                .ui
+                .file_filter
+                .as_ref()
+                .map(|file_filter| file_filter.text().to_owned())
+                .unwrap_or_default(),
+            origin_selection_key: self.ui.selection_key,
+            previous_filter: self.ui.file_filter.clone(),
+        });
+    }
+
+    /// Update the text of the filter prompt, and apply the filter as typed so
+    /// far.
+    fn update_filter_prompt(&mut self, text: String) {
+        let filter_prompt = match &mut self.ui.filter_prompt {
+            Some(filter_prompt) => filter_prompt,
+            None => return,
+        };
+        filter_prompt.text = text;
+        let origin_selection_key = filter_prompt.origin_selection_key;
+        let file_filter = FileFilter::new(filter_prompt.text.clone());
+        self.ui.selection_key = origin_selection_key;
+        self.set_file_filter(file_filter);
+    }
+
+    /// Close the filter prompt. If `accept` is false, the filter and
+    /// selection from before the prompt was opened are restored.
+    fn finish_filter(&mut self, accept: bool) {
+        let FilterPrompt {
+            text: _,
+            origin_selection_key,
+            previous_filter,
+        } = match self.ui.filter_prompt.take() {
+            Some(filter_prompt) => filter_prompt,
+            None => return,
+        };
+        if !accept {
+            self.ui.selection_key = origin_selection_key;
+            self.set_file_filter(previous_filter);
+        }
+    }
+
+    /// Show only the files matching `file_filter`, or all files if `None`. If
+    /// the selected item is hidden by the filter, the first visible item is
+    /// selected instead.
+    fn set_file_filter(&mut self, file_filter: Option<FileFilter>) {
+        self.ui.file_filter = file_filter;
+        self.ui.visual_anchor = None;
+        if !self.all_selection_keys().contains(&self.ui.selection_key) {
+            self.ui.selection_key = self.first_selection_key();
+        }
+        self.expand_item_ancestors(self.ui.selection_key);
+    }
+
     fn toggle_item(&mut self, selection: SelectionKey) -> Result<(), RecordError> {
         if self.state.is_read_only {
             return Ok(());
         }
 
+        // Toggling an item in the view for a commit either moves it into that
+        // commit, or, if any part of it is already in that commit, moves it
+        // out of that commit.
+        let commit_idx = self.ui.focused_commit_idx;
+        let target_commit_idx = if self.item_commit_idxs(selection)?.contains(&commit_idx) {
+            self.other_commit_idx(commit_idx)
+        } else {
+            commit_idx
+        };
+        self.move_item_to_commit(selection, target_commit_idx)
+    }
+
+    fn move_item_to_commit(
+        &mut self,
+        selection: SelectionKey,
+        target_commit_idx: usize,
+    ) -> Result<(), RecordError> {
+        if self.state.is_read_only {
+            return Ok(());
+        }
+
+        let num_commits = self.state.commits.len();
         let side_effects = match selection {
             SelectionKey::None => None,
+            SelectionKey::Dir(dir_key) => {
+                for file_key in self.dir_file_keys(dir_key)? {
+                    self.visit_file(file_key, |file| {
+                        file.assign_commit_idx(target_commit_idx, num_commits);
+                    })?;
+                }
+
+                None
+            }
             SelectionKey::File(file_key) => {
-                let tristate = self.file_tristate(file_key)?;
-                let is_checked_new = match tristate {
-                    Tristate::False => true,
-                    Tristate::Partial | Tristate::True => false,
-                };
                 self.visit_file(file_key, |file| {
-                    file.set_checked(is_checked_new);
+                    file.assign_commit_idx(target_commit_idx, num_commits);
                 })?;
 
                 None
             }
             SelectionKey::Section(section_key) => {
-                let tristate = self.section_tristate(section_key)?;
-                let is_checked_new = match tristate {
-                    Tristate::False => true,
-                    Tristate::Partial | Tristate::True => false,
-                };
-
                 let old_file_mode = self.visit_file_for_section(section_key, |f| f.file_mode)?;
 
                 self.visit_section(section_key, |section| {
-                    section.set_checked(is_checked_new);
+                    section.assign_commit_idx(target_commit_idx, num_commits);
 
                     if let Section::FileMode { mode, .. } = section {
                         return Some(ToggleSideEffects::ToggledModeChangeSection(
                             section_key,
                             old_file_mode,
                             *mode,
-                            is_checked_new,
+                            target_commit_idx,
                         ));
                     }
 
                     if let Section::Changed { .. } = section {
                         return Some(ToggleSideEffects::ToggledChangedSection(
                             section_key,
-                            is_checked_new,
+                            target_commit_idx,
                         ));
                     }
 
@@ -843,11 +2925,11 @@ impl<'state> App<'state> {
                 })?
             }
             SelectionKey::Line(line_key) => self.visit_line(line_key, |line| {
-                line.is_checked = !line.is_checked;
+                line.assign_commit_idx(target_commit_idx, num_commits);
 
                 Some(ToggleSideEffects::ToggledChangedLine(
                     line_key,
-                    line.is_checked,
+                    target_commit_idx,
                 ))
             })?,
         };
@@ -858,64 +2940,42 @@ impl<'state> App<'state> {
                     section_key,
                     old_mode,
                     new_mode,
-                    toggled_to,
+                    moved_to,
                 ) => {
-                    // If we check a deletion, all lines in the file must be deleted
-                    if toggled_to && new_mode == FileMode::Absent {
+                    // If we move a deletion, all lines in the file must be
+                    // deleted in the same commit or an earlier one.
+                    if new_mode == FileMode::Absent {
                         self.visit_file_for_section(section_key, |file| {
-                            for section in &mut file.sections {
-                                if matches!(section, Section::Changed { .. }) {
-                                    section.set_checked(true);
-                                }
-                            }
+                            reassign_commit_idxs(
+                                file.sections
+                                    .iter_mut()
+                                    .filter(|section| matches!(section, Section::Changed { .. })),
+                                num_commits,
+                                |commit_idx| commit_idx.min(moved_to),
+                            );
                         })?;
                     }
 
-                    // If we uncheck a creation, no lines in the file can be added
-                    if !toggled_to && old_mode == FileMode::Absent {
+                    // If we move a creation, no lines in the file can be added
+                    // before the file is created.
+                    if old_mode == FileMode::Absent {
                         self.visit_file_for_section(section_key, |file| {
-                            for section in &mut file.sections {
-                                section.set_checked(false);
-                            }
+                            reassign_commit_idxs(
+                                file.sections.iter_mut(),
+                                num_commits,
+                                |commit_idx| commit_idx.max(moved_to),
+                            );
                         })?;
                     }
                 }
-                ToggleSideEffects::ToggledChangedSection(section_key, toggled_to) => {
+                ToggleSideEffects::ToggledChangedSection(section_key, moved_to) => {
                     self.visit_file_for_section(section_key, |file| {
-                        for section in &mut file.sections {
-                            if let Section::FileMode { mode, is_checked } = section {
-                                // If we removed a line and the file was being deleted, it can no longer
-                                // be deleted as it needs to contain that line
-                                if !toggled_to && *mode == FileMode::Absent {
-                                    *is_checked = false;
-                                }
-
-                                // If we added a line and the file was not being created, it must be created
-                                // in order to contain that line
-                                if toggled_to && file.file_mode == FileMode::Absent {
-                                    *is_checked = true;
-                                }
-                            }
-                        }
+                        move_file_mode_for_changed_lines(file, num_commits, moved_to);
                     })?;
                 }
-                ToggleSideEffects::ToggledChangedLine(line_key, toggled_to) => {
+                ToggleSideEffects::ToggledChangedLine(line_key, moved_to) => {
                     self.visit_file_for_line(line_key, |file| {
-                        for section in &mut file.sections {
-                            if let Section::FileMode { mode, is_checked } = section {
-                                // If we removed a line and the file was being deleted, it can no longer
-                                // be deleted as it needs to contain that line
-                                if !toggled_to && *mode == FileMode::Absent {
-                                    *is_checked = false;
-                                }
-
-                                // If we added a line and the file was not being created, it must be created
-                                // in order to contain that line
-                                if toggled_to && file.file_mode == FileMode::Absent {
-                                    *is_checked = true;
-                                }
-                            }
-                        }
+                        move_file_mode_for_changed_lines(file, num_commits, moved_to);
                     })?;
                 }
             }
@@ -929,8 +2989,21 @@ impl<'state> App<'state> {
             return;
         }
 
-        for file in &mut self.state.files {
-            file.toggle_all();
+        let num_commits = self.state.commits.len();
+        let commit_idx = self.ui.focused_commit_idx;
+        let other_commit_idx = self.other_commit_idx(commit_idx);
+        let visible_file_idxs: Vec<usize> = (0..self.state.files.len())
+            .filter(|file_idx| self.is_file_visible(*file_idx))
+            .collect();
+        for file_idx in visible_file_idxs {
+            let file = &mut self.state.files[file_idx];
+            reassign_commit_idxs(file.sections.iter_mut(), num_commits, |item_commit_idx| {
+                if item_commit_idx == commit_idx {
+                    other_commit_idx
+                } else {
+                    commit_idx
+                }
+            });
         }
     }
 
@@ -939,40 +3012,249 @@ impl<'state> App<'state> {
             return;
         }
 
-        let checked = {
-            let tristate = self
-                .state
-                .files
-                .iter()
-                .map(|file| file.tristate())
-                .fold(None, |acc, elem| match (acc, elem) {
-                    (None, tristate) => Some(tristate),
-                    (Some(acc_tristate), tristate) if acc_tristate == tristate => Some(tristate),
-                    _ => Some(Tristate::Partial),
-                })
-                .unwrap_or(Tristate::False);
+        let num_commits = self.state.commits.len();
+        let commit_idx = self.ui.focused_commit_idx;
+        let visible_file_idxs: Vec<usize> = (0..self.state.files.len())
+            .filter(|file_idx| self.is_file_visible(*file_idx))
+            .collect();
+        let target_commit_idx = {
+            let tristate =
+                file_tree::fold_file_tristates(visible_file_idxs.iter().map(|file_idx| {
+                    self.state.files[*file_idx].tristate_for_commit(commit_idx, num_commits)
+                }));
             match tristate {
-                Tristate::False | Tristate::Partial => true,
-                Tristate::True => false,
+                Tristate::False | Tristate::Partial => commit_idx,
+                Tristate::True => self.other_commit_idx(commit_idx),
+            }
+        };
+        for file_idx in visible_file_idxs {
+            self.state.files[file_idx].assign_commit_idx(target_commit_idx, num_commits);
+        }
+    }
+
+    /// Show or hide the directory tree, keeping the selected item visible.
+    fn set_file_tree_mode(&mut self, file_tree_mode: FileTreeMode) {
+        self.ui.file_tree_mode = file_tree_mode;
+        // The files are indented differently, and in the sidebar mode, they
+        // have less room.
+        self.layout_cache.invalidate();
+        match (file_tree_mode, self.ui.selection_key) {
+            (FileTreeMode::Inline, selection_key) => self.expand_item_ancestors(selection_key),
+            (FileTreeMode::Off | FileTreeMode::Sidebar, SelectionKey::Dir(dir_key)) => {
+                self.ui.selection_key = self
+                    .dir_file_keys(dir_key)
+                    .ok()
+                    .and_then(|file_keys| file_keys.first().copied())
+                    .map(SelectionKey::File)
+                    .unwrap_or(SelectionKey::None);
+            }
+            (
+                FileTreeMode::Off | FileTreeMode::Sidebar,
+                SelectionKey::None
+                | SelectionKey::File(_)
+                | SelectionKey::Section(_)
+                | SelectionKey::Line(_),
+            ) => {}
+        }
+        self.ui.visual_anchor = None;
+    }
+
+    /// Reveal more lines of the unchanged section at `section_key`.
+    fn expand_context(
+        &mut self,
+        section_key: section::SectionKey,
+        expansion: section::ContextExpansion,
+    ) {
+        let section::SectionKey {
+            commit_idx: _,
+            file_idx,
+            section_idx,
+        } = section_key;
+        let context_expansion = self
+            .ui
+            .context_expansions
+            .entry((file_idx, section_idx))
+            .or_default();
+        *context_expansion = context_expansion.add(expansion);
+        self.layout_cache.invalidate_file(file_idx);
+    }
+
+    /// Move the focus to the view for the commit at `commit_idx`, keeping the
+    /// same item selected and the same items expanded.
+    fn focus_commit(&mut self, commit_idx: usize) {
+        let old_commit_idx = self.ui.focused_commit_idx;
+        if commit_idx == old_commit_idx {
+            return;
+        }
+
+        let expanded_items: Vec<SelectionKey> = self
+            .ui
+            .expanded_items
+            .iter()
+            .filter(|selection_key| selection_key.commit_idx() == Some(old_commit_idx))
+            .map(|selection_key| selection_key.with_commit_idx(commit_idx))
+            .collect();
+        self.ui
+            .expanded_items
+            .retain(|selection_key| selection_key.commit_idx() != Some(commit_idx));
+        self.ui.expanded_items.extend(expanded_items);
+        self.layout_cache.invalidate();
+
+        self.ui.focused_commit_idx = commit_idx;
+        self.ui.selection_key = self.ui.selection_key.with_commit_idx(commit_idx);
+        self.ui.visual_anchor = self
+            .ui
+            .visual_anchor
+            .map(|visual_anchor| visual_anchor.with_commit_idx(commit_idx));
+    }
+
+    /// The items in the visual range, in the order that they're rendered. If
+    /// visual mode isn't active, this is just the selected item.
+    ///
+    /// The range spans the items which can currently be focused, so the lines
+    /// of collapsed files and sections aren't part of it directly. Instead, a
+    /// collapsed directory, file or section in the range stands for all of its
+    /// contents, whether it's at either end of the range or in the middle. An
+    /// expanded header stands for nothing by itself, since its contents are
+    /// part of the range only as far as the range extends into them, unless
+    /// it's the only item in the range.
+    fn visual_range_keys(&self) -> Vec<SelectionKey> {
+        let visual_anchor = match self.ui.visual_anchor {
+            Some(visual_anchor) => visual_anchor,
+            None => return vec![self.ui.selection_key],
+        };
+        let (visible_keys, selection_idx) = self.find_selection();
+        let anchor_idx = visible_keys.iter().position(|key| *key == visual_anchor);
+        let (start, end) = match (anchor_idx, selection_idx) {
+            (Some(anchor_idx), Some(selection_idx)) => {
+                (anchor_idx.min(selection_idx), anchor_idx.max(selection_idx))
             }
+            (Some(idx), None) | (None, Some(idx)) => (idx, idx),
+            (None, None) => return Vec::new(),
         };
-        for file in &mut self.state.files {
-            file.set_checked(checked);
+        if start == end {
+            return vec![visible_keys[start]];
+        }
+        visible_keys[start..=end]
+            .iter()
+            .copied()
+            .filter(|selection_key| match selection_key {
+                SelectionKey::None => false,
+                SelectionKey::Dir(_) | SelectionKey::File(_) => {
+                    !self.ui.expanded_items.contains(selection_key)
+                }
+                SelectionKey::Section(section_key) => match self.section(*section_key).ok() {
+                    Some(Section::Changed { .. }) => {
+                        !self.ui.expanded_items.contains(selection_key)
+                    }
+                    // Mode changes and binary contents have no lines of their
+                    // own, so their header is the item itself.
+                    Some(Section::FileMode { .. } | Section::Binary { .. }) => true,
+                    Some(Section::Unchanged { .. }) | None => false,
+                },
+                SelectionKey::Line(_) => true,
+            })
+            .collect()
+    }
+
+    /// The range of line indices in the visual range for each section which
+    /// has any lines in it.
+    fn visual_line_ranges(&self) -> HashMap<section::SectionKey, Range<usize>> {
+        let mut result: HashMap<section::SectionKey, Range<usize>> = HashMap::new();
+        if self.ui.visual_anchor.is_none() {
+            return result;
+        }
+        for selection_key in self.visual_range_keys() {
+            if let SelectionKey::Line(LineKey {
+                commit_idx,
+                file_idx,
+                section_idx,
+                line_idx,
+            }) = selection_key
+            {
+                let section_key = section::SectionKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                };
+                let range = result.entry(section_key).or_insert(line_idx..line_idx);
+                range.start = range.start.min(line_idx);
+                range.end = range.end.max(line_idx + 1);
+            }
+        }
+        result
+    }
+
+    /// Check, uncheck or flip each item in the visual range, then leave visual
+    /// mode.
+    fn apply_to_range(&mut self, action: RangeAction) -> Result<(), RecordError> {
+        let selection_keys = self.visual_range_keys();
+        self.ui.visual_anchor = None;
+        if self.state.is_read_only {
+            return Ok(());
+        }
+
+        let commit_idx = self.ui.focused_commit_idx;
+        for selection_key in selection_keys {
+            match action {
+                RangeAction::Check => self.move_item_to_commit(selection_key, commit_idx)?,
+                RangeAction::Uncheck => {
+                    self.move_item_to_commit(selection_key, self.other_commit_idx(commit_idx))?
+                }
+                RangeAction::Flip => self.toggle_item(selection_key)?,
+            }
         }
+        Ok(())
     }
 
     fn expand_item_ancestors(&mut self, selection: SelectionKey) {
+        let tree_row = match selection {
+            SelectionKey::None => None,
+            SelectionKey::Dir(DirKey {
+                commit_idx,
+                dir_idx,
+            }) => Some((commit_idx, FileTreeRow::Dir(dir_idx))),
+            SelectionKey::File(FileKey {
+                commit_idx,
+                file_idx,
+            })
+            | SelectionKey::Section(section::SectionKey {
+                commit_idx,
+                file_idx,
+                section_idx: _,
+            })
+            | SelectionKey::Line(LineKey {
+                commit_idx,
+                file_idx,
+                section_idx: _,
+                line_idx: _,
+            }) => Some((commit_idx, FileTreeRow::File(file_idx))),
+        };
+        if let Some((commit_idx, row)) = tree_row {
+            self.ui
+                .expanded_items
+                .extend(self.file_tree.ancestor_dir_idxs(row).map(|dir_idx| {
+                    SelectionKey::Dir(DirKey {
+                        commit_idx,
+                        dir_idx,
+                    })
+                }));
+        }
+
         match selection {
-            SelectionKey::None | SelectionKey::File(_) => {}
+            SelectionKey::None | SelectionKey::Dir(_) | SelectionKey::File(_) => {}
             SelectionKey::Section(section::SectionKey {
                 commit_idx,
                 file_idx,
                 section_idx: _,
             }) => {
-                self.ui.expanded_items.insert(SelectionKey::File(FileKey {
-                    commit_idx,
-                    file_idx,
-                }));
+                self.set_expand_item(
+                    SelectionKey::File(FileKey {
+                        commit_idx,
+                        file_idx,
+                    }),
+                    true,
+                );
             }
             SelectionKey::Line(LineKey {
                 commit_idx,
@@ -980,36 +3262,55 @@ impl<'state> App<'state> {
                 section_idx,
                 line_idx: _,
             }) => {
-                self.ui.expanded_items.insert(SelectionKey::File(FileKey {
-                    commit_idx,
-                    file_idx,
-                }));
-                self.ui
-                    .expanded_items
-                    .insert(SelectionKey::Section(section::SectionKey {
+                self.set_expand_item(
+                    SelectionKey::File(FileKey {
                         commit_idx,
                         file_idx,
-                        section_idx,
-                    }));
+                    }),
+                    true,
+                );
+                let section_key = section::SectionKey {
+                    commit_idx,
+                    file_idx,
+                    section_idx,
+                };
+                // Unchanged sections can't be collapsed.
+                if !self.is_unchanged_section(section_key) {
+                    self.set_expand_item(SelectionKey::Section(section_key), true);
+                }
             }
         }
     }
 
     fn set_expand_item(&mut self, selection: SelectionKey, is_expanded: bool) {
-        if is_expanded {
-            self.ui.expanded_items.insert(selection);
+        let is_changed = if is_expanded {
+            self.ui.expanded_items.insert(selection)
         } else {
-            self.ui.expanded_items.remove(&selection);
+            self.ui.expanded_items.remove(&selection)
+        };
+        if is_changed {
+            self.layout_cache.invalidate_item(selection);
         }
     }
 
     fn toggle_expand_item(&mut self, selection: SelectionKey) -> Result<(), RecordError> {
         match selection {
             SelectionKey::None => {}
+            SelectionKey::Dir(dir_key) => {
+                if !self.ui.expanded_items.insert(SelectionKey::Dir(dir_key)) {
+                    self.ui.expanded_items.remove(&SelectionKey::Dir(dir_key));
+                    // Don't leave the selection hidden inside the collapsed
+                    // directory.
+                    if self.is_file_tree_mode_inline() && self.is_in_dir(dir_key)? {
+                        self.ui.selection_key = SelectionKey::Dir(dir_key);
+                    }
+                }
+            }
             SelectionKey::File(file_key) => {
                 if !self.ui.expanded_items.insert(SelectionKey::File(file_key)) {
                     self.ui.expanded_items.remove(&SelectionKey::File(file_key));
                 }
+                self.layout_cache.invalidate_item(selection);
             }
             SelectionKey::Section(section_key) => {
                 if !self
@@ -1021,6 +3322,7 @@ impl<'state> App<'state> {
                         .expanded_items
                         .remove(&SelectionKey::Section(section_key));
                 }
+                self.layout_cache.invalidate_item(selection);
             }
             SelectionKey::Line(_) => {
                 // Do nothing.
@@ -1030,22 +3332,42 @@ impl<'state> App<'state> {
     }
 
     fn expand_initial_items(&mut self) {
-        self.ui.expanded_items = self
-            .all_selection_keys()
-            .into_iter()
+        self.ui.expanded_items = (0..self.state.commits.len())
+            .flat_map(|commit_idx| {
+                let dir_keys = (0..self.file_tree.dirs.len()).map(move |dir_idx| {
+                    SelectionKey::Dir(DirKey {
+                        commit_idx,
+                        dir_idx,
+                    })
+                });
+                self.all_selection_keys_for_commit(commit_idx)
+                    .into_iter()
+                    .chain(dir_keys)
+            })
             .filter(|selection_key| match selection_key {
                 SelectionKey::None | SelectionKey::File(_) | SelectionKey::Line(_) => false,
-                SelectionKey::Section(_) => true,
+                SelectionKey::Dir(_) | SelectionKey::Section(_) => true,
             })
             .collect();
     }
 
     fn toggle_expand_all(&mut self) -> Result<(), RecordError> {
-        let all_selection_keys: HashSet<_> = self.all_selection_keys().into_iter().collect();
-        self.ui.expanded_items = if self.ui.expanded_items == all_selection_keys {
+        // Directories are left expanded when everything else is collapsed, so
+        // that all files stay visible.
+        let all_selection_keys: HashSet<_> = self
+            .all_selection_keys()
+            .into_iter()
+            .filter(|selection_key| match selection_key {
+                SelectionKey::None | SelectionKey::Dir(_) => false,
+                SelectionKey::File(_) | SelectionKey::Section(_) | SelectionKey::Line(_) => true,
+            })
+            .collect();
+        if all_selection_keys.is_subset(&self.ui.expanded_items) {
             // Select an ancestor file key that will still be visible.
             self.ui.selection_key = match self.ui.selection_key {
-                selection_key @ (SelectionKey::None | SelectionKey::File(_)) => selection_key,
+                selection_key @ (SelectionKey::None
+                | SelectionKey::Dir(_)
+                | SelectionKey::File(_)) => selection_key,
                 SelectionKey::Section(section::SectionKey {
                     commit_idx,
                     file_idx,
@@ -1061,13 +3383,235 @@ impl<'state> App<'state> {
                     file_idx,
                 }),
             };
-            Default::default()
+            self.ui
+                .expanded_items
+                .retain(|selection_key| !all_selection_keys.contains(selection_key));
         } else {
-            all_selection_keys
+            self.ui.expanded_items.extend(all_selection_keys);
+        }
+        self.layout_cache.invalidate();
+        Ok(())
+    }
+
+    /// Replace the added lines of the changed section at `section_key` with
+    /// `edited_lines`, splitting the section if the edited lines have lines
+    /// in common with its removed lines.
+    fn replace_added_lines(
+        &mut self,
+        section_key: section::SectionKey,
+        edited_lines: &str,
+    ) -> Result<(), RecordError> {
+        let section::SectionKey {
+            commit_idx,
+            file_idx,
+            section_idx,
+        } = section_key;
+        let num_commits = self.state.commits.len();
+        let section = self
+            .state
+            .files
+            .get(file_idx)
+            .and_then(|file| file.sections.get(section_idx));
+        let mut replacement_sections = match section {
+            Some(Section::Changed { lines }) => {
+                section_edit::rediff_section(lines, edited_lines, commit_idx, num_commits)
+            }
+            Some(Section::Unchanged { .. } | Section::FileMode { .. } | Section::Binary { .. }) => {
+                return Ok(());
+            }
+            None => {
+                return Err(RecordError::Bug(format!(
+                    "Out-of-bounds section key: {section_key:?}"
+                )))
+            }
+        };
+
+        // Splice in the replacement sections, merging adjacent unchanged
+        // sections, and remember where each of the other sections ended up.
+        let file = &mut self.state.files[file_idx];
+        let old_sections = std::mem::take(&mut file.sections);
+        let mut new_section_idxs = Vec::with_capacity(old_sections.len());
+        let mut new_line_offsets = Vec::with_capacity(old_sections.len());
+        let mut replacement_section_idxs = Vec::new();
+        for (old_section_idx, old_section) in old_sections.into_iter().enumerate() {
+            let is_replaced = old_section_idx == section_idx;
+            let sections = if is_replaced {
+                std::mem::take(&mut replacement_sections)
+            } else {
+                vec![old_section]
+            };
+            let mut line_offset = 0;
+            for section in sections {
+                let is_changed = match section {
+                    Section::Changed { .. } => true,
+                    Section::Unchanged { .. }
+                    | Section::FileMode { .. }
+                    | Section::Binary { .. } => false,
+                };
+                line_offset = match (file.sections.last_mut(), section) {
+                    (
+                        Some(Section::Unchanged { lines: prev_lines }),
+                        Section::Unchanged { lines },
+                    ) => {
+                        let line_offset = prev_lines.len();
+                        prev_lines.extend(lines);
+                        line_offset
+                    }
+                    (_, section) => {
+                        file.sections.push(section);
+                        0
+                    }
+                };
+                if is_replaced && is_changed {
+                    replacement_section_idxs.push(file.sections.len() - 1);
+                }
+            }
+            new_section_idxs.push(file.sections.len().checked_sub(1));
+            new_line_offsets.push(line_offset);
+        }
+
+        // The edited section's lines were replaced, so the keys of its lines
+        // are meaningless now, but the keys of other sections can be remapped.
+        // The lines of an unchanged section merged into the one before it
+        // come after that section's lines.
+        let remap_section_idx = |old_section_idx: usize| {
+            if old_section_idx == section_idx {
+                None
+            } else {
+                new_section_idxs.get(old_section_idx).copied().flatten()
+            }
+        };
+        let remap_line_idx =
+            |old_section_idx: usize, line_idx: usize| line_idx + new_line_offsets[old_section_idx];
+        let remap_selection_key = |selection_key: SelectionKey| match selection_key {
+            SelectionKey::Section(key) if key.file_idx == file_idx => {
+                remap_section_idx(key.section_idx).map(|section_idx| {
+                    SelectionKey::Section(section::SectionKey { section_idx, ..key })
+                })
+            }
+            SelectionKey::Line(key) if key.file_idx == file_idx => {
+                remap_section_idx(key.section_idx).map(|section_idx| {
+                    SelectionKey::Line(LineKey {
+                        section_idx,
+                        line_idx: remap_line_idx(key.section_idx, key.line_idx),
+                        ..key
+                    })
+                })
+            }
+            selection_key @ (SelectionKey::None
+            | SelectionKey::Dir(_)
+            | SelectionKey::File(_)
+            | SelectionKey::Section(_)
+            | SelectionKey::Line(_)) => Some(selection_key),
+        };
+        let was_expanded = self
+            .ui
+            .expanded_items
+            .contains(&SelectionKey::Section(section_key));
+        self.ui.expanded_items = self
+            .ui
+            .expanded_items
+            .iter()
+            .filter_map(|selection_key| remap_selection_key(*selection_key))
+            .collect();
+        if was_expanded {
+            self.ui
+                .expanded_items
+                .extend(replacement_section_idxs.iter().map(|section_idx| {
+                    SelectionKey::Section(section::SectionKey {
+                        section_idx: *section_idx,
+                        ..section_key
+                    })
+                }));
+        }
+        self.ui.selection_key = match remap_selection_key(self.ui.selection_key) {
+            Some(selection_key) => selection_key,
+            None => match replacement_section_idxs.first() {
+                Some(section_idx) => SelectionKey::Section(section::SectionKey {
+                    section_idx: *section_idx,
+                    ..section_key
+                }),
+                None => SelectionKey::File(FileKey {
+                    commit_idx,
+                    file_idx,
+                }),
+            },
         };
+
+        // The unchanged sections on either side of the edited section may have
+        // been merged with unchanged lines from the edit, so only the context
+        // revealed in the other sections is kept.
+        self.ui.context_expansions = std::mem::take(&mut self.ui.context_expansions)
+            .into_iter()
+            .filter_map(|((expansion_file_idx, expansion_section_idx), expansion)| {
+                if expansion_file_idx != file_idx {
+                    Some(((expansion_file_idx, expansion_section_idx), expansion))
+                } else if expansion_section_idx.abs_diff(section_idx) <= 1 {
+                    None
+                } else {
+                    remap_section_idx(expansion_section_idx)
+                        .map(|section_idx| ((file_idx, section_idx), expansion))
+                }
+            })
+            .collect();
+
+        // Comments on the other sections move with them, but the line numbers
+        // after the edit may have changed. Comments on the edited section are
+        // kept, but can no longer be found.
+        for (comment_key, comment_idx) in std::mem::take(&mut self.comment_idxs) {
+            let comment_key = match comment_key {
+                CommentKey::Section {
+                    file_idx: comment_file_idx,
+                    section_idx,
+                } if comment_file_idx == file_idx => {
+                    remap_section_idx(section_idx).map(|section_idx| CommentKey::Section {
+                        file_idx,
+                        section_idx,
+                    })
+                }
+                CommentKey::Line {
+                    file_idx: comment_file_idx,
+                    section_idx,
+                    line_idx,
+                } if comment_file_idx == file_idx => {
+                    remap_section_idx(section_idx).map(|new_section_idx| CommentKey::Line {
+                        file_idx,
+                        section_idx: new_section_idx,
+                        line_idx: remap_line_idx(section_idx, line_idx),
+                    })
+                }
+                comment_key @ (CommentKey::File { .. }
+                | CommentKey::Section { .. }
+                | CommentKey::Line { .. }) => Some(comment_key),
+            };
+            let comment = &mut self.state.comments[comment_idx];
+            if let Some(moved_comment) = comment_key.and_then(|comment_key| {
+                review::make_review_comment(&self.state.files, comment_key, comment.comment.clone())
+            }) {
+                *comment = moved_comment;
+            }
+        }
+        self.index_comments();
+
+        self.ui.visual_anchor = None;
+        self.highlighter.invalidate_file(file_idx);
+        self.word_diffs.invalidate_file(file_idx);
+        self.selection_counts.invalidate();
+        self.layout_cache.invalidate_file(file_idx);
+        self.history.invalidate_file(file_idx);
         Ok(())
     }
 
+    /// Whether the section at `section_key` is an unchanged section, whose
+    /// lines can only be selected in review mode.
+    fn is_unchanged_section(&self, section_key: section::SectionKey) -> bool {
+        match self.section(section_key) {
+            Ok(Section::Unchanged { .. }) => true,
+            Ok(Section::Changed { .. } | Section::FileMode { .. } | Section::Binary { .. })
+            | Err(_) => false,
+        }
+    }
+
     fn file(&self, file_key: FileKey) -> Result<&File<'_>, RecordError> {
         let FileKey {
             commit_idx: _,
@@ -1157,7 +3701,85 @@ impl<'state> App<'state> {
 
     fn file_tristate(&self, file_key: FileKey) -> Result<Tristate, RecordError> {
         let file = self.file(file_key)?;
-        Ok(file.tristate())
+        Ok(file.tristate_for_commit(file_key.commit_idx, self.state.commits.len()))
+    }
+
+    fn dir_tristate(&self, dir_key: DirKey) -> Result<Tristate, RecordError> {
+        let mut tristates = Vec::new();
+        for file_key in self.dir_file_keys(dir_key)? {
+            tristates.push(self.file_tristate(file_key)?);
+        }
+        Ok(file_tree::fold_file_tristates(tristates))
+    }
+
+    /// The keys of all files in the directory or its subdirectories, except
+    /// those hidden by the file filter.
+    fn dir_file_keys(&self, dir_key: DirKey) -> Result<Vec<FileKey>, RecordError> {
+        let DirKey {
+            commit_idx,
+            dir_idx,
+        } = dir_key;
+        match self.file_tree.dirs.get(dir_idx) {
+            Some(dir) => Ok(dir
+                .file_idxs
+                .iter()
+                .filter(|file_idx| self.is_file_visible(**file_idx))
+                .map(|file_idx| FileKey {
+                    commit_idx,
+                    file_idx: *file_idx,
+                })
+                .collect()),
+            None => Err(RecordError::Bug(format!(
+                "Out-of-bounds dir key: {dir_key:?}"
+            ))),
+        }
+    }
+
+    /// Whether the selected item is inside the directory, not counting the
+    /// directory itself.
+    fn is_in_dir(&self, dir_key: DirKey) -> Result<bool, RecordError> {
+        let DirKey {
+            commit_idx: _,
+            dir_idx,
+        } = dir_key;
+        if dir_idx >= self.file_tree.dirs.len() {
+            return Err(RecordError::Bug(format!(
+                "Out-of-bounds dir key: {dir_key:?}"
+            )));
+        }
+        let row = match self.ui.selection_key {
+            SelectionKey::None => return Ok(false),
+            SelectionKey::Dir(DirKey {
+                commit_idx: _,
+                dir_idx,
+            }) => FileTreeRow::Dir(dir_idx),
+            SelectionKey::File(FileKey {
+                commit_idx: _,
+                file_idx,
+            })
+            | SelectionKey::Section(section::SectionKey {
+                commit_idx: _,
+                file_idx,
+                section_idx: _,
+            })
+            | SelectionKey::Line(LineKey {
+                commit_idx: _,
+                file_idx,
+                section_idx: _,
+                line_idx: _,
+            }) => FileTreeRow::File(file_idx),
+        };
+        Ok(self
+            .file_tree
+            .ancestor_dir_idxs(row)
+            .any(|ancestor_dir_idx| ancestor_dir_idx == dir_idx))
+    }
+
+    fn is_file_tree_mode_inline(&self) -> bool {
+        match self.ui.file_tree_mode {
+            FileTreeMode::Off | FileTreeMode::Sidebar => false,
+            FileTreeMode::Inline => true,
+        }
     }
 
     fn file_expanded(&self, file_key: FileKey) -> Tristate {
@@ -1231,7 +3853,7 @@ impl<'state> App<'state> {
 
     fn section_tristate(&self, section_key: section::SectionKey) -> Result<Tristate, RecordError> {
         let section = self.section(section_key)?;
-        Ok(section.tristate())
+        Ok(section.tristate_for_commit(section_key.commit_idx, self.state.commits.len()))
     }
 
     fn visit_line<T>(
@@ -1258,3 +3880,78 @@ impl<'state> App<'state> {
         }
     }
 }
+
+/// Ensure that there are at least two commits, and that every change is
+/// assigned to a valid commit.
+pub(crate) fn normalize_commits(state: &mut RecordState) {
+    state.commits.extend(
+        iter::repeat_with(Commit::default).take(2_usize.saturating_sub(state.commits.len())),
+    );
+
+    let num_commits = state.commits.len();
+    for file in &mut state.files {
+        reassign_commit_idxs(file.sections.iter_mut(), num_commits, |commit_idx| {
+            commit_idx
+        });
+    }
+}
+
+/// Reassign each change in `sections` to the commit index returned by `f`,
+/// which is called with the index of the commit that the change is currently
+/// assigned to.
+fn reassign_commit_idxs<'a, 'b: 'a>(
+    sections: impl Iterator<Item = &'a mut Section<'b>>,
+    num_commits: usize,
+    f: impl Fn(usize) -> usize,
+) {
+    for section in sections {
+        match section {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    let commit_idx = f(line.assigned_commit_idx(num_commits));
+                    line.assign_commit_idx(commit_idx, num_commits);
+                }
+            }
+            Section::FileMode { .. } | Section::Binary { .. } => {
+                for commit_idx in section.assigned_commit_idxs(num_commits) {
+                    section.assign_commit_idx(f(commit_idx), num_commits);
+                }
+            }
+        }
+    }
+}
+
+/// After changed lines in `file` were moved to the commit at `moved_to`,
+/// update the file mode change so that the lines are still contained in the
+/// file.
+pub(crate) fn move_file_mode_for_changed_lines(
+    file: &mut File,
+    num_commits: usize,
+    moved_to: usize,
+) {
+    let old_file_mode = file.file_mode;
+    for section in &mut file.sections {
+        if let Section::FileMode { mode, .. } = section {
+            let mode = *mode;
+
+            // If we removed a line in a later commit than the one where the
+            // file was deleted, it can no longer be deleted until then, as it
+            // needs to contain that line.
+            if mode == FileMode::Absent {
+                reassign_commit_idxs(iter::once(&mut *section), num_commits, |commit_idx| {
+                    commit_idx.max(moved_to)
+                });
+            }
+
+            // If we added a line in an earlier commit than the one where the
+            // file was created, it must be created by then in order to contain
+            // that line.
+            if old_file_mode == FileMode::Absent {
+                reassign_commit_idxs(iter::once(&mut *section), num_commits, |commit_idx| {
+                    commit_idx.min(moved_to)
+                });
+            }
+        }
+    }
+}
@@ -1,14 +1,20 @@
 use crate::consts::ENV_VAR_DEBUG_UI;
 use crate::render::{DrawnRect, DrawnRects, Viewport};
-use crate::types::{RecordError, RecordState};
+use crate::types::{RecordError, RecordState, Section};
 use crate::ui::components::app::{AppDebugInfo, AppView};
 use crate::ui::components::commit_message_view::CommitViewMode;
+use crate::ui::components::section::{DiffViewMode, SectionKey};
+use crate::ui::components::status_bar::StatusBar;
 use crate::ui::components::ComponentId;
-use crate::ui::{event, input, terminal, App, StateUpdate};
+use crate::ui::file_tree::FileTreeMode;
+use crate::ui::keymap::{Keymap, KeymapError};
+use crate::ui::theme::Theme;
+use crate::ui::{event, input, section_edit, terminal, App, StateUpdate};
 use crate::util::UsizeExt;
 use ratatui::backend::{Backend, TestBackend};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::any::Any;
+use std::time::Duration;
 use std::{io, mem};
 
 /// UI component to record the user's changes.
@@ -19,6 +25,10 @@ pub struct Recorder<'state, 'input> {
     app: App<'state>,
     input: &'input mut dyn input::RecordInput,
     pending_events: Vec<event::Event>,
+    mouse_capture: bool,
+    tick_interval: Option<Duration>,
+    #[cfg(feature = "serde")]
+    session_store: Option<crate::ui::session::SessionStore>,
 }
 
 impl<'state, 'input> Recorder<'state, 'input> {
@@ -28,9 +38,109 @@ impl<'state, 'input> Recorder<'state, 'input> {
             app: App::new(state),
             input,
             pending_events: Default::default(),
+            mouse_capture: false,
+            tick_interval: None,
+            #[cfg(feature = "serde")]
+            session_store: None,
         }
     }
 
+    /// Use the given key bindings instead of the default ones. Fails if the
+    /// keymap doesn't pass [`Keymap::validate`], since conflicting bindings
+    /// would make some of them impossible to type.
+    pub fn with_keymap(mut self, keymap: Keymap) -> Result<Self, KeymapError> {
+        keymap.validate()?;
+        self.app.keymap = keymap;
+        Ok(self)
+    }
+
+    /// Use the given theme instead of the default one. If the `NO_COLOR`
+    /// environment variable is set, [`Theme::monochrome`] is used regardless.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.app.theme = theme.honor_no_color();
+        self.app.highlighter.invalidate_all();
+        self
+    }
+
+    /// Show the directory tree of the files being recorded from the start,
+    /// rather than a flat list of files.
+    pub fn with_file_tree_mode(mut self, file_tree_mode: FileTreeMode) -> Self {
+        self.app.set_file_tree_mode(file_tree_mode);
+        self.app.ui.selection_key = self.app.first_selection_key();
+        self
+    }
+
+    /// Show the given number of unchanged lines on each side of a changed
+    /// section, rather than the default of four.
+    pub fn with_context_lines(mut self, num_context_lines: usize) -> Self {
+        self.app.ui.num_context_lines = num_context_lines;
+        self
+    }
+
+    /// Save the selection to the file at `path` as it's made, so that it
+    /// isn't lost if the recorder is cancelled or the process dies. If the
+    /// file already exists when the recorder starts, the user is offered to
+    /// restore the selection saved in it. The file is deleted once the
+    /// selection is accepted.
+    ///
+    /// The saved selection is keyed by a hash of the changes being recorded.
+    /// If the changes differ from the saved ones, then the selection of each
+    /// change is matched up by its file and contents instead, so that the
+    /// selection survives small changes to the diff.
+    #[cfg(feature = "serde")]
+    pub fn with_session_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        use crate::ui::session::{hash_files, SessionStore};
+        let state_hash = hash_files(&self.app.state.files);
+        self.session_store = Some(SessionStore::new(path.into(), state_hash));
+        self
+    }
+
+    /// Show or hide the status bar at the bottom of the screen, which
+    /// summarizes the focused item and how much has been selected. It's shown
+    /// by default.
+    pub fn with_status_bar(mut self, show_status_bar: bool) -> Self {
+        self.app.ui.show_status_bar = show_status_bar;
+        self
+    }
+
+    /// Ask for confirmation before quitting. Accepting shows a summary of the
+    /// files which would be changed, and cancelling asks before discarding a
+    /// selection which was changed since the recorder started. Quitting
+    /// immediately, such as with Ctrl-C, is unaffected. Disabled by default.
+    pub fn with_quit_confirmation(mut self, confirm_quit: bool) -> Self {
+        self.app.ui.confirm_quit = confirm_quit;
+        self
+    }
+
+    /// Allow leaving comments on files, sections and lines, such as while
+    /// reviewing someone else's changes. The comments are returned in
+    /// [`RecordState::comments`], and can be exported with
+    /// [`RecordState::review_notes_markdown`]. Disabled by default.
+    pub fn with_review_mode(mut self, is_review_mode: bool) -> Self {
+        self.app.ui.is_review_mode = is_review_mode;
+        self
+    }
+
+    /// Capture mouse events when using a real terminal, so that items can be
+    /// clicked and the UI scrolled with the mouse wheel. This prevents
+    /// selecting text with the terminal's own selection (although many
+    /// terminals still allow it while holding Shift), so it's disabled by
+    /// default.
+    pub fn with_mouse_capture(mut self, mouse_capture: bool) -> Self {
+        self.mouse_capture = mouse_capture;
+        self
+    }
+
+    /// Produce [`event::Event::Tick`] whenever no input arrives within
+    /// `tick_interval`, so that [`input::RecordInput::on_tick`] can do work in
+    /// the background. Only inputs which implement
+    /// [`input::RecordInput::next_events_timeout`] produce ticks. Disabled by
+    /// default.
+    pub fn with_tick_interval(mut self, tick_interval: Option<Duration>) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
     /// Run the terminal user interface and have the user interactively select
     /// changes.
     pub fn run(self) -> Result<RecordState<'state>, RecordError> {
@@ -50,7 +160,7 @@ impl<'state, 'input> Recorder<'state, 'input> {
 
     /// Run the recorder UI using `crossterm` as the backend connected to stdout.
     fn run_crossterm(self) -> Result<RecordState<'state>, RecordError> {
-        terminal::set_up_crossterm()?;
+        terminal::set_up_crossterm(self.mouse_capture)?;
         terminal::install_panic_hook();
         let backend = CrosstermBackend::new(io::stdout());
         let mut term = Terminal::new(backend).map_err(RecordError::SetUpTerminal)?;
@@ -76,18 +186,38 @@ impl<'state, 'input> Recorder<'state, 'input> {
             false
         };
 
+        #[cfg(feature = "serde")]
+        self.load_session()?;
+
         'outer: loop {
             let app_view = self.app.view(None);
-            let term_height = usize::from(term.get_frame().area().height);
+            let status_bar = self.app.status_bar();
 
+            let mut term_height = 0;
             let mut drawn_rects: Option<DrawnRects<ComponentId>> = None;
             term.draw(|frame| {
+                // The terminal is only resized to fit the screen once drawing
+                // starts, so get its height from the frame.
+                let app_area = app_area(frame.area(), status_bar.as_ref());
+                term_height = usize::from(app_area.height);
                 drawn_rects = Some(Viewport::<ComponentId>::render_top_level(
                     frame,
+                    app_area,
                     0,
                     self.app.ui.scroll_offset_y,
                     &app_view,
                 ));
+                // The status bar isn't scrolled or clicked, so where it was
+                // drawn isn't recorded.
+                if let Some(status_bar) = &status_bar {
+                    Viewport::<ComponentId>::render_top_level(
+                        frame,
+                        frame.area(),
+                        0,
+                        0,
+                        status_bar,
+                    );
+                }
             })
             .map_err(RecordError::RenderFrame)?;
             let drawn_rects = drawn_rects.unwrap();
@@ -111,6 +241,7 @@ impl<'state, 'input> Recorder<'state, 'input> {
                 term.draw(|frame| {
                     Viewport::<ComponentId>::render_top_level(
                         frame,
+                        app_area(frame.area(), status_bar.as_ref()),
                         0,
                         self.app.ui.scroll_offset_y,
                         &debug_app,
@@ -118,21 +249,28 @@ impl<'state, 'input> Recorder<'state, 'input> {
                 })
                 .map_err(RecordError::RenderFrame)?;
             }
+            self.app.layout_cache.update(&drawn_rects, term_height);
 
-            let events = if self.pending_events.is_empty() {
-                self.input.next_events()?
-            } else {
+            let events = if !self.pending_events.is_empty() {
                 // FIXME: the pending events should be applied without redrawing
                 // the screen, as otherwise there may be a flash of content
                 // containing the screen contents before the event is applied.
                 mem::take(&mut self.pending_events)
+            } else if let Some(tick_interval) = self.tick_interval {
+                self.input.next_events_timeout(tick_interval)?
+            } else {
+                self.input.next_events()?
             };
             for event in events {
+                let event = self.app.resolve_key_event(event);
                 match self.app.handle_event(event, term_height, &drawn_rects)? {
                     StateUpdate::None => {}
                     StateUpdate::SetHelpDialog(help_dialog) => {
                         self.app.ui.help_dialog = help_dialog;
                     }
+                    StateUpdate::SetConfirmPrompt(confirm_prompt) => {
+                        self.app.ui.confirm_prompt = confirm_prompt;
+                    }
                     StateUpdate::QuitAccept => {
                         if self.app.ui.help_dialog.is_some() {
                             self.app.ui.help_dialog = None;
@@ -140,7 +278,13 @@ impl<'state, 'input> Recorder<'state, 'input> {
                             break 'outer;
                         }
                     }
-                    StateUpdate::QuitCancel => return Err(RecordError::Cancelled),
+                    StateUpdate::QuitCancel => {
+                        // Save any changes from earlier in this batch of
+                        // events, so that they can be restored later.
+                        #[cfg(feature = "serde")]
+                        self.save_session();
+                        return Err(RecordError::Cancelled);
+                    }
                     StateUpdate::TakeScreenshot(screenshot) => {
                         let backend: &dyn Any = term.backend();
                         let test_backend = backend
@@ -151,7 +295,27 @@ impl<'state, 'input> Recorder<'state, 'input> {
                     StateUpdate::Redraw => {
                         term.clear().map_err(RecordError::RenderFrame)?;
                     }
+                    StateUpdate::Resize { width, height } => {
+                        // A real terminal is resized when it's next drawn, but
+                        // a testing terminal only changes size when told to.
+                        let backend: &mut dyn Any = term.backend_mut();
+                        if let Some(test_backend) = backend.downcast_mut::<TestBackend>() {
+                            test_backend.resize(width.clamp_into_u16(), height.clamp_into_u16());
+                        }
+                        // Wrapped lines and split columns depend on the width.
+                        self.app.layout_cache.invalidate();
+                        // The scroll position can only be fixed up once the
+                        // UI has been laid out for the new size.
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::Tick => {
+                        let events = self.input.on_tick()?;
+                        self.pending_events.extend(events);
+                    }
                     StateUpdate::EnsureSelectionInViewport => {
+                        self.app.ui.scroll_offset_y =
+                            clamp_scroll_offset_y(&drawn_rects, self.app.ui.scroll_offset_y);
                         if let Some(scroll_offset_y) = self.app.ensure_in_viewport(
                             term_height,
                             &drawn_rects,
@@ -160,16 +324,22 @@ impl<'state, 'input> Recorder<'state, 'input> {
                             self.app.ui.scroll_offset_y = scroll_offset_y;
                         }
                     }
+                    StateUpdate::ScrollHorizontallyTo(scroll_offset_x) => {
+                        self.app.ui.scroll_offset_x = scroll_offset_x;
+                    }
                     StateUpdate::ScrollTo(scroll_offset_y) => {
-                        self.app.ui.scroll_offset_y = scroll_offset_y.clamp(0, {
-                            let DrawnRect { rect, timestamp: _ } = drawn_rects[&ComponentId::App];
-                            rect.height.unwrap_isize() - 1
-                        });
+                        self.app.ui.scroll_offset_y =
+                            clamp_scroll_offset_y(&drawn_rects, scroll_offset_y);
                     }
                     StateUpdate::SelectItem {
                         selection_key,
                         ensure_in_viewport,
                     } => {
+                        // The item may be in a different commit view if it was
+                        // clicked on.
+                        if let Some(commit_idx) = selection_key.commit_idx() {
+                            self.app.focus_commit(commit_idx);
+                        }
                         self.app.ui.selection_key = selection_key;
                         self.app.expand_item_ancestors(selection_key);
                         if ensure_in_viewport {
@@ -178,25 +348,103 @@ impl<'state, 'input> Recorder<'state, 'input> {
                         }
                     }
                     StateUpdate::ToggleItem(selection_key) => {
-                        self.app.toggle_item(selection_key)?;
+                        self.app
+                            .with_history(|app| app.toggle_item(selection_key))?;
                     }
                     StateUpdate::ToggleItemAndAdvance(selection_key, new_key) => {
-                        self.app.toggle_item(selection_key)?;
+                        self.app
+                            .with_history(|app| app.toggle_item(selection_key))?;
                         self.app.ui.selection_key = new_key;
                         self.pending_events
                             .push(event::Event::EnsureSelectionInViewport);
                     }
                     StateUpdate::ToggleAll => {
-                        self.app.toggle_all();
+                        self.app.with_history(|app| {
+                            app.toggle_all();
+                            Ok(())
+                        })?;
                     }
                     StateUpdate::ToggleAllUniform => {
-                        self.app.toggle_all_uniform();
+                        self.app.with_history(|app| {
+                            app.toggle_all_uniform();
+                            Ok(())
+                        })?;
+                    }
+                    StateUpdate::SetVisualAnchor(visual_anchor) => {
+                        self.app.ui.visual_anchor = visual_anchor;
+                    }
+                    StateUpdate::ApplyToRange(action) => {
+                        self.app.with_history(|app| app.apply_to_range(action))?;
+                    }
+                    StateUpdate::MoveItemToCommit(selection_key, commit_idx) => {
+                        self.app.with_history(|app| {
+                            app.move_item_to_commit(selection_key, commit_idx)
+                        })?;
+                    }
+                    StateUpdate::Undo => {
+                        if self.app.undo() {
+                            self.pending_events
+                                .push(event::Event::EnsureSelectionInViewport);
+                        }
+                    }
+                    StateUpdate::Redo => {
+                        if self.app.redo() {
+                            self.pending_events
+                                .push(event::Event::EnsureSelectionInViewport);
+                        }
+                    }
+                    StateUpdate::StartSearch => {
+                        self.app.start_search();
+                    }
+                    StateUpdate::UpdateSearchPrompt(text) => {
+                        self.app.update_search_prompt(text);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::FinishSearch { accept } => {
+                        self.app.finish_search(accept);
+                    }
+                    StateUpdate::ClearSearch => {
+                        self.app.ui.search_query = None;
+                    }
+                    StateUpdate::SelectSearchMatch(selection_key) => {
+                        self.app.select_search_match(selection_key);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::StartFilter => {
+                        self.app.start_filter();
+                    }
+                    StateUpdate::UpdateFilterPrompt(text) => {
+                        self.app.update_filter_prompt(text);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::FinishFilter { accept } => {
+                        self.app.finish_filter(accept);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::ClearFilter => {
+                        self.app.set_file_filter(None);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::FocusCommit(commit_idx) => {
+                        self.app.focus_commit(commit_idx);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
                     }
                     StateUpdate::SetExpandItem(selection_key, is_expanded) => {
                         self.app.set_expand_item(selection_key, is_expanded);
                         self.pending_events
                             .push(event::Event::EnsureSelectionInViewport);
                     }
+                    StateUpdate::ExpandContext(expansions) => {
+                        for (section_key, expansion) in expansions {
+                            self.app.expand_context(section_key, expansion);
+                        }
+                    }
                     StateUpdate::ToggleExpandItem(selection_key) => {
                         self.app.toggle_expand_item(selection_key)?;
                         self.pending_events
@@ -212,41 +460,180 @@ impl<'state, 'input> Recorder<'state, 'input> {
                             CommitViewMode::Inline => CommitViewMode::Adjacent,
                             CommitViewMode::Adjacent => CommitViewMode::Inline,
                         };
+                        self.app.layout_cache.invalidate();
+                    }
+                    StateUpdate::ToggleDiffViewMode => {
+                        self.app.ui.diff_view_mode = match self.app.ui.diff_view_mode {
+                            DiffViewMode::Unified => DiffViewMode::Split,
+                            DiffViewMode::Split => DiffViewMode::Unified,
+                        };
+                        self.app.layout_cache.invalidate();
+                    }
+                    StateUpdate::ToggleLineWrap => {
+                        self.app.ui.wrap_lines = !self.app.ui.wrap_lines;
+                        self.app.layout_cache.invalidate();
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::SetFileTreeMode(file_tree_mode) => {
+                        self.app.set_file_tree_mode(file_tree_mode);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
                     }
                     StateUpdate::EditCommitMessage { commit_idx } => {
+                        self.app.open_message_editor(commit_idx);
+                    }
+                    StateUpdate::UpdateMessageEditor(message_editor) => {
+                        self.app.ui.message_editor = Some(message_editor);
+                    }
+                    StateUpdate::FinishMessageEditor { accept } => {
+                        self.app.finish_message_editor(accept);
+                    }
+                    StateUpdate::EditMessageExternally => {
                         self.pending_events.push(event::Event::Redraw);
-                        self.edit_commit_message(commit_idx)?;
+                        self.edit_message_externally()?;
+                    }
+                    StateUpdate::EditAddedLines(section_key) => {
+                        self.pending_events.push(event::Event::Redraw);
+                        self.edit_added_lines(section_key)?;
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::EditComment(comment_key) => {
+                        self.app.open_comment_editor(comment_key);
+                    }
+                    StateUpdate::SetCommentList(comment_list) => {
+                        self.app.ui.comment_list = comment_list;
+                    }
+                    StateUpdate::JumpToComment(comment_key) => {
+                        self.app.jump_to_comment(comment_key);
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
+                    }
+                    StateUpdate::FinishSessionPrompt { restore } => {
+                        self.app.finish_session_prompt(restore)?;
+                        self.pending_events
+                            .push(event::Event::EnsureSelectionInViewport);
                     }
                 }
             }
+
+            #[cfg(feature = "serde")]
+            self.save_session();
         }
 
+        #[cfg(feature = "serde")]
+        if let Some(session_store) = &self.session_store {
+            session_store.remove()?;
+        }
         Ok(self.app.state)
     }
 
-    fn edit_commit_message(&mut self, commit_idx: usize) -> Result<(), RecordError> {
-        let message = &mut self.app.state.commits[commit_idx].message;
-        let message_str = match message.as_ref() {
-            Some(message) => message,
+    /// Offer to restore the selection from the session file, if any.
+    #[cfg(feature = "serde")]
+    fn load_session(&mut self) -> Result<(), RecordError> {
+        if let Some(session_store) = &self.session_store {
+            if let Some(session) = session_store.load()? {
+                self.app
+                    .open_session_prompt(session, session_store.state_hash());
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the current selection to the session file, if any. Failing to
+    /// save it isn't fatal, since the selection can still be accepted.
+    #[cfg(feature = "serde")]
+    fn save_session(&mut self) {
+        let session_store = match &mut self.session_store {
+            Some(session_store) => session_store,
+            None => return,
+        };
+        // Don't overwrite the saved session before the user has decided
+        // whether to restore it.
+        if self.app.state.is_read_only || self.app.ui.session_prompt.is_some() {
+            return;
+        }
+        let session = self.app.session(session_store.state_hash());
+        if let Err(err) = session_store.save(&session) {
+            tracing::warn!(?err, "failed to save session");
+        }
+    }
+
+    fn edit_message_externally(&mut self) -> Result<(), RecordError> {
+        let message_editor = match &mut self.app.ui.message_editor {
+            Some(message_editor) => message_editor,
             None => return Ok(()),
         };
-        let new_message = {
-            match self.input.terminal_kind() {
-                terminal::TerminalKind::Testing { .. } => {}
-                terminal::TerminalKind::Crossterm => {
-                    terminal::clean_up_crossterm()?;
-                }
-            }
-            let result = self.input.edit_commit_message(message_str);
-            match self.input.terminal_kind() {
-                terminal::TerminalKind::Testing { .. } => {}
-                terminal::TerminalKind::Crossterm => {
-                    terminal::set_up_crossterm()?;
-                }
+        match with_terminal_released(self.input, self.mouse_capture, |input| {
+            input.edit_commit_message(message_editor.text())
+        }) {
+            Ok(new_message) => message_editor.set_text(new_message),
+            Err(RecordError::Cancelled) => {}
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    fn edit_added_lines(&mut self, section_key: SectionKey) -> Result<(), RecordError> {
+        let lines = match self.app.section(section_key)? {
+            Section::Changed { lines } => section_edit::added_lines_text(lines),
+            Section::Unchanged { .. } | Section::FileMode { .. } | Section::Binary { .. } => {
+                return Ok(());
             }
-            result?
         };
-        *message = Some(new_message);
-        Ok(())
+        match with_terminal_released(self.input, self.mouse_capture, |input| {
+            input.edit_added_lines(&lines)
+        }) {
+            Ok(edited_lines) => self.app.replace_added_lines(section_key, &edited_lines),
+            Err(RecordError::Cancelled) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The area of the terminal in which to draw the [`AppView`], leaving room for
+/// the status bar at the bottom, if it's shown.
+fn app_area(
+    frame_area: ratatui::layout::Rect,
+    status_bar: Option<&StatusBar>,
+) -> ratatui::layout::Rect {
+    match status_bar {
+        // Don't leave the app with no room at all in a tiny terminal.
+        Some(_) if frame_area.height > StatusBar::HEIGHT => ratatui::layout::Rect {
+            height: frame_area.height - StatusBar::HEIGHT,
+            ..frame_area
+        },
+        Some(_) | None => frame_area,
+    }
+}
+
+/// Clamp `scroll_offset_y` so that at least the last line of the commit views
+/// is visible.
+fn clamp_scroll_offset_y(drawn_rects: &DrawnRects<ComponentId>, scroll_offset_y: isize) -> isize {
+    let DrawnRect { rect, timestamp: _ } = drawn_rects[&ComponentId::CommitViews];
+    scroll_offset_y.clamp(0, rect.height.unwrap_isize() - 1)
+}
+
+/// Run `f`, which may take over the terminal, such as to open an editor, while
+/// the UI is suspended.
+fn with_terminal_released<T>(
+    input: &mut dyn input::RecordInput,
+    mouse_capture: bool,
+    f: impl FnOnce(&mut dyn input::RecordInput) -> Result<T, RecordError>,
+) -> Result<T, RecordError> {
+    match input.terminal_kind() {
+        terminal::TerminalKind::Testing { .. } => {}
+        terminal::TerminalKind::Crossterm => {
+            terminal::clean_up_crossterm()?;
+        }
+    }
+    let result = f(input);
+    match input.terminal_kind() {
+        terminal::TerminalKind::Testing { .. } => {}
+        terminal::TerminalKind::Crossterm => {
+            terminal::set_up_crossterm(mouse_capture)?;
+        }
     }
+    result
 }
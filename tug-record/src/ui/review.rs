@@ -0,0 +1,367 @@
+//! Leaving comments on the changes while reviewing them.
+
+use std::collections::HashMap;
+
+use crate::types::{
+    ChangeType, File, ReviewComment, ReviewCommentTarget, Section, SectionChangedLine,
+};
+use crate::ui::components::app::SelectionKey;
+use crate::ui::components::file::FileKey;
+use crate::ui::components::line::LineKey;
+use crate::ui::components::section::SectionKey;
+
+/// The item which a comment is attached to. Unlike a [`SelectionKey`], it
+/// doesn't depend on the commit view which the item is shown in.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum CommentKey {
+    File {
+        file_idx: usize,
+    },
+    Section {
+        file_idx: usize,
+        section_idx: usize,
+    },
+    Line {
+        file_idx: usize,
+        section_idx: usize,
+        line_idx: usize,
+    },
+}
+
+impl CommentKey {
+    /// The key for the item focused by `selection_key`, if it can be
+    /// commented on.
+    pub fn from_selection_key(selection_key: SelectionKey) -> Option<Self> {
+        match selection_key {
+            SelectionKey::None | SelectionKey::Dir(_) => None,
+            SelectionKey::File(FileKey {
+                commit_idx: _,
+                file_idx,
+            }) => Some(Self::File { file_idx }),
+            SelectionKey::Section(SectionKey {
+                commit_idx: _,
+                file_idx,
+                section_idx,
+            }) => Some(Self::Section {
+                file_idx,
+                section_idx,
+            }),
+            SelectionKey::Line(LineKey {
+                commit_idx: _,
+                file_idx,
+                section_idx,
+                line_idx,
+            }) => Some(Self::Line {
+                file_idx,
+                section_idx,
+                line_idx,
+            }),
+        }
+    }
+
+    /// The key for this item in the commit view at `commit_idx`.
+    pub fn selection_key(self, commit_idx: usize) -> SelectionKey {
+        match self {
+            Self::File { file_idx } => SelectionKey::File(FileKey {
+                commit_idx,
+                file_idx,
+            }),
+            Self::Section {
+                file_idx,
+                section_idx,
+            } => SelectionKey::Section(SectionKey {
+                commit_idx,
+                file_idx,
+                section_idx,
+            }),
+            Self::Line {
+                file_idx,
+                section_idx,
+                line_idx,
+            } => SelectionKey::Line(LineKey {
+                commit_idx,
+                file_idx,
+                section_idx,
+                line_idx,
+            }),
+        }
+    }
+
+    /// The index of the file containing the item.
+    pub fn file_idx(self) -> usize {
+        match self {
+            Self::File { file_idx }
+            | Self::Section { file_idx, .. }
+            | Self::Line { file_idx, .. } => file_idx,
+        }
+    }
+}
+
+/// The numbers of the lines in the old and new versions of `file` at which
+/// each of its sections starts, counting from 1.
+pub fn section_start_line_nums(file: &File) -> Vec<(usize, usize)> {
+    let mut line_num = 1;
+    let mut new_line_num = 1;
+    let mut result = Vec::with_capacity(file.sections.len());
+    for section in &file.sections {
+        result.push((line_num, new_line_num));
+        match section {
+            Section::Unchanged { lines } => {
+                line_num += lines.len();
+                new_line_num += lines.len();
+            }
+            Section::Changed { lines } => {
+                for changed_line in lines {
+                    match changed_line.change_type {
+                        ChangeType::Added => new_line_num += 1,
+                        ChangeType::Removed => line_num += 1,
+                    }
+                }
+            }
+            Section::FileMode { .. } | Section::Binary { .. } => {}
+        }
+    }
+    result
+}
+
+/// Describe the item at `comment_key` in a [`ReviewComment`] containing
+/// `comment`. Returns `None` if there's no such item.
+pub fn make_review_comment(
+    files: &[File],
+    comment_key: CommentKey,
+    comment: String,
+) -> Option<ReviewComment> {
+    let file = files.get(comment_key.file_idx())?;
+    let (target, old_line_num, new_line_num, line) = match comment_key {
+        CommentKey::File { file_idx: _ } => (ReviewCommentTarget::File, None, None, None),
+        CommentKey::Section {
+            file_idx: _,
+            section_idx,
+        } => {
+            let section = file.sections.get(section_idx)?;
+            let (old_line_num, new_line_num) = section_start_line_nums(file)[section_idx];
+            (
+                ReviewCommentTarget::Section,
+                Some(old_line_num),
+                Some(new_line_num),
+                first_line(section),
+            )
+        }
+        CommentKey::Line {
+            file_idx: _,
+            section_idx,
+            line_idx,
+        } => {
+            let start_line_nums = section_start_line_nums(file)[section_idx];
+            let (old_line_num, new_line_num, line) = match file.sections.get(section_idx)? {
+                Section::Unchanged { lines } => {
+                    let (old_line_num, new_line_num) =
+                        unchanged_line_nums(start_line_nums, line_idx);
+                    (
+                        Some(old_line_num),
+                        Some(new_line_num),
+                        lines.get(line_idx)?.as_ref(),
+                    )
+                }
+                Section::Changed { lines } => {
+                    let changed_line = lines.get(line_idx)?;
+                    let (old_line_num, new_line_num) =
+                        changed_line_nums(start_line_nums, lines).nth(line_idx)?;
+                    (old_line_num, new_line_num, changed_line.line.as_ref())
+                }
+                Section::FileMode { .. } | Section::Binary { .. } => return None,
+            };
+            (
+                ReviewCommentTarget::Line,
+                old_line_num,
+                new_line_num,
+                Some(trim_newline(line)),
+            )
+        }
+    };
+    Some(ReviewComment {
+        path: file.path.to_path_buf(),
+        target,
+        old_line_num,
+        new_line_num,
+        line: line.map(str::to_owned),
+        comment,
+    })
+}
+
+/// The first line of `section`, if it has any lines.
+fn first_line<'a>(section: &'a Section) -> Option<&'a str> {
+    let line = match section {
+        Section::Unchanged { lines } => lines.first()?.as_ref(),
+        Section::Changed { lines } => lines.first()?.line.as_ref(),
+        Section::FileMode { .. } | Section::Binary { .. } => return None,
+    };
+    Some(trim_newline(line))
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// The numbers of the unchanged line at `line_idx` in the old and new
+/// versions of the file, given the numbers of the lines at which its section
+/// starts.
+fn unchanged_line_nums(
+    (old_line_num, new_line_num): (usize, usize),
+    line_idx: usize,
+) -> (usize, usize) {
+    (old_line_num + line_idx, new_line_num + line_idx)
+}
+
+/// The numbers of each of the changed `lines` in the old and new versions of
+/// the file, given the numbers of the lines at which their section starts.
+/// Removed lines only have an old line number, and added lines only have a
+/// new one.
+fn changed_line_nums<'a>(
+    (old_line_num, new_line_num): (usize, usize),
+    lines: &'a [SectionChangedLine],
+) -> impl Iterator<Item = (Option<usize>, Option<usize>)> + 'a {
+    lines.iter().scan(
+        (old_line_num, new_line_num),
+        |(old_line_num, new_line_num), line| {
+            Some(match line.change_type {
+                ChangeType::Added => {
+                    *new_line_num += 1;
+                    (None, Some(*new_line_num - 1))
+                }
+                ChangeType::Removed => {
+                    *old_line_num += 1;
+                    (Some(*old_line_num - 1), None)
+                }
+            })
+        },
+    )
+}
+
+/// Find the item which `comment` is attached to, by looking for an item in
+/// `files` which would be described the same way.
+fn find_comment_key(files: &[File], comment: &ReviewComment) -> Option<CommentKey> {
+    let ReviewComment {
+        path,
+        target,
+        old_line_num,
+        new_line_num,
+        line,
+        comment: _,
+    } = comment;
+    let file_idx = files.iter().position(|file| file.path.as_ref() == path)?;
+    let file = &files[file_idx];
+    let start_line_nums = section_start_line_nums(file);
+    match target {
+        ReviewCommentTarget::File => Some(CommentKey::File { file_idx }),
+        ReviewCommentTarget::Section => {
+            let section_idx = file.sections.iter().zip(&start_line_nums).position(
+                |(section, (section_old_line_num, section_new_line_num))| {
+                    *old_line_num == Some(*section_old_line_num)
+                        && *new_line_num == Some(*section_new_line_num)
+                        && first_line(section) == line.as_deref()
+                },
+            )?;
+            Some(CommentKey::Section {
+                file_idx,
+                section_idx,
+            })
+        }
+        ReviewCommentTarget::Line => {
+            for (section_idx, (section, section_start_line_nums)) in
+                file.sections.iter().zip(&start_line_nums).enumerate()
+            {
+                let line_idx = match section {
+                    Section::Unchanged { lines } => {
+                        lines
+                            .iter()
+                            .enumerate()
+                            .position(|(line_idx, unchanged_line)| {
+                                let (section_old_line_num, section_new_line_num) =
+                                    unchanged_line_nums(*section_start_line_nums, line_idx);
+                                (*old_line_num, *new_line_num)
+                                    == (Some(section_old_line_num), Some(section_new_line_num))
+                                    && Some(trim_newline(unchanged_line)) == line.as_deref()
+                            })
+                    }
+                    Section::Changed { lines } => {
+                        let line_nums = changed_line_nums(*section_start_line_nums, lines);
+                        lines
+                            .iter()
+                            .zip(line_nums)
+                            .position(|(changed_line, line_nums)| {
+                                line_nums == (*old_line_num, *new_line_num)
+                                    && Some(trim_newline(&changed_line.line)) == line.as_deref()
+                            })
+                    }
+                    Section::FileMode { .. } | Section::Binary { .. } => None,
+                };
+                if let Some(line_idx) = line_idx {
+                    return Some(CommentKey::Line {
+                        file_idx,
+                        section_idx,
+                        line_idx,
+                    });
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Sort `comments` by the position of the items which they're attached to,
+/// and return the index of the comment attached to each item. Comments whose
+/// items can't be found are kept, after the others.
+pub fn index_comments(
+    files: &[File],
+    comments: &mut Vec<ReviewComment>,
+) -> HashMap<CommentKey, usize> {
+    let mut found = Vec::new();
+    let mut not_found = Vec::new();
+    for comment in comments.drain(..) {
+        match find_comment_key(files, &comment) {
+            Some(comment_key) => found.push((comment_key, comment)),
+            None => not_found.push(comment),
+        }
+    }
+    found.sort_by_key(|(comment_key, _comment)| *comment_key);
+
+    let mut comment_idxs = HashMap::new();
+    for (comment_key, comment) in found {
+        comment_idxs.insert(comment_key, comments.len());
+        comments.push(comment);
+    }
+    comments.extend(not_found);
+    comment_idxs
+}
+
+/// A short description of where `comment` is attached, such as
+/// `src/main.rs:12`.
+pub fn comment_location(comment: &ReviewComment) -> String {
+    let ReviewComment {
+        path,
+        target,
+        old_line_num,
+        new_line_num,
+        line: _,
+        comment: _,
+    } = comment;
+    let path = path.to_string_lossy();
+    match (target, old_line_num, new_line_num) {
+        (ReviewCommentTarget::File, _, _) => path.into_owned(),
+        (ReviewCommentTarget::Section, _, Some(line_num)) => {
+            format!("{path}:{line_num} (section)")
+        }
+        (ReviewCommentTarget::Line, None, Some(line_num)) => format!("{path}:{line_num}"),
+        (ReviewCommentTarget::Line, Some(line_num), None) => format!("{path}:{line_num} (old)"),
+        (ReviewCommentTarget::Line, Some(_), Some(line_num)) => format!("{path}:{line_num}"),
+        (ReviewCommentTarget::Section | ReviewCommentTarget::Line, _, _) => path.into_owned(),
+    }
+}
+
+/// The state of the dialog listing all of the comments while it's open.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommentList {
+    /// The index of the focused comment in [`crate::RecordState::comments`].
+    pub focused_idx: usize,
+}
@@ -0,0 +1,134 @@
+//! Searching for text in file paths and diff lines.
+
+use std::ops::Range;
+
+use crate::ui::components::app::SelectionKey;
+
+/// A search query entered by the user.
+///
+/// Matching is "smart case": the query is matched case-insensitively unless it
+/// contains an uppercase character.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchQuery {
+    text: String,
+    is_case_sensitive: bool,
+}
+
+impl SearchQuery {
+    /// Constructor. Returns `None` if the query is empty, since an empty query
+    /// would match everything.
+    pub fn new(text: impl Into<String>) -> Option<Self> {
+        let text = text.into();
+        if text.is_empty() {
+            return None;
+        }
+        let is_case_sensitive = text.chars().any(char::is_uppercase);
+        Some(Self {
+            text,
+            is_case_sensitive,
+        })
+    }
+
+    /// Whether the query matches anywhere in `haystack`.
+    pub fn is_match(&self, haystack: &str) -> bool {
+        haystack
+            .char_indices()
+            .any(|(start, _)| self.match_len_at(&haystack[start..]).is_some())
+    }
+
+    /// The non-overlapping byte ranges of `haystack` which match the query.
+    pub fn find_matches(&self, haystack: &str) -> Vec<Range<usize>> {
+        let mut result = Vec::new();
+        let mut next_start = 0;
+        for (start, _) in haystack.char_indices() {
+            if start < next_start {
+                continue;
+            }
+            if let Some(len) = self.match_len_at(&haystack[start..]) {
+                result.push(start..start + len);
+                next_start = start + len;
+            }
+        }
+        result
+    }
+
+    /// If the query matches at the start of `haystack`, the length in bytes of
+    /// the matched prefix.
+    fn match_len_at(&self, haystack: &str) -> Option<usize> {
+        let Self {
+            text,
+            is_case_sensitive,
+        } = self;
+        if *is_case_sensitive {
+            return haystack.starts_with(text.as_str()).then_some(text.len());
+        }
+
+        let mut haystack_chars = haystack.char_indices();
+        for query_char in text.chars() {
+            let (_, haystack_char) = haystack_chars.next()?;
+            if !query_char.to_lowercase().eq(haystack_char.to_lowercase()) {
+                return None;
+            }
+        }
+        Some(match haystack_chars.next() {
+            Some((end, _)) => end,
+            None => haystack.len(),
+        })
+    }
+}
+
+/// The state of the search prompt while the user is typing a query.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchPrompt {
+    /// The text typed so far.
+    pub text: String,
+
+    /// The selection when the prompt was opened. Matches are searched for
+    /// starting from here, and it's restored if the search is cancelled.
+    pub origin_selection_key: SelectionKey,
+
+    /// The scroll offset when the prompt was opened, restored if the search
+    /// is cancelled.
+    pub origin_scroll_offset_y: isize,
+
+    /// The query which was active when the prompt was opened, restored if the
+    /// search is cancelled.
+    pub previous_query: Option<SearchQuery>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_query() {
+        assert_eq!(SearchQuery::new(""), None);
+    }
+
+    #[test]
+    fn test_smart_case() {
+        let query = SearchQuery::new("foo").unwrap();
+        assert!(query.is_match("foo"));
+        assert!(query.is_match("a FOO b"));
+        assert!(!query.is_match("fo"));
+
+        let query = SearchQuery::new("Foo").unwrap();
+        assert!(query.is_match("a Foo b"));
+        assert!(!query.is_match("foo"));
+        assert!(!query.is_match("FOO"));
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let query = SearchQuery::new("aa").unwrap();
+        assert_eq!(query.find_matches("aaaaa"), vec![0..2, 2..4]);
+        assert_eq!(query.find_matches("xAax aa"), vec![1..3, 5..7]);
+        assert!(query.find_matches("bbb").is_empty());
+
+        // Ranges are in bytes, and case-insensitive matching works on
+        // non-ASCII characters.
+        let query = SearchQuery::new("é").unwrap();
+        assert_eq!(query.find_matches("aÉé"), vec![1..3, 3..5]);
+        assert!(SearchQuery::new("σ").unwrap().is_match("Σ"));
+    }
+}
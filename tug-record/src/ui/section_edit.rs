@@ -0,0 +1,360 @@
+//! Editing the added lines of a changed section by hand, like the `e` action
+//! of `git add -p`.
+
+use std::borrow::Cow;
+
+use crate::{ChangeType, Section, SectionChangedLine};
+
+/// Don't diff the edited lines against the removed lines if their counts
+/// multiply to more than this, since the diff takes quadratic time and space.
+/// Instead, all of the removed lines are kept and all of the edited lines are
+/// added.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// The text to present to the user for editing: the added lines of the
+/// section, concatenated.
+pub fn added_lines_text(lines: &[SectionChangedLine]) -> String {
+    lines
+        .iter()
+        .filter(|line| match line.change_type {
+            ChangeType::Added => true,
+            ChangeType::Removed => false,
+        })
+        .map(|line| line.line.as_ref())
+        .collect()
+}
+
+/// Replace the added lines of a changed section with `edited_text`, and diff
+/// the result against the section's removed lines again. Returns the sections
+/// which should replace the original section.
+///
+/// Lines which the removed lines and the edited text have in common become
+/// unchanged sections, so there may be more than one section, or none at all
+/// if the section had only added lines and they were all deleted.
+///
+/// Removed lines keep their selection state. Added lines which are identical
+/// to one of the original added lines keep its selection state, and all other
+/// added lines are assigned to the commit at `commit_idx`.
+pub fn rediff_section<'a>(
+    lines: &[SectionChangedLine<'a>],
+    edited_text: &str,
+    commit_idx: usize,
+    num_commits: usize,
+) -> Vec<Section<'a>> {
+    let (removed_lines, original_added_lines): (Vec<_>, Vec<_>) =
+        lines.iter().partition(|line| match line.change_type {
+            ChangeType::Added => false,
+            ChangeType::Removed => true,
+        });
+
+    // Editors usually add a trailing newline, which shouldn't be recorded as
+    // a change if the original lines didn't end with one (such as at the end
+    // of a file without a trailing newline).
+    let original_text = added_lines_text(lines);
+    let edited_text = match edited_text.strip_suffix('\n') {
+        Some(stripped) if !original_text.is_empty() && !original_text.ends_with('\n') => stripped,
+        Some(_) | None => edited_text,
+    };
+    let edited_lines: Vec<&str> = edited_text.split_inclusive('\n').collect();
+
+    let mut is_original_line_used = vec![false; original_added_lines.len()];
+    let mut make_added_line = |line: &str| {
+        let original_line = original_added_lines
+            .iter()
+            .zip(is_original_line_used.iter_mut())
+            .find(|(original_line, is_used)| !**is_used && original_line.line == line);
+        match original_line {
+            Some((original_line, is_used)) => {
+                *is_used = true;
+                (*original_line).clone()
+            }
+            None => {
+                let mut added_line = SectionChangedLine {
+                    is_checked: false,
+                    commit_idx: 0,
+                    change_type: ChangeType::Added,
+                    line: Cow::Owned(line.to_owned()),
+                };
+                added_line.assign_commit_idx(commit_idx, num_commits);
+                added_line
+            }
+        }
+    };
+
+    let mut result = Vec::new();
+    let mut changed_lines: Vec<SectionChangedLine> = Vec::new();
+    let mut added_lines: Vec<SectionChangedLine> = Vec::new();
+    let mut unchanged_lines: Vec<Cow<str>> = Vec::new();
+    let flush_changed_lines =
+        |result: &mut Vec<Section<'a>>,
+         changed_lines: &mut Vec<SectionChangedLine<'a>>,
+         added_lines: &mut Vec<SectionChangedLine<'a>>| {
+            // Removed lines are conventionally listed before added lines.
+            changed_lines.append(added_lines);
+            if !changed_lines.is_empty() {
+                result.push(Section::Changed {
+                    lines: std::mem::take(changed_lines),
+                });
+            }
+        };
+    for line_diff in diff_lines(&removed_lines, &edited_lines) {
+        match line_diff {
+            LineDiff::Common(removed_idx) => {
+                flush_changed_lines(&mut result, &mut changed_lines, &mut added_lines);
+                unchanged_lines.push(removed_lines[removed_idx].line.clone());
+            }
+            LineDiff::Removed(removed_idx) => {
+                if !unchanged_lines.is_empty() {
+                    result.push(Section::Unchanged {
+                        lines: std::mem::take(&mut unchanged_lines),
+                    });
+                }
+                changed_lines.push(removed_lines[removed_idx].clone());
+            }
+            LineDiff::Added(edited_idx) => {
+                if !unchanged_lines.is_empty() {
+                    result.push(Section::Unchanged {
+                        lines: std::mem::take(&mut unchanged_lines),
+                    });
+                }
+                added_lines.push(make_added_line(edited_lines[edited_idx]));
+            }
+        }
+    }
+    flush_changed_lines(&mut result, &mut changed_lines, &mut added_lines);
+    if !unchanged_lines.is_empty() {
+        result.push(Section::Unchanged {
+            lines: unchanged_lines,
+        });
+    }
+    result
+}
+
+enum LineDiff {
+    /// The removed line at the given index is also in the edited lines.
+    Common(usize),
+
+    /// The removed line at the given index isn't in the edited lines.
+    Removed(usize),
+
+    /// The edited line at the given index isn't in the removed lines.
+    Added(usize),
+}
+
+/// Diff the removed lines against the edited lines using their longest
+/// common subsequence.
+fn diff_lines(removed_lines: &[&SectionChangedLine], edited_lines: &[&str]) -> Vec<LineDiff> {
+    let num_removed = removed_lines.len();
+    let num_edited = edited_lines.len();
+    if num_removed.saturating_mul(num_edited) > MAX_DIFF_CELLS {
+        return (0..num_removed)
+            .map(LineDiff::Removed)
+            .chain((0..num_edited).map(LineDiff::Added))
+            .collect();
+    }
+
+    // `lcs_lens[i][j]` is the length of the LCS of `removed_lines[i..]` and
+    // `edited_lines[j..]`.
+    let is_equal = |i: usize, j: usize| removed_lines[i].line == edited_lines[j];
+    let num_cols = num_edited + 1;
+    let mut lcs_lens = vec![0_usize; (num_removed + 1) * num_cols];
+    for i in (0..num_removed).rev() {
+        for j in (0..num_edited).rev() {
+            lcs_lens[i * num_cols + j] = if is_equal(i, j) {
+                lcs_lens[(i + 1) * num_cols + j + 1] + 1
+            } else {
+                lcs_lens[(i + 1) * num_cols + j].max(lcs_lens[i * num_cols + j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < num_removed || j < num_edited {
+        if i < num_removed && j < num_edited && is_equal(i, j) {
+            result.push(LineDiff::Common(i));
+            i += 1;
+            j += 1;
+        } else if j == num_edited
+            || (i < num_removed
+                && lcs_lens[(i + 1) * num_cols + j] >= lcs_lens[i * num_cols + j + 1])
+        {
+            result.push(LineDiff::Removed(i));
+            i += 1;
+        } else {
+            result.push(LineDiff::Added(j));
+            j += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    fn changed_line(
+        is_checked: bool,
+        change_type: ChangeType,
+        line: &'static str,
+    ) -> SectionChangedLine<'static> {
+        SectionChangedLine {
+            is_checked,
+            commit_idx: 0,
+            change_type,
+            line: Cow::Borrowed(line),
+        }
+    }
+
+    /// One row per line, with the selection state and commit of each changed
+    /// line, and a separator between sections.
+    fn render_sections(sections: &[Section]) -> String {
+        let render_line = |prefix: String, line: &str| match line.strip_suffix('\n') {
+            Some(line) => format!("{prefix}{line}\n"),
+            None => format!("{prefix}{line} (no newline)\n"),
+        };
+        sections
+            .iter()
+            .map(|section| match section {
+                Section::Unchanged { lines } => lines
+                    .iter()
+                    .map(|line| render_line("        ".to_owned(), line))
+                    .collect::<String>(),
+                Section::Changed { lines } => lines
+                    .iter()
+                    .map(|line| {
+                        let SectionChangedLine {
+                            is_checked,
+                            commit_idx,
+                            change_type,
+                            line,
+                        } = line;
+                        let change_type = match change_type {
+                            ChangeType::Added => '+',
+                            ChangeType::Removed => '-',
+                        };
+                        let is_checked = if *is_checked { 'x' } else { ' ' };
+                        render_line(format!("{change_type} [{is_checked}] {commit_idx} "), line)
+                    })
+                    .collect(),
+                Section::FileMode { .. } | Section::Binary { .. } => {
+                    panic!("unexpected section: {section:?}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("---\n")
+    }
+
+    #[test]
+    fn test_added_lines_text() {
+        let lines = [
+            changed_line(false, ChangeType::Removed, "foo\n"),
+            changed_line(false, ChangeType::Added, "bar\n"),
+            changed_line(false, ChangeType::Added, "baz"),
+        ];
+        assert_eq!(added_lines_text(&lines), "bar\nbaz");
+        assert_eq!(added_lines_text(&lines[..1]), "");
+    }
+
+    #[test]
+    fn test_rediff_unchanged_text() {
+        let lines = [
+            changed_line(false, ChangeType::Removed, "foo\n"),
+            changed_line(true, ChangeType::Added, "bar\n"),
+        ];
+        let edited_text = added_lines_text(&lines);
+        assert_snapshot!(render_sections(&rediff_section(&lines, &edited_text, 0, 2)), @r###"
+        - [ ] 0 foo
+        + [x] 0 bar
+        "###);
+    }
+
+    #[test]
+    fn test_rediff_section() {
+        let lines = [
+            changed_line(false, ChangeType::Removed, "foo\n"),
+            changed_line(true, ChangeType::Removed, "bar\n"),
+            changed_line(false, ChangeType::Removed, "qux\n"),
+            changed_line(true, ChangeType::Added, "FOO\n"),
+            changed_line(false, ChangeType::Added, "bar2\n"),
+            changed_line(true, ChangeType::Added, "qux2\n"),
+        ];
+        // Restore `foo` and `qux`, keep `bar2`, and add `baz`.
+        let edited_text = "foo\nbar2\nbaz\nqux\n";
+        assert_snapshot!(render_sections(&rediff_section(&lines, edited_text, 0, 2)), @r###"
+                foo
+        ---
+        - [x] 0 bar
+        + [ ] 0 bar2
+        + [x] 0 baz
+        ---
+                qux
+        "###);
+
+        // New lines are assigned to the given commit.
+        assert_snapshot!(render_sections(&rediff_section(&lines, edited_text, 1, 3)), @r###"
+                foo
+        ---
+        - [x] 0 bar
+        + [ ] 0 bar2
+        + [x] 1 baz
+        ---
+                qux
+        "###);
+    }
+
+    #[test]
+    fn test_rediff_duplicate_lines() {
+        // Each original line keeps its state at most once.
+        let lines = [
+            changed_line(true, ChangeType::Added, "foo\n"),
+            changed_line(false, ChangeType::Added, "foo\n"),
+        ];
+        assert_snapshot!(render_sections(&rediff_section(&lines, "foo\nfoo\nfoo\n", 0, 2)), @r###"
+        + [x] 0 foo
+        + [ ] 0 foo
+        + [x] 0 foo
+        "###);
+    }
+
+    #[test]
+    fn test_rediff_delete_all_lines() {
+        let lines = [
+            changed_line(true, ChangeType::Added, "foo\n"),
+            changed_line(true, ChangeType::Added, "bar\n"),
+        ];
+        assert!(rediff_section(&lines, "", 0, 2).is_empty());
+
+        let lines = [
+            changed_line(true, ChangeType::Removed, "foo\n"),
+            changed_line(true, ChangeType::Added, "bar\n"),
+        ];
+        assert_snapshot!(render_sections(&rediff_section(&lines, "", 0, 2)), @r###"- [x] 0 foo"###);
+    }
+
+    #[test]
+    fn test_rediff_trailing_newline() {
+        // The editor's trailing newline isn't added to a line which didn't
+        // have one.
+        let lines = [
+            changed_line(false, ChangeType::Removed, "foo"),
+            changed_line(true, ChangeType::Added, "bar"),
+        ];
+        assert_snapshot!(render_sections(&rediff_section(&lines, "baz\n", 0, 2)), @r###"
+        - [ ] 0 foo (no newline)
+        + [x] 0 baz (no newline)
+        "###);
+
+        // But it's kept if the original lines ended with one.
+        let lines = [
+            changed_line(false, ChangeType::Removed, "foo"),
+            changed_line(true, ChangeType::Added, "bar\n"),
+        ];
+        assert_snapshot!(render_sections(&rediff_section(&lines, "baz\n", 0, 2)), @r###"
+        - [ ] 0 foo (no newline)
+        + [x] 0 baz
+        "###);
+    }
+}
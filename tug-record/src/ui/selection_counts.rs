@@ -0,0 +1,107 @@
+//! Counts of the changes assigned to each commit, shown in the status bar.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{File, Section};
+
+/// The number of changed lines and files assigned to each commit.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SelectionCounts {
+    /// The total number of changed lines in all files.
+    pub num_lines: usize,
+
+    /// The total number of files with any changes.
+    pub num_files: usize,
+
+    /// The number of changed lines assigned to each commit, indexed by commit
+    /// index.
+    lines_by_commit: Vec<usize>,
+
+    /// The number of files with at least one change assigned to each commit,
+    /// indexed by commit index. File mode and binary changes count towards
+    /// these, even though they aren't lines.
+    files_by_commit: Vec<usize>,
+}
+
+impl SelectionCounts {
+    /// Count the changes in `files`, out of `num_commits` commits.
+    pub fn new(files: &[File], num_commits: usize) -> Self {
+        let mut result = Self {
+            num_lines: 0,
+            num_files: 0,
+            lines_by_commit: vec![0; num_commits],
+            files_by_commit: vec![0; num_commits],
+        };
+        let mut is_file_in_commit = vec![false; num_commits];
+        for file in files {
+            is_file_in_commit.fill(false);
+            for section in &file.sections {
+                if let Section::Changed { lines } = section {
+                    result.num_lines += lines.len();
+                    for line in lines {
+                        if let Some(num_lines) = result
+                            .lines_by_commit
+                            .get_mut(line.assigned_commit_idx(num_commits))
+                        {
+                            *num_lines += 1;
+                        }
+                    }
+                }
+                for commit_idx in section.assigned_commit_idxs(num_commits) {
+                    if let Some(is_in_commit) = is_file_in_commit.get_mut(commit_idx) {
+                        *is_in_commit = true;
+                    }
+                }
+            }
+
+            if file.sections.iter().any(Section::is_editable) {
+                result.num_files += 1;
+            }
+            for (num_files, is_in_commit) in
+                result.files_by_commit.iter_mut().zip(&is_file_in_commit)
+            {
+                if *is_in_commit {
+                    *num_files += 1;
+                }
+            }
+        }
+        result
+    }
+
+    /// The number of changed lines assigned to the commit at `commit_idx`.
+    pub fn num_lines_in_commit(&self, commit_idx: usize) -> usize {
+        self.lines_by_commit.get(commit_idx).copied().unwrap_or(0)
+    }
+
+    /// The number of files with at least one change assigned to the commit at
+    /// `commit_idx`.
+    pub fn num_files_in_commit(&self, commit_idx: usize) -> usize {
+        self.files_by_commit.get(commit_idx).copied().unwrap_or(0)
+    }
+}
+
+/// Caches the [`SelectionCounts`], so that they're only recounted after the
+/// selection has changed, rather than every time that the UI is drawn.
+#[derive(Debug, Default)]
+pub struct SelectionCountsCache {
+    cache: RefCell<Option<Rc<SelectionCounts>>>,
+}
+
+impl SelectionCountsCache {
+    /// The counts for `files`, counting them if necessary.
+    pub fn get(&self, files: &[File], num_commits: usize) -> Rc<SelectionCounts> {
+        if let Some(counts) = self.cache.borrow().as_ref() {
+            return Rc::clone(counts);
+        }
+        let counts = Rc::new(SelectionCounts::new(files, num_commits));
+        *self.cache.borrow_mut() = Some(Rc::clone(&counts));
+        counts
+    }
+
+    /// Discard the cached counts, such as after changes have been checked or
+    /// moved to another commit.
+    pub fn invalidate(&self) {
+        *self.cache.borrow_mut() = None;
+    }
+}
@@ -0,0 +1,594 @@
+//! Saving the selection while recording, so that it can be restored if the
+//! recorder is interrupted.
+//!
+//! Saving and loading sessions requires the `serde` feature.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "serde")]
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use crate::types::{ChangeType, File, FileMode, Section};
+use crate::ui::components::app::SelectionKey;
+
+/// The selection made in a recording session, along with enough of the
+/// changes to match it up with a slightly different diff later.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Session<'a> {
+    /// The hash of the changes being recorded, as computed by [`hash_files`].
+    /// If it matches the changes of a new session, then the saved selection
+    /// can be restored as-is, including the UI state.
+    pub state_hash: u64,
+
+    /// The selection state of the changes in each file.
+    pub files: Vec<SessionFile<'a>>,
+
+    /// The commit whose view was focused.
+    pub focused_commit_idx: usize,
+
+    /// The item which was selected in the UI.
+    pub selection_key: SelectionKey,
+
+    /// The items which were expanded in the UI.
+    pub expanded_items: Vec<SelectionKey>,
+}
+
+/// The selection state of the changes in a single file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SessionFile<'a> {
+    /// The path of the file.
+    pub path: Cow<'a, Path>,
+
+    /// The selection state of each change in the file, in order.
+    pub changes: Vec<SessionChange<'a>>,
+}
+
+/// The selection state of a single change.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SessionChange<'a> {
+    /// The change itself.
+    pub content: ChangeContent<'a>,
+
+    /// Whether the change was checked.
+    pub is_checked: bool,
+
+    /// The commit that the change was assigned to, if it was checked.
+    pub commit_idx: usize,
+}
+
+/// Identifies a change by its contents rather than its position, so that it
+/// can be found again even if other changes were added or removed.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ChangeContent<'a> {
+    /// A changed line.
+    Line {
+        change_type: ChangeType,
+        line: Cow<'a, str>,
+    },
+
+    /// A change of the file mode.
+    FileMode { mode: FileMode },
+
+    /// A change of binary contents.
+    Binary {
+        old_description: Option<Cow<'a, str>>,
+        new_description: Option<Cow<'a, str>>,
+    },
+}
+
+impl ChangeContent<'_> {
+    /// A copy of this value which borrows its contents.
+    fn borrowed(&self) -> ChangeContent<'_> {
+        match self {
+            ChangeContent::Line { change_type, line } => ChangeContent::Line {
+                change_type: *change_type,
+                line: Cow::Borrowed(line),
+            },
+            ChangeContent::FileMode { mode } => ChangeContent::FileMode { mode: *mode },
+            ChangeContent::Binary {
+                old_description,
+                new_description,
+            } => ChangeContent::Binary {
+                old_description: old_description.as_deref().map(Cow::Borrowed),
+                new_description: new_description.as_deref().map(Cow::Borrowed),
+            },
+        }
+    }
+}
+
+impl<'a> Session<'a> {
+    /// Record the selection state of the changes in `files`.
+    #[cfg(feature = "serde")]
+    pub fn new(
+        state_hash: u64,
+        files: &'a [File],
+        focused_commit_idx: usize,
+        selection_key: SelectionKey,
+        expanded_items: Vec<SelectionKey>,
+    ) -> Self {
+        let files = files
+            .iter()
+            .map(|file| {
+                let mut changes = Vec::new();
+                visit_changes(file, |content, is_checked, commit_idx| {
+                    changes.push(SessionChange {
+                        content,
+                        is_checked: *is_checked,
+                        commit_idx: *commit_idx,
+                    });
+                });
+                SessionFile {
+                    path: Cow::Borrowed(file.path.as_ref()),
+                    changes,
+                }
+            })
+            .collect();
+        Self {
+            state_hash,
+            files,
+            focused_commit_idx,
+            selection_key,
+            expanded_items,
+        }
+    }
+
+    /// Apply the saved selection to the changes in `files`, matching up the
+    /// changes of each file by their contents. Changes which don't appear in
+    /// the session are left as they are.
+    pub fn restore_selection(&self, files: &mut [File]) {
+        let saved_files: HashMap<&Path, &SessionFile> = self
+            .files
+            .iter()
+            .map(|file| (file.path.as_ref(), file))
+            .collect();
+        for file in files {
+            let saved_file = match saved_files.get(file.path.as_ref()) {
+                Some(saved_file) => saved_file,
+                None => continue,
+            };
+
+            // Identical changes are matched up in order.
+            let mut saved_states: HashMap<ChangeContent, VecDeque<(bool, usize)>> = HashMap::new();
+            for change in &saved_file.changes {
+                saved_states
+                    .entry(change.content.borrowed())
+                    .or_default()
+                    .push_back((change.is_checked, change.commit_idx));
+            }
+            visit_changes_mut(file, |content, is_checked, commit_idx| {
+                if let Some((saved_is_checked, saved_commit_idx)) = saved_states
+                    .get_mut(&content)
+                    .and_then(|states| states.pop_front())
+                {
+                    *is_checked = saved_is_checked;
+                    *commit_idx = saved_commit_idx;
+                }
+            });
+        }
+    }
+}
+
+/// Compute a hash of the changes in `files`, ignoring their selection state.
+/// The hash is stable across runs, so it can be saved to disk.
+#[cfg(feature = "serde")]
+pub fn hash_files(files: &[File]) -> u64 {
+    let mut hasher = Fnv1aHasher::default();
+    files.len().hash(&mut hasher);
+    for file in files {
+        let File {
+            old_path,
+            path,
+            file_mode,
+            sections,
+        } = file;
+        old_path.hash(&mut hasher);
+        path.hash(&mut hasher);
+        file_mode.hash(&mut hasher);
+        sections.len().hash(&mut hasher);
+        for section in sections {
+            match section {
+                Section::Unchanged { lines } => {
+                    0_u8.hash(&mut hasher);
+                    lines.hash(&mut hasher);
+                }
+                Section::Changed { lines } => {
+                    1_u8.hash(&mut hasher);
+                    lines.len().hash(&mut hasher);
+                    for line in lines {
+                        line.change_type.hash(&mut hasher);
+                        line.line.hash(&mut hasher);
+                    }
+                }
+                Section::FileMode {
+                    is_checked: _,
+                    commit_idx: _,
+                    mode,
+                } => {
+                    2_u8.hash(&mut hasher);
+                    mode.hash(&mut hasher);
+                }
+                Section::Binary {
+                    is_checked: _,
+                    commit_idx: _,
+                    old_description,
+                    new_description,
+                } => {
+                    3_u8.hash(&mut hasher);
+                    old_description.hash(&mut hasher);
+                    new_description.hash(&mut hasher);
+                }
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// The 64-bit FNV-1a hash function. Unlike the standard library's default
+/// hasher, its output is guaranteed not to change between releases.
+#[cfg(feature = "serde")]
+struct Fnv1aHasher(u64);
+
+#[cfg(feature = "serde")]
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn visit_changes<'a>(file: &'a File, mut f: impl FnMut(ChangeContent<'a>, &'a bool, &'a usize)) {
+    for section in &file.sections {
+        match section {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    let content = ChangeContent::Line {
+                        change_type: line.change_type,
+                        line: Cow::Borrowed(&line.line),
+                    };
+                    f(content, &line.is_checked, &line.commit_idx);
+                }
+            }
+            Section::FileMode {
+                is_checked,
+                commit_idx,
+                mode,
+            } => f(
+                ChangeContent::FileMode { mode: *mode },
+                is_checked,
+                commit_idx,
+            ),
+            Section::Binary {
+                is_checked,
+                commit_idx,
+                old_description,
+                new_description,
+            } => {
+                let content = ChangeContent::Binary {
+                    old_description: old_description.as_deref().map(Cow::Borrowed),
+                    new_description: new_description.as_deref().map(Cow::Borrowed),
+                };
+                f(content, is_checked, commit_idx);
+            }
+        }
+    }
+}
+
+fn visit_changes_mut<'a>(
+    file: &'a mut File,
+    mut f: impl FnMut(ChangeContent<'a>, &'a mut bool, &'a mut usize),
+) {
+    for section in &mut file.sections {
+        match section {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    let content = ChangeContent::Line {
+                        change_type: line.change_type,
+                        line: Cow::Borrowed(&line.line),
+                    };
+                    f(content, &mut line.is_checked, &mut line.commit_idx);
+                }
+            }
+            Section::FileMode {
+                is_checked,
+                commit_idx,
+                mode,
+            } => f(
+                ChangeContent::FileMode { mode: *mode },
+                is_checked,
+                commit_idx,
+            ),
+            Section::Binary {
+                is_checked,
+                commit_idx,
+                old_description,
+                new_description,
+            } => {
+                let content = ChangeContent::Binary {
+                    old_description: old_description.as_deref().map(Cow::Borrowed),
+                    new_description: new_description.as_deref().map(Cow::Borrowed),
+                };
+                f(content, is_checked, commit_idx);
+            }
+        }
+    }
+}
+
+/// The state of the prompt to restore a saved session, shown when the
+/// recorder starts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionPrompt {
+    /// The saved session.
+    pub session: Session<'static>,
+
+    /// Whether the session was saved for exactly the same changes, in which
+    /// case the UI state is restored along with the selection.
+    pub is_exact: bool,
+
+    /// The number of changes which would be checked after restoring.
+    pub num_checked: usize,
+
+    /// The total number of changes.
+    pub num_changes: usize,
+}
+
+/// The file where the selection is saved while recording.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub struct SessionStore {
+    path: std::path::PathBuf,
+    state_hash: u64,
+
+    /// The contents last written to the file, to avoid rewriting it when
+    /// nothing changed.
+    last_saved: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl SessionStore {
+    /// Constructor. `state_hash` identifies the changes being recorded, as
+    /// computed by [`hash_files`].
+    pub fn new(path: std::path::PathBuf, state_hash: u64) -> Self {
+        Self {
+            path,
+            state_hash,
+            last_saved: None,
+        }
+    }
+
+    /// The hash of the changes being recorded.
+    pub fn state_hash(&self) -> u64 {
+        self.state_hash
+    }
+
+    /// Read the saved session, if there is one. A session which can't be
+    /// parsed, such as one saved by an incompatible version, is ignored.
+    pub fn load(&self) -> Result<Option<Session<'static>>, crate::RecordError> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(crate::RecordError::ReadFile(err)),
+        };
+        match serde_json::from_str(&contents) {
+            Ok(session) => Ok(Some(session)),
+            Err(err) => {
+                tracing::warn!(?err, path = ?self.path, "ignoring unreadable session file");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Save `session`, replacing the previously saved one. The file is
+    /// replaced atomically, so that it's never left half-written.
+    pub fn save(&mut self, session: &Session) -> Result<(), crate::RecordError> {
+        let contents = serde_json::to_string(session).map_err(crate::RecordError::SerializeJson)?;
+        if self.last_saved.as_ref() == Some(&contents) {
+            return Ok(());
+        }
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, &contents).map_err(crate::RecordError::WriteFile)?;
+        std::fs::rename(&temp_path, &self.path).map_err(crate::RecordError::WriteFile)?;
+        self.last_saved = Some(contents);
+        Ok(())
+    }
+
+    /// Delete the saved session, such as once the selection has been
+    /// accepted.
+    pub fn remove(&self) -> Result<(), crate::RecordError> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(crate::RecordError::WriteFile(err)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+    use crate::types::SectionChangedLine;
+
+    /// A file whose single changed section has the given lines, written as
+    /// `x1 +line`: the selection state, the commit index and the change.
+    fn file(path: &'static str, lines: &[&'static str]) -> File<'static> {
+        let lines = lines
+            .iter()
+            .map(|line| {
+                let (is_checked, line) = line.split_at(1);
+                let (commit_idx, line) = line.split_at(1);
+                let (change_type, line) = line[1..].split_at(1);
+                SectionChangedLine {
+                    is_checked: is_checked == "x",
+                    commit_idx: commit_idx.parse().unwrap(),
+                    change_type: match change_type {
+                        "+" => ChangeType::Added,
+                        "-" => ChangeType::Removed,
+                        _ => panic!("unknown change type: {change_type}"),
+                    },
+                    line: Cow::Owned(format!("{line}\n")),
+                }
+            })
+            .collect();
+        File {
+            old_path: None,
+            path: Cow::Borrowed(Path::new(path)),
+            file_mode: FileMode::FILE_DEFAULT,
+            sections: vec![
+                Section::Unchanged {
+                    lines: vec![Cow::Borrowed("context\n")],
+                },
+                Section::Changed { lines },
+            ],
+        }
+    }
+
+    /// The selection state of each change in `files`, in the format taken by
+    /// [`file`].
+    fn render_selection(files: &[File]) -> String {
+        let mut rendered = String::new();
+        for file in files {
+            rendered.push_str(&format!("{}:\n", file.path.display()));
+            visit_changes(file, |content, is_checked, commit_idx| {
+                let is_checked = if *is_checked { 'x' } else { ' ' };
+                let change = match content {
+                    ChangeContent::Line { change_type, line } => {
+                        let change_type = match change_type {
+                            ChangeType::Added => '+',
+                            ChangeType::Removed => '-',
+                        };
+                        format!("{change_type}{}", line.trim_end())
+                    }
+                    ChangeContent::FileMode { mode } => format!("mode {mode:?}"),
+                    ChangeContent::Binary { .. } => "binary".to_owned(),
+                };
+                rendered.push_str(&format!("  {is_checked}{commit_idx} {change}\n"));
+            });
+        }
+        rendered
+    }
+
+    fn session<'a>(files: &'a [File]) -> Session<'a> {
+        Session::new(hash_files(files), files, 0, SelectionKey::None, Vec::new())
+    }
+
+    #[test]
+    fn test_restore_selection_unchanged() {
+        let saved_files = vec![file("foo", &["x0 -a", " 0 +b", "x1 +c"])];
+        let session = session(&saved_files);
+        let mut files = vec![file("foo", &[" 0 -a", " 0 +b", " 0 +c"])];
+        assert_eq!(session.state_hash, hash_files(&files));
+        session.restore_selection(&mut files);
+        assert_eq!(render_selection(&files), render_selection(&saved_files));
+    }
+
+    #[test]
+    fn test_restore_selection_remaps_by_content() {
+        let saved_files = vec![file("foo", &["x0 +a", " 0 +b", "x1 +c"])];
+        let session = session(&saved_files);
+
+        // A line was inserted before the saved lines and another was removed,
+        // so the remaining lines are at different positions.
+        let mut files = vec![file("foo", &[" 0 +new", " 0 +a", " 0 +c"])];
+        assert_ne!(session.state_hash, hash_files(&files));
+        session.restore_selection(&mut files);
+        assert_snapshot!(render_selection(&files), @r###"
+        foo:
+           0 +new
+          x0 +a
+          x1 +c
+        "###);
+    }
+
+    #[test]
+    fn test_restore_selection_matches_change_type() {
+        let saved_files = vec![file("foo", &["x0 -a", " 0 +a"])];
+        let session = session(&saved_files);
+        let mut files = vec![file("foo", &[" 0 +a", " 0 -a"])];
+        session.restore_selection(&mut files);
+        assert_snapshot!(render_selection(&files), @r###"
+        foo:
+           0 +a
+          x0 -a
+        "###);
+    }
+
+    #[test]
+    fn test_restore_selection_duplicate_lines() {
+        let saved_files = vec![file("foo", &["x0 +a", " 0 +b", "x1 +a", " 0 +a"])];
+        let session = session(&saved_files);
+
+        // Identical lines are matched up in order, and extra copies are left
+        // as they are.
+        let mut files = vec![file("foo", &[" 0 +a", " 0 +a", "x0 +a", "x0 +a"])];
+        session.restore_selection(&mut files);
+        assert_snapshot!(render_selection(&files), @r###"
+        foo:
+          x0 +a
+          x1 +a
+           0 +a
+          x0 +a
+        "###);
+    }
+
+    #[test]
+    fn test_restore_selection_by_path() {
+        let saved_files = vec![file("foo", &["x0 +a"]), file("bar", &["x1 +b"])];
+        let session = session(&saved_files);
+
+        // Files are matched up by path rather than position, and files which
+        // aren't in the session are left as they are.
+        let mut files = vec![
+            file("baz", &["x0 +a", " 0 +b"]),
+            file("bar", &[" 0 +a", " 0 +b"]),
+            file("foo", &[" 0 +a", " 0 +b"]),
+        ];
+        session.restore_selection(&mut files);
+        assert_snapshot!(render_selection(&files), @r###"
+        baz:
+          x0 +a
+           0 +b
+        bar:
+           0 +a
+          x1 +b
+        foo:
+          x0 +a
+           0 +b
+        "###);
+    }
+
+    #[test]
+    fn test_hash_files_ignores_selection() {
+        let files = vec![file("foo", &[" 0 +a", " 0 -b"])];
+        let selected_files = vec![file("foo", &["x1 +a", "x0 -b"])];
+        assert_eq!(hash_files(&files), hash_files(&selected_files));
+
+        let renamed_files = vec![file("bar", &[" 0 +a", " 0 -b"])];
+        assert_ne!(hash_files(&files), hash_files(&renamed_files));
+        let edited_files = vec![file("foo", &[" 0 +a", " 0 +b"])];
+        assert_ne!(hash_files(&files), hash_files(&edited_files));
+    }
+}
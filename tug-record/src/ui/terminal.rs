@@ -1,5 +1,6 @@
 use std::{fmt::Write, io, panic};
 
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, EnterAlternateScreen,
     LeaveAlternateScreen,
@@ -67,11 +68,15 @@ pub fn install_panic_hook() {
     }));
 }
 
-pub fn set_up_crossterm() -> Result<(), RecordError> {
+pub fn set_up_crossterm(mouse_capture: bool) -> Result<(), RecordError> {
     if !is_raw_mode_enabled().map_err(RecordError::SetUpTerminal)? {
         crossterm::execute!(io::stdout(), EnterAlternateScreen)
             .map_err(RecordError::SetUpTerminal)?;
         enable_raw_mode().map_err(RecordError::SetUpTerminal)?;
+        if mouse_capture {
+            crossterm::execute!(io::stdout(), EnableMouseCapture)
+                .map_err(RecordError::SetUpTerminal)?;
+        }
     }
     Ok(())
 }
@@ -79,7 +84,8 @@ pub fn set_up_crossterm() -> Result<(), RecordError> {
 pub fn clean_up_crossterm() -> Result<(), RecordError> {
     if is_raw_mode_enabled().map_err(RecordError::CleanUpTerminal)? {
         disable_raw_mode().map_err(RecordError::CleanUpTerminal)?;
-        crossterm::execute!(io::stdout(), LeaveAlternateScreen)
+        // Disabling mouse capture is harmless if it was never enabled.
+        crossterm::execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen)
             .map_err(RecordError::CleanUpTerminal)?;
     }
     Ok(())
@@ -0,0 +1,308 @@
+//! Colors and text styles used to render the UI.
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::consts::ENV_VAR_NO_COLOR;
+
+/// The styles used to render each kind of UI element.
+///
+/// Several presets are provided: [`Theme::dark`] (the default),
+/// [`Theme::light`], [`Theme::high_contrast`] and [`Theme::monochrome`].
+/// Embedders can also construct their own theme, typically by modifying one
+/// of the presets, and pass it to [`crate::Recorder::with_theme`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Theme {
+    /// The path in a file header.
+    pub file_path: Style,
+
+    /// The name of a directory in the directory tree.
+    pub dir_path: Style,
+
+    /// The line separating the directory tree sidebar from the list of files.
+    pub sidebar_border: Style,
+
+    /// The header of a section of changed lines, such as `Section 1/3`.
+    pub section_header: Style,
+
+    /// The description of a file mode change or binary change.
+    pub section_description: Style,
+
+    /// An added line.
+    pub added_line: Style,
+
+    /// A removed line.
+    pub removed_line: Style,
+
+    /// The parts of an added line which differ from the corresponding removed
+    /// line.
+    pub added_word: Style,
+
+    /// The parts of a removed line which differ from the corresponding added
+    /// line.
+    pub removed_word: Style,
+
+    /// An unchanged context line.
+    pub unchanged_line: Style,
+
+    /// The ellipsis indicating that unchanged lines were omitted.
+    pub ellipsis: Style,
+
+    /// The replacement text for a control character, such as `→` for a tab.
+    pub control_character: Style,
+
+    /// The marker at the start of each extra row of a wrapped line.
+    pub wrap_marker: Style,
+
+    /// Text matching the active search query.
+    pub search_match: Style,
+
+    /// The checkbox of an item which isn't selected.
+    pub unchecked_box: Style,
+
+    /// The checkbox of an item which is selected.
+    pub checked_box: Style,
+
+    /// The checkbox of an item which is partially selected.
+    pub partial_box: Style,
+
+    /// The checkbox of an item which can't be changed.
+    pub read_only_box: Style,
+
+    /// The expand/collapse icon.
+    pub expand_box: Style,
+
+    /// Applied on top of the row of the focused item.
+    pub selection_highlight: Style,
+
+    /// The preview of the body of a commit message, below its subject line.
+    pub commit_message_body: Style,
+
+    /// The length of the subject line of a commit message being edited, when
+    /// it's longer than recommended.
+    pub subject_length_warning: Style,
+
+    /// The length of the subject line of a commit message being edited, when
+    /// it's long enough to be truncated by other tools.
+    pub subject_length_error: Style,
+
+    /// The status bar at the bottom of the screen.
+    pub status_bar: Style,
+
+    /// The keys suggested in the status bar. Drawn on top of
+    /// [`Theme::status_bar`].
+    pub status_bar_key: Style,
+
+    /// The marker of a file, section or line with a review comment.
+    pub comment_marker: Style,
+
+    /// The name of the built-in `syntect` theme used to highlight source
+    /// code, such as `base16-ocean.dark`, or `None` to disable syntax
+    /// highlighting. Ignored unless compiled with the `syntax-highlighting`
+    /// feature.
+    pub syntax_theme: Option<String>,
+
+    /// An added line when syntax highlighting is enabled. The token colors
+    /// are drawn on top of this style, so it should typically only set a
+    /// background color.
+    pub syntax_added_line: Style,
+
+    /// A removed line when syntax highlighting is enabled. See
+    /// [`Theme::syntax_added_line`].
+    pub syntax_removed_line: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// A theme for terminals with a dark background.
+    pub fn dark() -> Self {
+        Self {
+            file_path: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            dir_path: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            sidebar_border: Style::new().fg(Color::DarkGray),
+            section_header: Style::new().fg(Color::LightMagenta),
+            section_description: Style::new().fg(Color::Magenta),
+            added_line: Style::new().fg(Color::Green),
+            removed_line: Style::new().fg(Color::Red),
+            added_word: Style::new().fg(Color::Black).bg(Color::Green),
+            removed_word: Style::new().fg(Color::Black).bg(Color::Red),
+            unchanged_line: Style::new().fg(Color::Gray).add_modifier(Modifier::DIM),
+            ellipsis: Style::new().add_modifier(Modifier::DIM),
+            control_character: Style::new().fg(Color::DarkGray),
+            wrap_marker: Style::new().fg(Color::DarkGray),
+            search_match: Style::new().fg(Color::Black).bg(Color::Yellow),
+            unchecked_box: Style::new()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+            checked_box: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            partial_box: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            read_only_box: Style::new().fg(Color::Gray).add_modifier(Modifier::DIM),
+            expand_box: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            selection_highlight: Style::new().bg(Color::Rgb(38, 38, 38)),
+            commit_message_body: Style::new().fg(Color::Gray).add_modifier(Modifier::DIM),
+            subject_length_warning: Style::new().fg(Color::Yellow),
+            subject_length_error: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            status_bar: Style::new().fg(Color::Gray).bg(Color::Rgb(48, 48, 48)),
+            status_bar_key: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            comment_marker: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            syntax_theme: Some("base16-ocean.dark".to_owned()),
+            syntax_added_line: Style::new().bg(Color::Rgb(0, 48, 0)),
+            syntax_removed_line: Style::new().bg(Color::Rgb(64, 0, 0)),
+        }
+    }
+
+    /// A theme for terminals with a light background.
+    pub fn light() -> Self {
+        Self {
+            file_path: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            dir_path: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            sidebar_border: Style::new().fg(Color::Gray),
+            section_header: Style::new().fg(Color::Blue),
+            section_description: Style::new().fg(Color::Magenta),
+            added_line: Style::new().fg(Color::Rgb(0, 128, 0)),
+            removed_line: Style::new().fg(Color::Rgb(176, 0, 0)),
+            added_word: Style::new().fg(Color::White).bg(Color::Rgb(0, 128, 0)),
+            removed_word: Style::new().fg(Color::White).bg(Color::Rgb(176, 0, 0)),
+            unchanged_line: Style::new().fg(Color::DarkGray),
+            ellipsis: Style::new().fg(Color::DarkGray),
+            control_character: Style::new().fg(Color::Gray),
+            wrap_marker: Style::new().fg(Color::Gray),
+            search_match: Style::new().fg(Color::Black).bg(Color::LightYellow),
+            unchecked_box: Style::new().fg(Color::Gray).add_modifier(Modifier::BOLD),
+            checked_box: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            partial_box: Style::new()
+                .fg(Color::Rgb(175, 95, 0))
+                .add_modifier(Modifier::BOLD),
+            read_only_box: Style::new().fg(Color::Gray),
+            expand_box: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            selection_highlight: Style::new().bg(Color::Rgb(218, 218, 218)),
+            commit_message_body: Style::new().fg(Color::DarkGray),
+            subject_length_warning: Style::new().fg(Color::Rgb(175, 95, 0)),
+            subject_length_error: Style::new()
+                .fg(Color::Rgb(176, 0, 0))
+                .add_modifier(Modifier::BOLD),
+            status_bar: Style::new().fg(Color::Black).bg(Color::Rgb(208, 208, 208)),
+            status_bar_key: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            comment_marker: Style::new()
+                .fg(Color::Rgb(175, 95, 0))
+                .add_modifier(Modifier::BOLD),
+            syntax_theme: Some("InspiredGitHub".to_owned()),
+            syntax_added_line: Style::new().bg(Color::Rgb(220, 255, 220)),
+            syntax_removed_line: Style::new().bg(Color::Rgb(255, 220, 220)),
+        }
+    }
+
+    /// A theme using only bright colors and bold text, which doesn't rely on
+    /// telling red and green apart.
+    pub fn high_contrast() -> Self {
+        Self {
+            file_path: Style::new()
+                .fg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            dir_path: Style::new()
+                .fg(Color::LightMagenta)
+                .add_modifier(Modifier::BOLD),
+            sidebar_border: Style::new().fg(Color::White),
+            section_header: Style::new()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            section_description: Style::new().fg(Color::LightCyan),
+            added_line: Style::new().fg(Color::LightBlue),
+            removed_line: Style::new().fg(Color::LightYellow),
+            added_word: Style::new()
+                .fg(Color::Black)
+                .bg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+            removed_word: Style::new()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            unchanged_line: Style::new().fg(Color::White),
+            ellipsis: Style::new().fg(Color::White),
+            control_character: Style::new().fg(Color::LightMagenta),
+            wrap_marker: Style::new().fg(Color::LightMagenta),
+            search_match: Style::new()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            unchecked_box: Style::new().fg(Color::White).add_modifier(Modifier::BOLD),
+            checked_box: Style::new()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            partial_box: Style::new()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            read_only_box: Style::new().fg(Color::White),
+            expand_box: Style::new()
+                .fg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD),
+            selection_highlight: Style::new().bg(Color::DarkGray),
+            commit_message_body: Style::new().fg(Color::White),
+            subject_length_warning: Style::new().fg(Color::LightYellow),
+            subject_length_error: Style::new()
+                .fg(Color::LightMagenta)
+                .add_modifier(Modifier::BOLD),
+            status_bar: Style::new().fg(Color::Black).bg(Color::White),
+            status_bar_key: Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            comment_marker: Style::new()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            // Token colors would make the added and removed colors harder to
+            // tell apart.
+            syntax_theme: None,
+            syntax_added_line: Style::new().fg(Color::LightBlue),
+            syntax_removed_line: Style::new().fg(Color::LightYellow),
+        }
+    }
+
+    /// A theme which doesn't use any colors, only text modifiers such as bold
+    /// and reversed text. This theme is used when the `NO_COLOR` environment
+    /// variable is set.
+    pub fn monochrome() -> Self {
+        Self {
+            file_path: Style::new().add_modifier(Modifier::BOLD),
+            dir_path: Style::new().add_modifier(Modifier::BOLD),
+            sidebar_border: Style::new().add_modifier(Modifier::DIM),
+            section_header: Style::new().add_modifier(Modifier::BOLD),
+            section_description: Style::new(),
+            added_line: Style::new(),
+            removed_line: Style::new(),
+            added_word: Style::new().add_modifier(Modifier::UNDERLINED),
+            removed_word: Style::new().add_modifier(Modifier::UNDERLINED),
+            unchanged_line: Style::new().add_modifier(Modifier::DIM),
+            ellipsis: Style::new().add_modifier(Modifier::DIM),
+            control_character: Style::new().add_modifier(Modifier::DIM),
+            wrap_marker: Style::new().add_modifier(Modifier::DIM),
+            search_match: Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            unchecked_box: Style::new(),
+            checked_box: Style::new().add_modifier(Modifier::BOLD),
+            partial_box: Style::new().add_modifier(Modifier::BOLD),
+            read_only_box: Style::new().add_modifier(Modifier::DIM),
+            expand_box: Style::new().add_modifier(Modifier::BOLD),
+            selection_highlight: Style::new().add_modifier(Modifier::REVERSED),
+            commit_message_body: Style::new().add_modifier(Modifier::DIM),
+            subject_length_warning: Style::new().add_modifier(Modifier::BOLD),
+            subject_length_error: Style::new().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            status_bar: Style::new().add_modifier(Modifier::REVERSED),
+            status_bar_key: Style::new().add_modifier(Modifier::BOLD),
+            comment_marker: Style::new().add_modifier(Modifier::BOLD),
+            syntax_theme: None,
+            syntax_added_line: Style::new(),
+            syntax_removed_line: Style::new(),
+        }
+    }
+
+    /// Replace this theme with [`Theme::monochrome`] if the user has asked
+    /// for no colors by setting the `NO_COLOR` environment variable to a
+    /// non-empty value (see <https://no-color.org>).
+    pub(crate) fn honor_no_color(self) -> Self {
+        match std::env::var_os(ENV_VAR_NO_COLOR) {
+            Some(value) if !value.is_empty() => Self::monochrome(),
+            Some(_) | None => self,
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Word-level diffs between the removed and added lines of a section, used to
+//! emphasize the parts of a line which actually changed.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::{ChangeType, SectionChangedLine};
+
+/// Don't diff pairs of lines whose token counts multiply to more than this,
+/// since the diff takes quadratic time and space.
+const MAX_DIFF_CELLS: usize = 250_000;
+
+/// Don't emphasize anything if less than this percentage of the two lines is
+/// in common, since the lines are probably unrelated, and emphasizing almost
+/// everything would just be noise.
+const MIN_SIMILARITY_PERCENT: usize = 50;
+
+/// Byte ranges within a line, in order.
+type LineRanges = Vec<Range<usize>>;
+
+/// The emphasized byte ranges of each line of a changed section.
+#[derive(Debug, Default)]
+pub struct SectionWordDiff {
+    /// Indexed by line index. Lines without a counterpart have no ranges.
+    lines: Vec<LineRanges>,
+}
+
+impl SectionWordDiff {
+    /// Pair up the removed and added lines of a section (see [`pair_lines`])
+    /// and diff each pair.
+    pub fn new(lines: &[SectionChangedLine]) -> Self {
+        let mut result = Self {
+            lines: vec![Vec::new(); lines.len()],
+        };
+        for (removed_idx, added_idx) in pair_lines(lines) {
+            if let (Some(removed_idx), Some(added_idx)) = (removed_idx, added_idx) {
+                if let Some((removed_ranges, added_ranges)) =
+                    diff_lines(&lines[removed_idx].line, &lines[added_idx].line)
+                {
+                    result.lines[removed_idx] = removed_ranges;
+                    result.lines[added_idx] = added_ranges;
+                }
+            }
+        }
+        result
+    }
+
+    /// The emphasized byte ranges of the given line, in order.
+    pub fn line(&self, line_idx: usize) -> &[Range<usize>] {
+        self.lines
+            .get(line_idx)
+            .map(|ranges| ranges.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Pair up the removed and added lines of a changed section, returning the
+/// line indices of each pair in order.
+///
+/// Lines are paired positionally within each block of removed lines followed
+/// by a block of added lines: the first removed line with the first added
+/// line, and so on. If one block is longer than the other, its remaining lines
+/// are paired with `None`.
+pub fn pair_lines(lines: &[SectionChangedLine]) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut result = Vec::new();
+    let mut line_idx = 0;
+    while line_idx < lines.len() {
+        let removed_start = line_idx;
+        while line_idx < lines.len() && lines[line_idx].change_type == ChangeType::Removed {
+            line_idx += 1;
+        }
+        let added_start = line_idx;
+        while line_idx < lines.len() && lines[line_idx].change_type == ChangeType::Added {
+            line_idx += 1;
+        }
+        let num_removed = added_start - removed_start;
+        let num_added = line_idx - added_start;
+        for offset in 0..num_removed.max(num_added) {
+            let removed_idx = Some(removed_start + offset).filter(|_| offset < num_removed);
+            let added_idx = Some(added_start + offset).filter(|_| offset < num_added);
+            result.push((removed_idx, added_idx));
+        }
+    }
+    result
+}
+
+/// Computes and caches the word diffs for each changed section. Sections are
+/// only diffed the first time that they're requested, which is typically when
+/// they're first expanded.
+#[derive(Debug, Default)]
+pub struct WordDiffCache {
+    /// Keyed by file index and section index.
+    cache: RefCell<HashMap<(usize, usize), Rc<SectionWordDiff>>>,
+}
+
+impl WordDiffCache {
+    /// The word diff for the given section, computing it if necessary.
+    pub fn section_word_diff(
+        &self,
+        file_idx: usize,
+        section_idx: usize,
+        lines: &[SectionChangedLine],
+    ) -> Rc<SectionWordDiff> {
+        let key = (file_idx, section_idx);
+        if let Some(word_diff) = self.cache.borrow().get(&key) {
+            return Rc::clone(word_diff);
+        }
+        let word_diff = Rc::new(SectionWordDiff::new(lines));
+        self.cache.borrow_mut().insert(key, Rc::clone(&word_diff));
+        word_diff
+    }
+
+    /// Discard the cached word diffs for all sections of the file at
+    /// `file_idx`, such as after its lines have been edited.
+    pub fn invalidate_file(&self, file_idx: usize) {
+        self.cache
+            .borrow_mut()
+            .retain(|(cached_file_idx, _section_idx), _| *cached_file_idx != file_idx);
+    }
+}
+
+/// Split a line into tokens: runs of word characters, runs of whitespace, and
+/// individual punctuation characters. Returns the byte range of each token.
+fn tokenize(line: &str) -> Vec<Range<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum CharClass {
+        Word,
+        Whitespace,
+        Other,
+    }
+    let char_class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            CharClass::Word
+        } else if c.is_whitespace() {
+            CharClass::Whitespace
+        } else {
+            CharClass::Other
+        }
+    };
+
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut prev_class = None;
+    for (idx, c) in line.char_indices() {
+        let class = char_class(c);
+        let end = idx + c.len_utf8();
+        match tokens.last_mut() {
+            Some(token) if prev_class == Some(class) && class != CharClass::Other => {
+                token.end = end;
+            }
+            _ => tokens.push(idx..end),
+        }
+        prev_class = Some(class);
+    }
+    tokens
+}
+
+/// Diff two lines token by token. Returns the byte ranges of the tokens which
+/// differ in each line, or `None` if the lines shouldn't be emphasized.
+fn diff_lines(old: &str, new: &str) -> Option<(LineRanges, LineRanges)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len().saturating_mul(new_tokens.len()) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // Compute the longest common subsequence of tokens. `lcs_lens[i][j]` is
+    // the length of the LCS of `old_tokens[i..]` and `new_tokens[j..]`.
+    let old_token = |i: usize| &old[old_tokens[i].clone()];
+    let new_token = |j: usize| &new[new_tokens[j].clone()];
+    let num_cols = new_tokens.len() + 1;
+    let mut lcs_lens = vec![0_usize; (old_tokens.len() + 1) * num_cols];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lcs_lens[i * num_cols + j] = if old_token(i) == new_token(j) {
+                lcs_lens[(i + 1) * num_cols + j + 1] + 1
+            } else {
+                lcs_lens[(i + 1) * num_cols + j].max(lcs_lens[i * num_cols + j + 1])
+            };
+        }
+    }
+
+    let mut old_ranges = Vec::new();
+    let mut new_ranges = Vec::new();
+    let mut common_len = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < old_tokens.len() || j < new_tokens.len() {
+        if i < old_tokens.len() && j < new_tokens.len() && old_token(i) == new_token(j) {
+            common_len += old_tokens[i].len();
+            i += 1;
+            j += 1;
+        } else if j == new_tokens.len()
+            || (i < old_tokens.len()
+                && lcs_lens[(i + 1) * num_cols + j] >= lcs_lens[i * num_cols + j + 1])
+        {
+            push_range(&mut old_ranges, old_tokens[i].clone());
+            i += 1;
+        } else {
+            push_range(&mut new_ranges, new_tokens[j].clone());
+            j += 1;
+        }
+    }
+
+    let total_len = old.len() + new.len();
+    if 2 * common_len * 100 < MIN_SIMILARITY_PERCENT * total_len {
+        return None;
+    }
+    Some((old_ranges, new_ranges))
+}
+
+/// Push `range` onto `ranges`, merging it with the last range if they're
+/// adjacent.
+fn push_range(ranges: &mut LineRanges, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn changed_line(change_type: ChangeType, line: &'static str) -> SectionChangedLine<'static> {
+        SectionChangedLine {
+            is_checked: false,
+            commit_idx: 0,
+            change_type,
+            line: Cow::Borrowed(line),
+        }
+    }
+
+    fn emphasized<'a>(line: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|range| &line[range.clone()]).collect()
+    }
+
+    /// The emphasized parts of each line, or `None` if nothing is emphasized.
+    fn diff_words(old: &str, new: &str) -> Option<(Vec<String>, Vec<String>)> {
+        let (old_ranges, new_ranges) = diff_lines(old, new)?;
+        let to_strings = |line, ranges| {
+            emphasized(line, ranges)
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect()
+        };
+        Some((to_strings(old, &old_ranges), to_strings(new, &new_ranges)))
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let line = "foo_bar  (x)!=\n";
+        assert_eq!(
+            emphasized(line, &tokenize(line)),
+            vec!["foo_bar", "  ", "(", "x", ")", "!", "=", "\n"]
+        );
+        assert!(tokenize("").is_empty());
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(
+            diff_words("let x = 1;\n", "let x = 2;\n"),
+            Some((vec!["1".to_owned()], vec!["2".to_owned()]))
+        );
+        assert_eq!(
+            diff_words("let x = 1;\n", "let x = 1;\n"),
+            Some((vec![], vec![]))
+        );
+        // Adjacent changed tokens are merged into a single range.
+        assert_eq!(
+            diff_words("call(a, b)\n", "call(a, b, c)\n"),
+            Some((vec![], vec![", c".to_owned()]))
+        );
+        // Ranges are byte ranges of whole characters.
+        assert_eq!(
+            diff_words("héllo wörld\n", "héllo world\n"),
+            Some((vec!["wörld".to_owned()], vec!["world".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_not_emphasized() {
+        // Too little in common.
+        assert_eq!(diff_words("foo bar baz\n", "qux quux corge\n"), None);
+        assert_eq!(diff_words("", "foo\n"), None);
+
+        // Too many tokens, even though the lines are identical.
+        let line = "x ".repeat(300);
+        assert_eq!(diff_words(&line, &line), None);
+    }
+
+    #[test]
+    fn test_pair_lines() {
+        let lines = [
+            changed_line(ChangeType::Added, "a\n"),
+            changed_line(ChangeType::Removed, "b\n"),
+            changed_line(ChangeType::Removed, "c\n"),
+            changed_line(ChangeType::Added, "d\n"),
+            changed_line(ChangeType::Removed, "e\n"),
+            changed_line(ChangeType::Added, "f\n"),
+            changed_line(ChangeType::Added, "g\n"),
+        ];
+        assert_eq!(
+            pair_lines(&lines),
+            vec![
+                (None, Some(0)),
+                (Some(1), Some(3)),
+                (Some(2), None),
+                (Some(4), Some(5)),
+                (None, Some(6)),
+            ]
+        );
+        assert!(pair_lines(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_section_word_diff() {
+        let lines = [
+            changed_line(ChangeType::Removed, "let x = 1;\n"),
+            changed_line(ChangeType::Removed, "unrelated\n"),
+            changed_line(ChangeType::Added, "let x = 2;\n"),
+            changed_line(ChangeType::Added, "something else\n"),
+            changed_line(ChangeType::Added, "extra\n"),
+        ];
+        let word_diff = SectionWordDiff::new(&lines);
+        let emphasized_lines: Vec<Vec<&str>> = lines
+            .iter()
+            .enumerate()
+            .map(|(line_idx, line)| emphasized(&line.line, word_diff.line(line_idx)))
+            .collect();
+        assert_eq!(
+            emphasized_lines,
+            vec![vec!["1"], vec![], vec!["2"], vec![], vec![]]
+        );
+        assert!(word_diff.line(lines.len()).is_empty());
+    }
+
+    #[test]
+    fn test_word_diff_cache() {
+        let lines = [
+            changed_line(ChangeType::Removed, "let x = 1;\n"),
+            changed_line(ChangeType::Added, "let x = 2;\n"),
+        ];
+        let cache = WordDiffCache::default();
+        let word_diff = cache.section_word_diff(0, 1, &lines);
+        assert_eq!(emphasized(&lines[1].line, word_diff.line(1)), vec!["2"]);
+
+        // Cached sections aren't diffed again until they're invalidated.
+        let edited_lines = [
+            changed_line(ChangeType::Removed, "let x = 1;\n"),
+            changed_line(ChangeType::Added, "let y = 2;\n"),
+        ];
+        assert!(Rc::ptr_eq(
+            &cache.section_word_diff(0, 1, &edited_lines),
+            &word_diff
+        ));
+        cache.invalidate_file(1);
+        assert!(Rc::ptr_eq(
+            &cache.section_word_diff(0, 1, &edited_lines),
+            &word_diff
+        ));
+        cache.invalidate_file(0);
+        let word_diff = cache.section_word_diff(0, 1, &edited_lines);
+        assert_eq!(
+            emphasized(&edited_lines[1].line, word_diff.line(1)),
+            vec!["y", "2"]
+        );
+    }
+}
@@ -0,0 +1,702 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use insta::assert_snapshot;
+
+use tug_record::helpers::TestingInput;
+use tug_record::{
+    ChangeType, Event, File, FileMode, FileTreeMode, KeyChord, Keymap, KeymapError, RecordError,
+    RecordState, Recorder, Section, SectionChangedLine, TestingScreenshot,
+};
+
+fn changed_line(
+    is_checked: bool,
+    change_type: ChangeType,
+    line: &'static str,
+) -> SectionChangedLine<'static> {
+    SectionChangedLine {
+        is_checked,
+        commit_idx: 0,
+        change_type,
+        line: Cow::Borrowed(line),
+    }
+}
+
+/// The events produced by typing `text`, such as into a prompt.
+fn type_text(text: &str) -> Vec<Event> {
+    text.chars()
+        .map(|c| {
+            let key = match c {
+                ' ' => "space".to_owned(),
+                c => c.to_string(),
+            };
+            Event::from(KeyChord::parse(&key).unwrap())
+        })
+        .collect()
+}
+
+/// Whether each changed line of `file` is checked, in order.
+fn line_checked_states(file: &File) -> Vec<bool> {
+    file.sections
+        .iter()
+        .flat_map(|section| match section {
+            Section::Changed { lines } => lines.iter().map(|line| line.is_checked).collect(),
+            Section::Unchanged { .. } | Section::FileMode { .. } | Section::Binary { .. } => {
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+fn example_contents() -> RecordState<'static> {
+    RecordState {
+        is_read_only: false,
+        commits: Default::default(),
+        comments: Default::default(),
+        files: vec![
+            File {
+                old_path: None,
+                path: Cow::Borrowed(Path::new("foo/bar")),
+                file_mode: FileMode::FILE_DEFAULT,
+                sections: vec![
+                    Section::Unchanged {
+                        lines: std::iter::repeat(Cow::Borrowed("this is some text\n"))
+                            .take(20)
+                            .collect(),
+                    },
+                    Section::Changed {
+                        lines: vec![
+                            changed_line(true, ChangeType::Removed, "before text 1\n"),
+                            changed_line(true, ChangeType::Removed, "before text 2\n"),
+                            changed_line(true, ChangeType::Added, "after text 1\n"),
+                            changed_line(false, ChangeType::Added, "after text 2\n"),
+                        ],
+                    },
+                    Section::Unchanged {
+                        lines: vec![Cow::Borrowed("this is some trailing text\n")],
+                    },
+                ],
+            },
+            File {
+                old_path: None,
+                path: Cow::Borrowed(Path::new("baz")),
+                file_mode: FileMode::FILE_DEFAULT,
+                sections: vec![
+                    Section::Unchanged {
+                        lines: vec![
+                            Cow::Borrowed("Some leading text 1\n"),
+                            Cow::Borrowed("Some leading text 2\n"),
+                        ],
+                    },
+                    Section::Changed {
+                        lines: vec![
+                            changed_line(true, ChangeType::Removed, "before text 1\n"),
+                            changed_line(true, ChangeType::Removed, "before text 2\n"),
+                            changed_line(true, ChangeType::Added, "after text 1\n"),
+                            changed_line(true, ChangeType::Added, "after text 2\n"),
+                        ],
+                    },
+                    Section::Unchanged {
+                        lines: vec![Cow::Borrowed("this is some trailing text\n")],
+                    },
+                ],
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_undo_redo() -> Result<(), RecordError> {
+    let toggled = TestingScreenshot::default();
+    let undone = TestingScreenshot::default();
+    let mut input = TestingInput::new(
+        80,
+        4,
+        [
+            Event::ToggleItem,
+            Event::FocusNext,
+            Event::ToggleItem,
+            toggled.event(),
+            Event::Undo,
+            Event::Undo,
+            undone.event(),
+            // Nothing more to undo.
+            Event::Undo,
+            Event::Redo,
+            Event::QuitAccept,
+        ],
+    );
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    assert_snapshot!(toggled, @r###"
+    "▶ [ ] foo/bar                                                                   "
+    "▶ [ ] baz                                                                       "
+    "                                                                                "
+    " baz │ 0/8 lines, 0/2 files │ commit 1/2 │ unified       space toggle  f expand "
+    "###);
+    // Undoing moves the focus back to where the change was made.
+    assert_snapshot!(undone, @r###"
+    "▶ [~] foo/bar                                                                   "
+    "▶ [*] baz                                                                       "
+    "                                                                                "
+    " foo/bar │ 7/8 lines, 2/2 files │ commit 1/2 │ unified   space toggle  f expand "
+    "###);
+    let expected_files = {
+        let mut files = example_contents().files;
+        files[0].set_checked(false);
+        files
+    };
+    assert_eq!(state.files, expected_files);
+    Ok(())
+}
+
+#[test]
+fn test_search_wraps_around() -> Result<(), RecordError> {
+    let first_match = TestingScreenshot::default();
+    let mut events = vec![Event::Search];
+    events.extend(type_text("text 1"));
+    events.extend([
+        Event::from(KeyChord::parse("enter").unwrap()),
+        first_match.event(),
+        Event::SearchNext,
+        Event::SearchNext,
+        Event::SearchNext,
+        Event::SearchNext,
+        // Past the last match, so the search continues from the top.
+        Event::SearchNext,
+        Event::ToggleItem,
+        // Before the first match, so the search continues from the bottom.
+        Event::SearchPrev,
+        Event::ToggleItem,
+        Event::QuitAccept,
+    ]);
+    let mut input = TestingInput::new(80, 12, events);
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    // The collapsed file was expanded to show the match.
+    assert_snapshot!(first_match, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "        ⋮                                                                       "
+    "       17 this is some text⏎                                                    "
+    "       18 this is some text⏎                                                    "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/1                                                             "
+    "    [*] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                        "
+    "    [*] + after text 1⏎                                                         "
+    "    [ ] + after text 2⏎                                                         "
+    " foo/bar section 1/1 │ 7/8 lines, 2/2 files │ commit 1/2 │ unified              "
+    "###);
+
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![false, true, true, false]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[1]),
+        vec![true, true, false, true]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_custom_keymap() -> Result<(), Box<dyn std::error::Error>> {
+    let mut keymap = Keymap::default();
+    keymap.unbind_event(&Event::ToggleItem);
+    keymap.bind("y", Event::ToggleItem)?;
+
+    let events = vec![
+        // No longer bound to anything.
+        Event::from(KeyChord::parse("space")?),
+        Event::from(KeyChord::parse("y")?),
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 4, events);
+    let state = Recorder::new(example_contents(), &mut input)
+        .with_keymap(keymap.clone())?
+        .run()?;
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![false, false, false, false]
+    );
+
+    // `j` is already bound to `FocusNext`.
+    keymap.bind("j", Event::ToggleItem)?;
+    let mut input = TestingInput::new(80, 4, Vec::new());
+    let result = Recorder::new(example_contents(), &mut input).with_keymap(keymap);
+    assert!(matches!(result, Err(KeymapError::Conflicts(_))));
+    Ok(())
+}
+
+#[test]
+fn test_edit_added_lines() -> Result<(), RecordError> {
+    let edited = TestingScreenshot::default();
+    let events = vec![
+        Event::ExpandItem,
+        Event::FocusNext,
+        Event::EditAddedLines,
+        edited.event(),
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 12, events);
+    input
+        .edited_added_lines
+        .push_back(Some("before text 2\nafter text 1\nnew text\n".to_owned()));
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    // `before text 2` is no longer removed, `after text 1` keeps its
+    // selection, and `new text` is selected like a newly checked line.
+    assert_snapshot!(edited, @r###"
+    "▼ [*] foo/bar                                                                   "
+    "        ⋮                                                                       "
+    "       17 this is some text⏎                                                    "
+    "       18 this is some text⏎                                                    "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [*] Section 1/2                                                             "
+    "    [*] - before text 1⏎                                                        "
+    "       22 before text 2⏎                                                        "
+    "  ▼ [*] Section 2/2                                                             "
+    "    [*] + after text 1⏎                                                         "
+    " foo/bar section 1/2 │ 7/7 lines, 2/2 files │ commit 1/2 │ unified              "
+    "###);
+    assert_eq!(line_checked_states(&state.files[0]), vec![true, true, true]);
+    Ok(())
+}
+
+#[test]
+fn test_cancel_edit_added_lines() -> Result<(), RecordError> {
+    let cancelled = TestingScreenshot::default();
+    let events = vec![
+        Event::ExpandItem,
+        Event::FocusNext,
+        Event::EditAddedLines,
+        cancelled.event(),
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 12, events);
+    input.edited_added_lines.push_back(None);
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    // The recorder keeps running, and the section is left as it was.
+    assert_snapshot!(cancelled, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "        ⋮                                                                       "
+    "       17 this is some text⏎                                                    "
+    "       18 this is some text⏎                                                    "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/1                                                             "
+    "    [*] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                        "
+    "    [*] + after text 1⏎                                                         "
+    "    [ ] + after text 2⏎                                                         "
+    " foo/bar section 1/1 │ 7/8 lines, 2/2 files │ commit 1/2 │ unified              "
+    "###);
+    assert_eq!(state.files, example_contents().files);
+    Ok(())
+}
+
+#[test]
+fn test_undo_after_edit_added_lines() -> Result<(), RecordError> {
+    let undone = TestingScreenshot::default();
+    let events = vec![
+        Event::FocusNext,
+        Event::ToggleItem,
+        Event::FocusPrev,
+        Event::ExpandItem,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::ToggleItem,
+        Event::EditAddedLines,
+        Event::Undo,
+        Event::Undo,
+        undone.event(),
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 12, events);
+    input
+        .edited_added_lines
+        .push_back(Some("after text 1\nafter text 2\nnew text\n".to_owned()));
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    // The line toggled in the edited file can no longer be undone, but the
+    // toggle of the other file can.
+    assert_snapshot!(undone, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/1                                                             "
+    "    [ ] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                        "
+    "    [*] + after text 1⏎                                                         "
+    "    [ ] + after text 2⏎                                                         "
+    "    [*] + new text⏎                                                             "
+    "       23 this is some trailing text⏎                                           "
+    "▶ [*] baz                                                                       "
+    " baz │ 7/9 lines, 2/2 files │ commit 1/2 │ unified       space toggle  f expand "
+    "###);
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![false, true, true, false, true]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[1]),
+        vec![true, true, true, true]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_range_across_collapsed_file() -> Result<(), RecordError> {
+    let mut contents = example_contents();
+    contents.files[1].set_checked(false);
+    let events = vec![
+        Event::FocusNext,
+        Event::ExpandItem,
+        Event::FocusPrev,
+        Event::ToggleVisualMode,
+        // The header and section of `baz` are expanded, so they only extend
+        // the range down to its first line.
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::CheckRange,
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 20, events);
+    let state = Recorder::new(contents, &mut input).run()?;
+    // `foo/bar` is collapsed, so all of it is in the range.
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![true, true, true, true]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[1]),
+        vec![true, false, false, false]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uncheck_range_ending_on_collapsed_file() -> Result<(), RecordError> {
+    let events = vec![
+        Event::ExpandItem,
+        // The range starts on `after text 1` and ends on the header of `baz`.
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::ToggleVisualMode,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::UncheckRange,
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 20, events);
+    let state = Recorder::new(example_contents(), &mut input).run()?;
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![true, true, false, false]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[1]),
+        vec![false, false, false, false]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_flip_range_across_collapsed_file() -> Result<(), RecordError> {
+    let mut contents = example_contents();
+    let mut qux = contents.files[1].clone();
+    qux.path = Cow::Borrowed(Path::new("qux"));
+    qux.set_checked(false);
+    contents.files.push(qux);
+    let events = vec![
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::ExpandItem,
+        Event::FocusPrev,
+        Event::FocusPrev,
+        Event::ExpandItem,
+        // The range starts on `after text 2` of `foo/bar`, spans the collapsed
+        // `baz`, and ends on the first line of `qux`.
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::ToggleVisualMode,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::FocusNext,
+        Event::ToggleItem,
+        Event::QuitAccept,
+    ];
+    let mut input = TestingInput::new(80, 20, events);
+    let state = Recorder::new(contents, &mut input).run()?;
+    assert_eq!(
+        line_checked_states(&state.files[0]),
+        vec![true, true, true, true]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[1]),
+        vec![false, false, false, false]
+    );
+    assert_eq!(
+        line_checked_states(&state.files[2]),
+        vec![true, false, false, false]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_scroll_past_end_with_sidebar() -> Result<(), RecordError> {
+    let scrolled = TestingScreenshot::default();
+    let mut events = vec![Event::ExpandAll];
+    events.extend(std::iter::repeat_n(Event::ScrollDown, 200));
+    events.extend(std::iter::repeat_n(Event::ScrollUp, 15));
+    events.extend([scrolled.event(), Event::QuitAccept]);
+    let mut input = TestingInput::new(80, 12, events);
+    Recorder::new(example_contents(), &mut input)
+        .with_file_tree_mode(FileTreeMode::Sidebar)
+        .run()?;
+    // The sidebar doesn't count towards the height of the UI, so scrolling
+    // stops at the last line of the files.
+    assert_snapshot!(scrolled, @r###"
+    "▼ [~] foo/         │▼ [~] foo/bar                                               "
+    "    [~] bar        │  ▼ [~] Section 1/1                                         "
+    "  [*] baz          │    [*] - before text 1⏎                                    "
+    "                   │    [*] - before text 2⏎                                    "
+    "                   │    [*] + after text 1⏎                                     "
+    "                   │    [ ] + after text 2⏎                                     "
+    "                   │       23 this is some trailing text⏎                       "
+    "                   │▼ [*] baz                                                   "
+    "                   │        1 Some leading text 1⏎                              "
+    "                   │        2 Some leading text 2⏎                              "
+    "                   │  ▼ [*] Section 1/1                                         "
+    " foo/bar │ 7/8 lines, 2/2 files │ commit 1/2 │ unified     space toggle  f fold "
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_comment_survives_commit_move() -> Result<(), RecordError> {
+    let moved = TestingScreenshot::default();
+    // The context lines before the section can be selected in review mode.
+    let mut events = vec![Event::ExpandItem];
+    events.extend(std::iter::repeat(Event::FocusNext).take(7));
+    events.push(Event::EditComment);
+    events.extend(type_text("keep this"));
+    events.extend([
+        Event::from(KeyChord::parse("ctrl-s").unwrap()),
+        Event::MoveItemToNextCommit,
+        Event::FocusNextCommit,
+        moved.event(),
+        Event::QuitAccept,
+    ]);
+    let mut input = TestingInput::new(80, 12, events);
+    let state = Recorder::new(example_contents(), &mut input)
+        .with_review_mode(true)
+        .run()?;
+    // The comment is attached to the line rather than to its commit, so it's
+    // still shown after the line is moved.
+    assert_snapshot!(moved, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "        ⋮                                                                       "
+    "       17 this is some text⏎                                                    "
+    "       18 this is some text⏎                                                    "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/1                                                             "
+    "    [ ] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                       ●"
+    "    [ ] + after text 1⏎                                                         "
+    "    [*] + after text 2⏎                                                         "
+    " foo/bar section 1/1 │ 2/8 lines, 1/2 files │ commit 2/2 │ 1 comment │ unified  "
+    "###);
+    assert_eq!(
+        state.files[0].sections[1].assigned_commit_idxs(2),
+        vec![0, 1, 0, 1]
+    );
+    assert_snapshot!(state.review_notes_markdown(), @r###"
+    # Review notes
+
+    ## `foo/bar`
+
+    ### Line 22 (old)
+
+    ```diff
+    -before text 2
+    ```
+
+    keep this
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_comment_survives_edit() -> Result<(), RecordError> {
+    let mut contents = example_contents();
+    contents.files[0].sections.extend([
+        Section::Changed {
+            lines: vec![changed_line(false, ChangeType::Added, "later text\n")],
+        },
+        Section::Unchanged {
+            lines: vec![Cow::Borrowed("this is some final text\n")],
+        },
+    ]);
+    let edited = TestingScreenshot::default();
+    let mut events = vec![Event::ExpandItem];
+    events.extend(std::iter::repeat(Event::FocusNext).take(12));
+    events.push(Event::EditComment);
+    events.extend(type_text("keep this"));
+    events.extend([
+        Event::from(KeyChord::parse("ctrl-s").unwrap()),
+        Event::FocusPrev,
+        Event::FocusPrev,
+        Event::FocusPrev,
+        Event::EditAddedLines,
+        Event::FocusNextSameKind,
+        edited.event(),
+        Event::QuitAccept,
+    ]);
+    let mut input = TestingInput::new(80, 12, events);
+    input
+        .edited_added_lines
+        .push_back(Some("after text 1\nafter text 2\nnew text\n".to_owned()));
+    let state = Recorder::new(contents, &mut input)
+        .with_review_mode(true)
+        .run()?;
+    // The comment on the line after the edited section moves down along with
+    // it.
+    assert_snapshot!(edited, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/2                                                             "
+    "    [*] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                        "
+    "    [*] + after text 1⏎                                                         "
+    "    [ ] + after text 2⏎                                                         "
+    "    [*] + new text⏎                                                             "
+    "       23 this is some trailing text⏎                                           "
+    "  ▼ [ ] Section 2/2                                                             "
+    "    [ ] + later text⏎                                                          ●"
+    " foo/bar section 2/2 │ 8/10 lines, 2/2 files │ commit 1/2 │ 1 comment │ unified "
+    "###);
+    assert_snapshot!(state.review_notes_markdown(), @r###"
+    # Review notes
+
+    ## `foo/bar`
+
+    ### Line 25 (new)
+
+    ```diff
+    +later text
+    ```
+
+    keep this
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_comment_on_context_line() -> Result<(), RecordError> {
+    let mut contents = example_contents();
+    if let Section::Changed { lines } = &mut contents.files[0].sections[1] {
+        lines.push(changed_line(false, ChangeType::Added, "after text 3\n"));
+    }
+    let commented = TestingScreenshot::default();
+    let mut events = vec![Event::ExpandItem];
+    events.extend(std::iter::repeat(Event::FocusNext).take(11));
+    events.push(Event::EditComment);
+    events.extend(type_text("why keep this?"));
+    events.extend([
+        Event::from(KeyChord::parse("ctrl-s").unwrap()),
+        commented.event(),
+        Event::QuitAccept,
+    ]);
+    let mut input = TestingInput::new(80, 12, events);
+    let state = Recorder::new(contents, &mut input)
+        .with_review_mode(true)
+        .run()?;
+    assert_snapshot!(commented, @r###"
+    "▼ [~] foo/bar                                                                   "
+    "       18 this is some text⏎                                                    "
+    "       19 this is some text⏎                                                    "
+    "       20 this is some text⏎                                                    "
+    "  ▼ [~] Section 1/1                                                             "
+    "    [*] - before text 1⏎                                                        "
+    "    [*] - before text 2⏎                                                        "
+    "    [*] + after text 1⏎                                                         "
+    "    [ ] + after text 2⏎                                                         "
+    "    [ ] + after text 3⏎                                                         "
+    "       23 this is some trailing text⏎                                          ●"
+    " foo/bar │ 7/9 lines, 2/2 files │ commit 1/2 │ 1 comment │ unified    m comment "
+    "###);
+    // The unchanged line is in both versions of the file, at different line
+    // numbers.
+    assert_snapshot!(state.review_notes_markdown(), @r###"
+    # Review notes
+
+    ## `foo/bar`
+
+    ### Line 23 (old), 24 (new)
+
+    ```diff
+     this is some trailing text
+    ```
+
+    why keep this?
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_search_after_wrapping_lines() -> Result<(), RecordError> {
+    let files = (0..40)
+        .map(|file_idx| File {
+            old_path: None,
+            path: Cow::Owned(format!("file{file_idx:02}").into()),
+            file_mode: FileMode::FILE_DEFAULT,
+            sections: vec![Section::Changed {
+                lines: vec![SectionChangedLine {
+                    is_checked: false,
+                    commit_idx: 0,
+                    change_type: ChangeType::Added,
+                    line: Cow::Owned(format!("a line which wraps in file {file_idx:02}\n")),
+                }],
+            }],
+        })
+        .collect();
+    let found = TestingScreenshot::default();
+    let mut events = vec![Event::ExpandAll, Event::ToggleLineWrap, Event::Search];
+    events.extend(type_text("file 39"));
+    events.extend([
+        Event::from(KeyChord::parse("enter").unwrap()),
+        found.event(),
+        Event::QuitAccept,
+    ]);
+    let mut input = TestingInput::new(30, 10, events);
+    let state = RecordState {
+        is_read_only: false,
+        commits: Vec::new(),
+        comments: Vec::new(),
+        files,
+    };
+    Recorder::new(state, &mut input).run()?;
+    // Only the files near the viewport were drawn after wrapping the lines,
+    // so the sizes of the others must have been discarded for the match to be
+    // scrolled into view.
+    assert_snapshot!(found, @r###"
+    "▼ [ ] file37                  "
+    "▼ [ ] file38                  "
+    "  ▼ [ ] Section 1/1           "
+    "    [ ] + a line which wraps i"
+    "        ↪ n file 38⏎          "
+    "▼ [ ] file39                  "
+    "  ▼ [ ] Section 1/1           "
+    "    [ ] + a line which wraps i"
+    "        ↪ n file 39⏎          "
+    " file39 section 1/1 │ 0/40 li…"
+    "###);
+    Ok(())
+}